@@ -0,0 +1,333 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! An in-process beacon API that serves recorded responses from fixture files,
+//! so tests can run against a fixed chain instead of a live node.
+//!
+//! Fixtures are laid out by route below `eth/v1/beacon/`:
+//!
+//! ```text
+//! light_client/updates/<period>.json      a single element of the `updates` response
+//! light_client/bootstrap/<block_root>.json
+//! light_client/finality_update.json
+//! headers/<block_id>.json                 also answers `blocks/<block_id>/root`
+//! states/head/fork.json
+//! genesis.json
+//! ```
+//!
+//! `eth/v1/events` streams whatever is passed to [`MockBeacon::emit`].
+
+use axum::{
+    extract::{Query, State},
+    http::{StatusCode, Uri},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use futures::stream::{self, Stream};
+use preprocessor::{BeaconSource, Encoding, PreprocessorError, RootFn};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{sync::broadcast, task::JoinHandle};
+
+const PREFIX: &str = "eth/v1/beacon/";
+const UPDATES: &str = "light_client/updates";
+
+/// A running mock beacon node, shut down on drop.
+pub struct MockBeacon {
+    addr: SocketAddr,
+    events: broadcast::Sender<(String, Value)>,
+    handle: JoinHandle<()>,
+}
+
+struct MockState {
+    dir: PathBuf,
+    events: broadcast::Sender<(String, Value)>,
+}
+
+impl MockBeacon {
+    /// Serves the fixtures in `dir` on a random local port.
+    pub async fn start(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let (events, _) = broadcast::channel(16);
+        let app = Router::new()
+            .route("/eth/v1/events", get(stream_events))
+            .fallback(serve)
+            .with_state(Arc::new(MockState {
+                dir: dir.into(),
+                events: events.clone(),
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let handle = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        Ok(Self {
+            addr,
+            events,
+            handle,
+        })
+    }
+
+    /// Base url to construct a beacon client with.
+    pub fn url(&self) -> Url {
+        Url::parse(&format!("http://{}", self.addr)).unwrap()
+    }
+
+    /// Sends an event on `topic` to all connected subscribers.
+    pub fn emit(&self, topic: &str, data: Value) {
+        let _ = self.events.send((topic.to_string(), data));
+    }
+
+    /// Number of open event streams.
+    pub fn subscribers(&self) -> usize {
+        self.events.receiver_count()
+    }
+}
+
+impl Drop for MockBeacon {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+fn not_found(route: &str) -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "code": 404, "message": format!("no fixture for {route}") })),
+    )
+        .into_response()
+}
+
+fn read_fixture(path: &Path) -> Option<Value> {
+    serde_json::from_slice(&std::fs::read(path).ok()?).ok()
+}
+
+async fn stream_events(
+    State(state): State<Arc<MockState>>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let topics: Vec<String> = query
+        .get("topics")
+        .map(|topics| topics.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    let events = stream::unfold(state.events.subscribe(), move |mut rx| {
+        let topics = topics.clone();
+        async move {
+            loop {
+                match rx.recv().await {
+                    Ok((topic, data)) if topics.contains(&topic) => {
+                        let event = Event::default().event(topic).data(data.to_string());
+                        return Some((Ok(event), rx));
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+async fn serve(
+    State(state): State<Arc<MockState>>,
+    Query(query): Query<HashMap<String, u64>>,
+    uri: Uri,
+) -> Response {
+    let dir = &state.dir;
+    let Some(route) = uri.path().trim_start_matches('/').strip_prefix(PREFIX) else {
+        return not_found(uri.path());
+    };
+
+    if route == UPDATES {
+        let start = query.get("start_period").copied().unwrap_or_default();
+        let count = query.get("count").copied().unwrap_or(1);
+        // like a real node, answer with the available prefix of the requested range
+        let updates: Vec<Value> = (start..start + count)
+            .map_while(|period| read_fixture(&dir.join(UPDATES).join(format!("{period}.json"))))
+            .collect();
+        if updates.is_empty() {
+            return not_found(route);
+        }
+        return Json(updates).into_response();
+    }
+
+    if let Some(fixture) = read_fixture(&fixture_path(dir, route)) {
+        return Json(fixture).into_response();
+    }
+    // block roots are part of the recorded headers
+    if let Some(id) = route
+        .strip_prefix("blocks/")
+        .and_then(|id| id.strip_suffix("/root"))
+    {
+        if let Some(header) = read_fixture(&dir.join("headers").join(format!("{id}.json"))) {
+            return Json(json!({ "data": { "root": header["data"]["root"] } })).into_response();
+        }
+    }
+    not_found(route)
+}
+
+fn fixture_path(dir: &Path, route: &str) -> PathBuf {
+    dir.join(format!("{route}.json"))
+}
+
+/// A [`BeaconSource`] that stores every response of `inner` as a fixture for [`MockBeacon`].
+///
+/// Fixtures are JSON only, so SSZ requests are refused and callers fall back to JSON.
+pub struct Recorder<B: BeaconSource> {
+    inner: B,
+    dir: PathBuf,
+}
+
+impl<B: BeaconSource> Recorder<B> {
+    pub fn new(inner: B, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    fn store(&self, route: &str, query: &[(&str, u64)], body: &[u8]) -> std::io::Result<()> {
+        let Some(route) = route.strip_prefix(PREFIX) else {
+            return Ok(());
+        };
+        let value: Value = serde_json::from_slice(body)?;
+        if route == UPDATES {
+            let start = query
+                .iter()
+                .find(|(k, _)| *k == "start_period")
+                .map(|(_, v)| *v)
+                .unwrap_or_default();
+            let updates = value.as_array().cloned().unwrap_or_default();
+            for (period, update) in (start..).zip(updates) {
+                let path = self.dir.join(UPDATES).join(format!("{period}.json"));
+                write_fixture(&path, &update)?;
+            }
+            return Ok(());
+        }
+        write_fixture(&fixture_path(&self.dir, route), &value)
+    }
+}
+
+fn write_fixture(path: &Path, value: &Value) -> std::io::Result<()> {
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(path, serde_json::to_vec_pretty(value)?)
+}
+
+impl<B: BeaconSource> BeaconSource for Recorder<B> {
+    fn name(&self) -> String {
+        format!("recording {}", self.inner.name())
+    }
+
+    async fn fetch(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> preprocessor::Result<Vec<u8>> {
+        if encoding != Encoding::Json {
+            return Err(PreprocessorError::UnsupportedEncoding {
+                route: route.to_string(),
+                encoding: encoding.to_string(),
+            });
+        }
+        let body = self.inner.fetch(route, query, encoding, root).await?;
+        if let Err(e) = self.store(route, query, &body) {
+            eprintln!("Failed to record {}: {}", route, e);
+        }
+        Ok(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_serve_fixtures() {
+        let dir = tempfile::tempdir().unwrap();
+        write_fixture(
+            &dir.path().join("light_client/updates/841.json"),
+            &json!({ "version": "deneb", "data": {} }),
+        )
+        .unwrap();
+        write_fixture(
+            &dir.path().join("headers/6897664.json"),
+            &json!({ "data": { "root": "0x01" } }),
+        )
+        .unwrap();
+        let mock = MockBeacon::start(dir.path()).await.unwrap();
+        let get = |route: &str| {
+            let url = mock.url().join(route).unwrap();
+            async move { reqwest::get(url).await.unwrap() }
+        };
+
+        let updates: Value = get("eth/v1/beacon/light_client/updates?start_period=841&count=2")
+            .await
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(updates.as_array().unwrap().len(), 1);
+
+        let root: Value = get("eth/v1/beacon/blocks/6897664/root")
+            .await
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(root["data"]["root"], "0x01");
+
+        let response = get("eth/v1/beacon/light_client/updates?start_period=842&count=1").await;
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_reconnects() {
+        use preprocessor::events::{subscribe, Backoff, LightClientEvent, FINALITY_UPDATE_TOPIC};
+        use std::time::Duration;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mock = MockBeacon::start(dir.path()).await.unwrap();
+        // nothing listens on the first endpoint, so the subscription has to move on
+        let unreachable = Url::parse("http://127.0.0.1:1").unwrap();
+        let backoff = Backoff {
+            initial: Duration::from_millis(10),
+            max: Duration::from_millis(100),
+        };
+        let mut events = subscribe(vec![unreachable, mock.url()], backoff);
+        while mock.subscribers() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        mock.emit(
+            FINALITY_UPDATE_TOPIC,
+            json!({
+                "attested_header": { "beacon": { "slot": "6897700" } },
+                "finalized_header": { "beacon": { "slot": "6897632" } },
+                "signature_slot": "6897701",
+            }),
+        );
+        let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            event,
+            LightClientEvent::FinalityUpdate {
+                attested_slot: 6897700,
+                finalized_slot: 6897632,
+                signature_slot: 6897701,
+            }
+        );
+    }
+}
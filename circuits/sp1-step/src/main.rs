@@ -1,5 +1,6 @@
 #![no_main]
-use committee_iso::utils::{merkleize_keys, uint64_to_le_256, verify_merkle_proof};
+use committee_iso::constants::{EXECUTION_PAYLOAD_INDEX, FINALIZED_HEADER_INDEX};
+use committee_iso::utils::{merkleize_keys, track, uint64_to_le_256, verify_merkle_proof};
 use step_iso::{
     types::{SyncStepArgs, SyncStepCircuitInput, SyncStepCircuitOutput},
     verify_aggregate_signature,
@@ -11,25 +12,28 @@ sp1_zkvm::entrypoint!(main);
 pub fn main() {
     let inputs: SyncStepCircuitInput = borsh::from_slice(&sp1_zkvm::io::read_vec()).unwrap();
     let args: SyncStepArgs = inputs.args;
-    verify_merkle_proof(
-        args.execution_payload_branch.to_vec(),
-        args.execution_payload_root.clone(),
-        &args.finalized_header.body_root.to_vec(),
-        9,
-    );
-    let finalized_header_root: Vec<u8> = merkleize_keys(vec![
-        uint64_to_le_256(args.finalized_header.slot.parse::<u64>().unwrap()),
-        uint64_to_le_256(args.finalized_header.proposer_index.parse::<u64>().unwrap()),
-        args.finalized_header.parent_root.to_vec(),
-        args.finalized_header.state_root.to_vec(),
-        args.finalized_header.body_root.to_vec(),
-    ]);
-    verify_merkle_proof(
-        args.finality_branch.clone(),
-        finalized_header_root.clone(),
-        &args.attested_header.state_root.to_vec(),
-        105,
-    );
+    let finalized_header_root: Vec<u8> = track("merkle proofs", || {
+        verify_merkle_proof(
+            args.execution_payload_branch.to_vec(),
+            args.execution_payload_root.clone(),
+            &args.finalized_header.body_root.to_vec(),
+            EXECUTION_PAYLOAD_INDEX,
+        );
+        let finalized_header_root: Vec<u8> = merkleize_keys(vec![
+            uint64_to_le_256(args.finalized_header.slot.parse::<u64>().unwrap()),
+            uint64_to_le_256(args.finalized_header.proposer_index.parse::<u64>().unwrap()),
+            args.finalized_header.parent_root.to_vec(),
+            args.finalized_header.state_root.to_vec(),
+            args.finalized_header.body_root.to_vec(),
+        ]);
+        verify_merkle_proof(
+            args.finality_branch.clone(),
+            finalized_header_root.clone(),
+            &args.attested_header.state_root.to_vec(),
+            FINALIZED_HEADER_INDEX,
+        );
+        finalized_header_root
+    });
     verify_aggregate_signature(args.clone(), inputs.commitment);
     #[cfg(not(feature = "wrapped"))]
     {
@@ -0,0 +1,169 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+use crate::{
+    network::{forks, genesis_validators_root},
+    Slot, Spec,
+};
+use sha2::{Digest, Sha256};
+
+pub type Version = [u8; 4];
+pub type DomainType = [u8; 4];
+pub type Domain = [u8; 32];
+pub type ForkDigest = [u8; 4];
+
+pub const DOMAIN_SYNC_COMMITTEE: DomainType = [7, 0, 0, 0];
+
+/// A scheduled network upgrade, active from `epoch` onwards.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Fork {
+    pub name: &'static str,
+    pub version: Version,
+    pub epoch: u64,
+}
+
+impl Fork {
+    pub const fn new(name: &'static str, version: Version, epoch: u64) -> Self {
+        Self {
+            name,
+            version,
+            epoch,
+        }
+    }
+}
+
+/// Returns the fork active at `epoch` according to the schedule of `S`,
+/// or the one configured with [`crate::configure_network`].
+pub fn fork_at_epoch<S: Spec>(epoch: u64) -> &'static Fork {
+    forks::<S>()
+        .iter()
+        .rev()
+        .find(|fork| fork.epoch <= epoch)
+        .expect("fork schedule must start at epoch 0")
+}
+
+/// Returns the fork active at `slot` according to the schedule of `S`.
+pub fn fork_at_slot<S: Spec>(slot: u64) -> &'static Fork {
+    fork_at_epoch::<S>(Slot(slot).epoch::<S>().0)
+}
+
+pub fn compute_fork_data_root(version: Version, genesis_validators_root: [u8; 32]) -> [u8; 32] {
+    let mut padded_version = [0u8; 32];
+    padded_version[..4].copy_from_slice(&version);
+    let mut hasher = Sha256::new();
+    hasher.update(padded_version);
+    hasher.update(genesis_validators_root);
+    hasher.finalize().into()
+}
+
+pub fn compute_fork_digest(version: Version, genesis_validators_root: [u8; 32]) -> ForkDigest {
+    compute_fork_data_root(version, genesis_validators_root)[..4]
+        .try_into()
+        .unwrap()
+}
+
+/// Returns the fork of `S` whose digest is `digest`, as found in the context bytes of SSZ responses.
+pub fn fork_by_digest<S: Spec>(digest: ForkDigest) -> Option<&'static Fork> {
+    let genesis_validators_root = genesis_validators_root::<S>();
+    forks::<S>()
+        .iter()
+        .find(|fork| compute_fork_digest(fork.version, genesis_validators_root) == digest)
+}
+
+pub fn compute_domain(
+    domain_type: DomainType,
+    version: Version,
+    genesis_validators_root: [u8; 32],
+) -> Domain {
+    let fork_data_root = compute_fork_data_root(version, genesis_validators_root);
+    let mut domain = [0u8; 32];
+    domain[..4].copy_from_slice(&domain_type);
+    domain[4..].copy_from_slice(&fork_data_root[..28]);
+    domain
+}
+
+/// Sync committee signing domain for a signature included at `signature_slot`.
+///
+/// As in the consensus spec, the fork is taken at `max(signature_slot, 1) - 1`,
+/// so an update signed in the first slot of a fork still uses the previous version.
+pub fn domain_at_slot<S: Spec>(signature_slot: u64) -> Domain {
+    let fork = fork_at_slot::<S>(signature_slot.max(1) - 1);
+    compute_domain(
+        DOMAIN_SYNC_COMMITTEE,
+        fork.version,
+        genesis_validators_root::<S>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chiado, Epoch, Gnosis, Testnet};
+
+    #[test]
+    fn test_fork_at_epoch() {
+        assert_eq!(fork_at_epoch::<Testnet>(0).name, "phase0");
+        assert_eq!(fork_at_epoch::<Testnet>(56831).name, "bellatrix");
+        assert_eq!(fork_at_epoch::<Testnet>(56832).name, "capella");
+        assert_eq!(fork_at_epoch::<Testnet>(u64::MAX).name, "electra");
+    }
+
+    #[test]
+    fn test_gnosis_fork_at_slot() {
+        // 16 slots per epoch
+        assert_eq!(fork_at_slot::<Gnosis>(8191).name, "phase0");
+        assert_eq!(fork_at_slot::<Gnosis>(8192).name, "altair");
+        assert_eq!(fork_at_slot::<Chiado>(1439).name, "phase0");
+        assert_eq!(fork_at_slot::<Chiado>(1440).name, "altair");
+        for fork in Gnosis::FORKS {
+            let digest = compute_fork_digest(fork.version, Gnosis::GENESIS_VALIDATORS_ROOT);
+            assert_eq!(fork_by_digest::<Gnosis>(digest), Some(fork));
+            assert_eq!(fork_by_digest::<Chiado>(digest), None);
+        }
+    }
+
+    #[test]
+    fn test_domain_at_fork_boundary() {
+        let first_capella_slot = Epoch(56832).start_slot::<Testnet>().0;
+        let bellatrix = fork_at_epoch::<Testnet>(56831).version;
+        assert_eq!(
+            domain_at_slot::<Testnet>(first_capella_slot),
+            compute_domain(
+                DOMAIN_SYNC_COMMITTEE,
+                bellatrix,
+                Testnet::GENESIS_VALIDATORS_ROOT
+            )
+        );
+        assert_ne!(
+            domain_at_slot::<Testnet>(first_capella_slot + 1),
+            domain_at_slot::<Testnet>(first_capella_slot)
+        );
+    }
+
+    #[test]
+    fn test_fork_by_digest() {
+        for fork in Testnet::FORKS {
+            let digest = compute_fork_digest(fork.version, Testnet::GENESIS_VALIDATORS_ROOT);
+            assert_eq!(fork_by_digest::<Testnet>(digest), Some(fork));
+        }
+        assert_eq!(fork_by_digest::<Testnet>([0xff; 4]), None);
+    }
+
+    #[test]
+    fn test_compute_domain() {
+        // domain of `data/sync_step_512.json`
+        let expected: Domain = [
+            7, 0, 0, 0, 48, 83, 175, 74, 95, 250, 246, 166, 104, 40, 151, 228, 42, 212, 194, 8, 48,
+            56, 232, 147, 61, 9, 41, 204, 88, 234, 56, 134,
+        ];
+        assert_eq!(
+            compute_domain(
+                DOMAIN_SYNC_COMMITTEE,
+                [0, 0, 0, 0],
+                Testnet::GENESIS_VALIDATORS_ROOT
+            ),
+            expected
+        );
+    }
+}
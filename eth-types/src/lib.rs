@@ -4,8 +4,18 @@
 
 #![feature(trait_alias)]
 
+mod fork;
+mod network;
 mod spec;
-pub use spec::{Mainnet, Minimal, Spec, Testnet};
+mod time;
+pub use fork::{
+    compute_domain, compute_fork_data_root, compute_fork_digest, domain_at_slot, fork_at_epoch,
+    fork_at_slot, fork_by_digest, Domain, DomainType, Fork, ForkDigest, Version,
+    DOMAIN_SYNC_COMMITTEE,
+};
+pub use network::{configure_network, NetworkConfig, FAR_FUTURE_EPOCH};
+pub use spec::{Chiado, Gnosis, Mainnet, Minimal, Spec, Testnet};
+pub use time::{Epoch, Period, Slot};
 
 pub const NUM_LIMBS: usize = 5;
 pub const LIMB_BITS: usize = 104;
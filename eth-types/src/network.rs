@@ -0,0 +1,221 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Genesis validators root and fork schedule, which devnets of the same preset choose at genesis.
+
+use crate::{Fork, Spec, Version};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+
+/// Epoch of forks that are not scheduled.
+pub const FAR_FUTURE_EPOCH: u64 = u64::MAX;
+
+/// Forks after phase0 in activation order, with the prefix of their keys in `/eth/v1/config/spec`.
+const SCHEDULE: [(&str, &str); 5] = [
+    ("altair", "ALTAIR"),
+    ("bellatrix", "BELLATRIX"),
+    ("capella", "CAPELLA"),
+    ("deneb", "DENEB"),
+    ("electra", "ELECTRA"),
+];
+
+/// The parameters of a network that enter the signing domain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NetworkConfig {
+    pub genesis_validators_root: [u8; 32],
+    /// Fork schedule ordered by activation epoch, starting at epoch 0.
+    pub forks: Vec<Fork>,
+}
+
+impl NetworkConfig {
+    /// The constants of `S`.
+    pub fn of<S: Spec>() -> Self {
+        Self {
+            genesis_validators_root: S::GENESIS_VALIDATORS_ROOT,
+            forks: S::FORKS.to_vec(),
+        }
+    }
+
+    /// Reads the `data` of the `/eth/v1/beacon/genesis` and `/eth/v1/config/spec` responses,
+    /// and checks that the node runs the preset of `S`.
+    pub fn from_beacon_api<S: Spec>(genesis: &Value, spec: &Value) -> Result<Self, String> {
+        let field = |value: &Value, key: &str| -> Result<String, String> {
+            value
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| format!("missing {key}"))
+        };
+        let bytes = |value: &Value, key: &str| -> Result<Vec<u8>, String> {
+            let hex_value = field(value, key)?;
+            hex::decode(hex_value.trim_start_matches("0x")).map_err(|e| format!("{key}: {e}"))
+        };
+        let number = |key: &str| -> Result<u64, String> {
+            field(spec, key)?.parse().map_err(|e| format!("{key}: {e}"))
+        };
+        let version = |key: &str| -> Result<Version, String> {
+            bytes(spec, key)?
+                .try_into()
+                .map_err(|_| format!("{key} is not a 4 byte version"))
+        };
+
+        for (key, expected) in [
+            ("SLOTS_PER_EPOCH", S::SLOTS_PER_EPOCH),
+            (
+                "EPOCHS_PER_SYNC_COMMITTEE_PERIOD",
+                S::EPOCHS_PER_SYNC_COMMITTEE_PERIOD,
+            ),
+            ("SYNC_COMMITTEE_SIZE", S::SYNC_COMMITTEE_SIZE as u64),
+        ] {
+            let actual = number(key)?;
+            if actual != expected {
+                return Err(format!(
+                    "{key} is {actual}, the {} preset expects {expected}",
+                    S::NAME
+                ));
+            }
+        }
+
+        let mut forks = vec![Fork::new("phase0", version("GENESIS_FORK_VERSION")?, 0)];
+        for (name, prefix) in SCHEDULE {
+            // nodes leave out the keys of forks they do not implement
+            let epoch_key = format!("{prefix}_FORK_EPOCH");
+            if spec.get(&epoch_key).is_none() {
+                break;
+            }
+            let epoch = number(&epoch_key)?;
+            if epoch == FAR_FUTURE_EPOCH {
+                break;
+            }
+            if epoch < forks[forks.len() - 1].epoch {
+                return Err(format!("{name} is scheduled before the previous fork"));
+            }
+            forks.push(Fork::new(
+                name,
+                version(&format!("{prefix}_FORK_VERSION"))?,
+                epoch,
+            ));
+        }
+        Ok(Self {
+            genesis_validators_root: bytes(genesis, "genesis_validators_root")?
+                .try_into()
+                .map_err(|_| "genesis_validators_root is not 32 bytes".to_string())?,
+            forks,
+        })
+    }
+}
+
+type Overrides = RwLock<HashMap<&'static str, &'static NetworkConfig>>;
+
+fn overrides() -> &'static Overrides {
+    static OVERRIDES: OnceLock<Overrides> = OnceLock::new();
+    OVERRIDES.get_or_init(Default::default)
+}
+
+fn configured<S: Spec>() -> Option<&'static NetworkConfig> {
+    overrides().read().unwrap().get(S::NAME).copied()
+}
+
+/// Replaces the genesis validators root and fork schedule of `S` for the rest of the process,
+/// e.g. with those of a local devnet running the minimal preset.
+pub fn configure_network<S: Spec>(config: NetworkConfig) {
+    // configured once per process, leaked so that forks can be handed out as `&'static`
+    overrides()
+        .write()
+        .unwrap()
+        .insert(S::NAME, Box::leak(Box::new(config)));
+}
+
+pub(crate) fn genesis_validators_root<S: Spec>() -> [u8; 32] {
+    configured::<S>()
+        .map(|config| config.genesis_validators_root)
+        .unwrap_or(S::GENESIS_VALIDATORS_ROOT)
+}
+
+pub(crate) fn forks<S: Spec>() -> &'static [Fork] {
+    configured::<S>()
+        .map(|config| config.forks.as_slice())
+        .unwrap_or(S::FORKS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compute_domain, compute_fork_digest, domain_at_slot, fork_at_slot, fork_by_digest, Minimal,
+        DOMAIN_SYNC_COMMITTEE,
+    };
+
+    /// The minimal preset, configured by a devnet in this test only.
+    #[derive(Copy, Clone, Debug, Default)]
+    struct Devnet;
+
+    impl Spec for Devnet {
+        const NAME: &'static str = "devnet test";
+        const NETWORK: &'static str = Minimal::NETWORK;
+        const SYNC_COMMITTEE_SIZE: usize = Minimal::SYNC_COMMITTEE_SIZE;
+        const SYNC_COMMITTEE_ROOT_INDEX: usize = Minimal::SYNC_COMMITTEE_ROOT_INDEX;
+        const SYNC_COMMITTEE_DEPTH: usize = Minimal::SYNC_COMMITTEE_DEPTH;
+        const SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX: usize = Minimal::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX;
+        const SYNC_COMMITTEE_PUBKEYS_DEPTH: usize = Minimal::SYNC_COMMITTEE_PUBKEYS_DEPTH;
+        const DST: &'static [u8] = Minimal::DST;
+        const EXECUTION_STATE_ROOT_INDEX: usize = Minimal::EXECUTION_STATE_ROOT_INDEX;
+        const EXECUTION_STATE_ROOT_DEPTH: usize = Minimal::EXECUTION_STATE_ROOT_DEPTH;
+        const FINALIZED_HEADER_INDEX: usize = Minimal::FINALIZED_HEADER_INDEX;
+        const FINALIZED_HEADER_DEPTH: usize = Minimal::FINALIZED_HEADER_DEPTH;
+        const SLOTS_PER_EPOCH: u64 = Minimal::SLOTS_PER_EPOCH;
+        const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = Minimal::EPOCHS_PER_SYNC_COMMITTEE_PERIOD;
+        const GENESIS_VALIDATORS_ROOT: [u8; 32] = Minimal::GENESIS_VALIDATORS_ROOT;
+        const FORKS: &'static [Fork] = Minimal::FORKS;
+    }
+
+    fn load(name: &str) -> Value {
+        let path = format!("{}/../data/devnet/{name}", env!("CARGO_MANIFEST_DIR"));
+        let response: Value = serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap();
+        response["data"].clone()
+    }
+
+    #[test]
+    fn test_devnet_config() {
+        let (genesis, spec) = (load("genesis.json"), load("spec.json"));
+        let config = NetworkConfig::from_beacon_api::<Devnet>(&genesis, &spec).unwrap();
+        assert_eq!(
+            config
+                .forks
+                .iter()
+                .map(|fork| fork.name)
+                .collect::<Vec<_>>(),
+            ["phase0", "altair", "bellatrix", "capella", "deneb"]
+        );
+        assert_ne!(config, NetworkConfig::of::<Devnet>());
+        // a mainnet preset node is not a minimal devnet
+        assert!(NetworkConfig::from_beacon_api::<crate::Testnet>(&genesis, &spec).is_err());
+
+        assert_eq!(
+            domain_at_slot::<Devnet>(100),
+            domain_at_slot::<Minimal>(100)
+        );
+        configure_network::<Devnet>(config.clone());
+        let deneb = config.forks[4];
+        let signature_slot = deneb.epoch * Devnet::SLOTS_PER_EPOCH + 1;
+        assert_eq!(fork_at_slot::<Devnet>(signature_slot), &deneb);
+        assert_eq!(
+            domain_at_slot::<Devnet>(signature_slot),
+            compute_domain(
+                DOMAIN_SYNC_COMMITTEE,
+                deneb.version,
+                config.genesis_validators_root
+            )
+        );
+        assert_ne!(
+            domain_at_slot::<Devnet>(signature_slot),
+            domain_at_slot::<Minimal>(signature_slot)
+        );
+        let digest = compute_fork_digest(deneb.version, config.genesis_validators_root);
+        assert_eq!(fork_by_digest::<Devnet>(digest), Some(&deneb));
+    }
+}
@@ -2,11 +2,14 @@
 // Code: https://github.com/ChainSafe/Spectre
 // SPDX-License-Identifier: LGPL-3.0-only
 
+use crate::fork::Fork;
 use core::fmt::Debug;
 
 /// Beacon chain specification.
 pub trait Spec: 'static + Sized + Copy + Default + Debug {
     const NAME: &'static str;
+    /// Network the fork schedule and genesis validators root belong to.
+    const NETWORK: &'static str;
     const SYNC_COMMITTEE_SIZE: usize;
     const SYNC_COMMITTEE_ROOT_INDEX: usize;
     const SYNC_COMMITTEE_DEPTH: usize;
@@ -20,6 +23,15 @@ pub trait Spec: 'static + Sized + Copy + Default + Debug {
     const FINALIZED_HEADER_DEPTH: usize;
     const BYTES_PER_LOGS_BLOOM: usize = 256;
     const MAX_EXTRA_DATA_BYTES: usize = 32;
+
+    const SLOTS_PER_EPOCH: u64;
+    const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64;
+    const SLOTS_PER_SYNC_COMMITTEE_PERIOD: u64 =
+        Self::SLOTS_PER_EPOCH * Self::EPOCHS_PER_SYNC_COMMITTEE_PERIOD;
+
+    const GENESIS_VALIDATORS_ROOT: [u8; 32];
+    /// Fork schedule ordered by activation epoch, starting at epoch 0.
+    const FORKS: &'static [Fork];
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -27,6 +39,7 @@ pub struct Minimal;
 
 impl Spec for Minimal {
     const NAME: &'static str = "minimal";
+    const NETWORK: &'static str = "devnet";
     const SYNC_COMMITTEE_SIZE: usize = 32;
     const SYNC_COMMITTEE_DEPTH: usize = 5;
     const SYNC_COMMITTEE_ROOT_INDEX: usize = 55;
@@ -41,6 +54,20 @@ impl Spec for Minimal {
 
     const BYTES_PER_LOGS_BLOOM: usize = 256;
     const MAX_EXTRA_DATA_BYTES: usize = 32;
+
+    const SLOTS_PER_EPOCH: u64 = 8;
+    const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 8;
+
+    // placeholders, every devnet chooses its own, see `NetworkConfig::from_beacon_api`
+    const GENESIS_VALIDATORS_ROOT: [u8; 32] = [0; 32];
+    const FORKS: &'static [Fork] = &[
+        Fork::new("phase0", [0, 0, 0, 1], 0),
+        Fork::new("altair", [1, 0, 0, 1], 0),
+        Fork::new("bellatrix", [2, 0, 0, 1], 0),
+        Fork::new("capella", [3, 0, 0, 1], 0),
+        Fork::new("deneb", [4, 0, 0, 1], 0),
+        Fork::new("electra", [5, 0, 0, 1], 0),
+    ];
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -48,6 +75,7 @@ pub struct Testnet;
 
 impl Spec for Testnet {
     const NAME: &'static str = "testnet";
+    const NETWORK: &'static str = "sepolia";
     const SYNC_COMMITTEE_SIZE: usize = 512;
     const SYNC_COMMITTEE_DEPTH: usize = 5;
     const SYNC_COMMITTEE_ROOT_INDEX: usize = 55;
@@ -61,6 +89,23 @@ impl Spec for Testnet {
 
     const BYTES_PER_LOGS_BLOOM: usize = 256;
     const MAX_EXTRA_DATA_BYTES: usize = 32;
+
+    const SLOTS_PER_EPOCH: u64 = 32;
+    const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 256;
+
+    // Sepolia
+    const GENESIS_VALIDATORS_ROOT: [u8; 32] = [
+        216, 234, 23, 31, 60, 148, 174, 162, 30, 188, 66, 161, 237, 97, 5, 42, 207, 63, 146, 9,
+        192, 14, 78, 251, 170, 221, 172, 9, 237, 155, 128, 120,
+    ];
+    const FORKS: &'static [Fork] = &[
+        Fork::new("phase0", [144, 0, 0, 105], 0),
+        Fork::new("altair", [144, 0, 0, 112], 50),
+        Fork::new("bellatrix", [144, 0, 0, 113], 100),
+        Fork::new("capella", [144, 0, 0, 114], 56832),
+        Fork::new("deneb", [144, 0, 0, 115], 132608),
+        Fork::new("electra", [144, 0, 0, 116], 222464),
+    ];
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
@@ -68,6 +113,44 @@ pub struct Mainnet;
 
 impl Spec for Mainnet {
     const NAME: &'static str = "mainnet";
+    const NETWORK: &'static str = "mainnet";
+    const SYNC_COMMITTEE_SIZE: usize = 512;
+    const SYNC_COMMITTEE_DEPTH: usize = 5;
+    const SYNC_COMMITTEE_ROOT_INDEX: usize = 55;
+    const SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX: usize = Self::SYNC_COMMITTEE_ROOT_INDEX * 2;
+    const SYNC_COMMITTEE_PUBKEYS_DEPTH: usize = Self::SYNC_COMMITTEE_DEPTH + 1;
+    const DST: &'static [u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+    const EXECUTION_STATE_ROOT_INDEX: usize = 25;
+    const EXECUTION_STATE_ROOT_DEPTH: usize = 4;
+    const FINALIZED_HEADER_INDEX: usize = 105;
+    const FINALIZED_HEADER_DEPTH: usize = 6;
+
+    const BYTES_PER_LOGS_BLOOM: usize = 256;
+    const MAX_EXTRA_DATA_BYTES: usize = 32;
+
+    const SLOTS_PER_EPOCH: u64 = 32;
+    const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 256;
+
+    const GENESIS_VALIDATORS_ROOT: [u8; 32] = [
+        75, 54, 61, 185, 78, 40, 97, 32, 215, 110, 185, 5, 52, 15, 221, 78, 84, 191, 233, 240, 107,
+        243, 63, 246, 207, 90, 210, 127, 81, 27, 254, 149,
+    ];
+    const FORKS: &'static [Fork] = &[
+        Fork::new("phase0", [0, 0, 0, 0], 0),
+        Fork::new("altair", [1, 0, 0, 0], 74240),
+        Fork::new("bellatrix", [2, 0, 0, 0], 144896),
+        Fork::new("capella", [3, 0, 0, 0], 194048),
+        Fork::new("deneb", [4, 0, 0, 0], 269568),
+        Fork::new("electra", [5, 0, 0, 0], 364032),
+    ];
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Gnosis;
+
+impl Spec for Gnosis {
+    const NAME: &'static str = "gnosis";
+    const NETWORK: &'static str = "gnosis";
     const SYNC_COMMITTEE_SIZE: usize = 512;
     const SYNC_COMMITTEE_DEPTH: usize = 5;
     const SYNC_COMMITTEE_ROOT_INDEX: usize = 55;
@@ -81,4 +164,59 @@ impl Spec for Mainnet {
 
     const BYTES_PER_LOGS_BLOOM: usize = 256;
     const MAX_EXTRA_DATA_BYTES: usize = 32;
+
+    // 5 second slots
+    const SLOTS_PER_EPOCH: u64 = 16;
+    const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 512;
+
+    const GENESIS_VALIDATORS_ROOT: [u8; 32] = [
+        245, 220, 181, 86, 78, 130, 154, 171, 39, 38, 75, 155, 236, 213, 223, 170, 1, 112, 133, 97,
+        18, 36, 203, 48, 54, 245, 115, 54, 141, 187, 157, 71,
+    ];
+    const FORKS: &'static [Fork] = &[
+        Fork::new("phase0", [0, 0, 0, 100], 0),
+        Fork::new("altair", [1, 0, 0, 100], 512),
+        Fork::new("bellatrix", [2, 0, 0, 100], 385536),
+        Fork::new("capella", [3, 0, 0, 100], 648704),
+        Fork::new("deneb", [4, 0, 0, 100], 889856),
+        Fork::new("electra", [5, 0, 0, 100], 1337856),
+    ];
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Chiado;
+
+impl Spec for Chiado {
+    const NAME: &'static str = "chiado";
+    const NETWORK: &'static str = "chiado";
+    const SYNC_COMMITTEE_SIZE: usize = 512;
+    const SYNC_COMMITTEE_DEPTH: usize = 5;
+    const SYNC_COMMITTEE_ROOT_INDEX: usize = 55;
+    const SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX: usize = Self::SYNC_COMMITTEE_ROOT_INDEX * 2;
+    const SYNC_COMMITTEE_PUBKEYS_DEPTH: usize = Self::SYNC_COMMITTEE_DEPTH + 1;
+    const DST: &'static [u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+    const EXECUTION_STATE_ROOT_INDEX: usize = 25;
+    const EXECUTION_STATE_ROOT_DEPTH: usize = 4;
+    const FINALIZED_HEADER_INDEX: usize = 105;
+    const FINALIZED_HEADER_DEPTH: usize = 6;
+
+    const BYTES_PER_LOGS_BLOOM: usize = 256;
+    const MAX_EXTRA_DATA_BYTES: usize = 32;
+
+    const SLOTS_PER_EPOCH: u64 = 16;
+    const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = 512;
+
+    // Gnosis Chain testnet
+    const GENESIS_VALIDATORS_ROOT: [u8; 32] = [
+        157, 100, 45, 172, 115, 5, 143, 191, 57, 192, 174, 65, 171, 30, 52, 228, 216, 137, 4, 60,
+        177, 153, 133, 29, 237, 112, 149, 188, 153, 235, 76, 30,
+    ];
+    const FORKS: &'static [Fork] = &[
+        Fork::new("phase0", [0, 0, 0, 111], 0),
+        Fork::new("altair", [1, 0, 0, 111], 90),
+        Fork::new("bellatrix", [2, 0, 0, 111], 180),
+        Fork::new("capella", [3, 0, 0, 111], 244224),
+        Fork::new("deneb", [4, 0, 0, 111], 516608),
+        Fork::new("electra", [5, 0, 0, 111], 948224),
+    ];
 }
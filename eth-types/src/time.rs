@@ -0,0 +1,105 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Beacon chain time units, converted with the timing constants of a [`Spec`].
+
+use crate::Spec;
+use core::fmt;
+
+macro_rules! time_unit {
+    ($(#[$doc:meta])* $name:ident) => {
+        $(#[$doc])*
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+        pub struct $name(pub u64);
+
+        impl From<u64> for $name {
+            fn from(value: u64) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for u64 {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+time_unit!(
+    /// A beacon chain slot.
+    Slot
+);
+time_unit!(
+    /// A beacon chain epoch of `S::SLOTS_PER_EPOCH` slots.
+    Epoch
+);
+time_unit!(
+    /// A sync committee period of `S::EPOCHS_PER_SYNC_COMMITTEE_PERIOD` epochs.
+    Period
+);
+
+impl Slot {
+    pub fn epoch<S: Spec>(self) -> Epoch {
+        Epoch(self.0 / S::SLOTS_PER_EPOCH)
+    }
+
+    pub fn period<S: Spec>(self) -> Period {
+        Period(self.0 / S::SLOTS_PER_SYNC_COMMITTEE_PERIOD)
+    }
+}
+
+impl Epoch {
+    pub fn start_slot<S: Spec>(self) -> Slot {
+        Slot(self.0 * S::SLOTS_PER_EPOCH)
+    }
+
+    pub fn period<S: Spec>(self) -> Period {
+        Period(self.0 / S::EPOCHS_PER_SYNC_COMMITTEE_PERIOD)
+    }
+}
+
+impl Period {
+    pub fn start_slot<S: Spec>(self) -> Slot {
+        Slot(self.0 * S::SLOTS_PER_SYNC_COMMITTEE_PERIOD)
+    }
+
+    pub fn start_epoch<S: Spec>(self) -> Epoch {
+        Epoch(self.0 * S::EPOCHS_PER_SYNC_COMMITTEE_PERIOD)
+    }
+
+    pub fn next(self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Gnosis, Minimal, Testnet};
+
+    #[test]
+    fn test_conversions() {
+        let slot = Slot(6897664);
+        assert_eq!(slot.epoch::<Testnet>(), Epoch(215552));
+        assert_eq!(slot.period::<Testnet>(), Period(842));
+        assert_eq!(slot.epoch::<Testnet>().period::<Testnet>(), Period(842));
+        assert_eq!(Period(842).start_slot::<Testnet>(), slot);
+        assert_eq!(Period(842).next().start_epoch::<Testnet>(), Epoch(215808));
+
+        assert_eq!(Slot(64).epoch::<Minimal>(), Epoch(8));
+        assert_eq!(Slot(64).period::<Minimal>(), Period(1));
+        assert_eq!(Slot(63).period::<Minimal>(), Period(0));
+
+        assert_eq!(Slot(8192).epoch::<Gnosis>(), Epoch(512));
+        assert_eq!(Slot(8192).period::<Gnosis>(), Period(1));
+        assert_eq!(Period(1).start_epoch::<Gnosis>(), Epoch(512));
+    }
+}
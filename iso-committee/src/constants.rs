@@ -1,2 +1,11 @@
 pub const DEFAULT_FIELD_MODULUS: &'static str =
     "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+
+// Generalized indices of the proven beacon state and block body fields.
+// They only depend on the container layout, which is the same for every preset,
+// so the programs work for any committee size as long as the witness is consistent.
+pub const SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX: usize = 110;
+pub const FINALIZED_HEADER_INDEX: usize = 105;
+pub const EXECUTION_PAYLOAD_INDEX: usize = 9;
+// the execution payload index is relative to the body root, one level below its gindex
+pub const EXECUTION_PAYLOAD_DEPTH: usize = 4;
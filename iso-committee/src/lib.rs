@@ -1,11 +1,42 @@
 pub mod constants;
 pub mod types;
 pub mod utils;
+pub mod witness;
+use constants::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX;
 use types::CommitteeUpdateArgs;
+use utils::{hash_keys, is_valid_merkle_proof, merkleize_keys};
+use witness::{check_lengths, CircuitKind, Witness, WitnessError};
+
+impl Witness for CommitteeUpdateArgs {
+    const KIND: CircuitKind = CircuitKind::Committee;
+
+    fn validate(&self) -> Result<(), WitnessError> {
+        check_lengths("Public key", &self.pubkeys_compressed, 48)?;
+        check_lengths(
+            "Sync committee branch node",
+            &self.sync_committee_branch,
+            32,
+        )?;
+        if self.sync_committee_branch.len() != SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX.ilog2() as usize
+            || !is_valid_merkle_proof(
+                self.sync_committee_branch.clone(),
+                merkleize_keys(hash_keys(self.pubkeys_compressed.clone())),
+                self.finalized_header.state_root.as_ref(),
+                SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+            )
+        {
+            return Err(WitnessError::Invalid(
+                "sync committee branch does not match the finalized state root".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
 
 #[cfg(test)]
 mod test {
     use crate::{
+        constants::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
         types::PublicKeyHashes,
         utils::{hash_keys, load_circuit_args_env, merkleize_keys, verify_merkle_proof},
         CommitteeUpdateArgs,
@@ -20,7 +51,7 @@ mod test {
             args.sync_committee_branch,
             committee_root_ssz,
             &finalized_state_root,
-            110,
+            SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
         );
     }
 }
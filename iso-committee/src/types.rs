@@ -28,8 +28,10 @@ pub type Commitment = Vec<u8>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct CommitteeUpdateArgs {
+    #[serde(with = "crate::witness::hex_list")]
     pub pubkeys_compressed: PublicKeys,
     pub finalized_header: BeaconBlockHeader,
+    #[serde(with = "crate::witness::hex_list")]
     pub sync_committee_branch: Branch,
 }
 
@@ -1,3 +1,4 @@
+use crate::witness::WitnessFile;
 use crate::{
     types::{Branch, Leaf, PublicKeyHashes, PublicKeys, ZERO_HASHES},
     CommitteeUpdateArgs,
@@ -5,19 +6,27 @@ use crate::{
 use itertools::Itertools;
 use num_bigint::BigUint;
 pub use sha2::{Digest, Sha256};
-use std::{env, fs};
+use std::env;
 
+/// Loads a committee witness in any format of [`WitnessFile`], panicking on failure.
 pub fn load_circuit_args(path: &str) -> CommitteeUpdateArgs {
-    serde_json::from_slice(&fs::read(&path).unwrap()).unwrap()
+    match WitnessFile::load(path) {
+        Ok(file) => file.witness,
+        Err(e) => panic!("Failed to load {}: {}", path, e),
+    }
 }
 
 pub fn load_circuit_args_env() -> CommitteeUpdateArgs {
     let path =
         env::var("COMMITTEE_UPDATE_TEST_PATH").unwrap_or("../data/rotation_512.json".to_string());
-    serde_json::from_slice(&fs::read(&path).unwrap()).unwrap()
+    load_circuit_args(&path)
+}
+
+pub fn verify_merkle_proof(branch: Branch, leaf: Leaf, root: &Vec<u8>, gindex: usize) {
+    assert!(is_valid_merkle_proof(branch, leaf, root, gindex));
 }
 
-pub fn verify_merkle_proof(branch: Branch, leaf: Leaf, root: &Vec<u8>, mut gindex: usize) {
+pub fn is_valid_merkle_proof(branch: Branch, leaf: Leaf, root: &[u8], mut gindex: usize) -> bool {
     let mut computed_hash = leaf;
     for node in branch {
         if gindex % 2 == 0 {
@@ -27,7 +36,7 @@ pub fn verify_merkle_proof(branch: Branch, leaf: Leaf, root: &Vec<u8>, mut ginde
         }
         gindex /= 2;
     }
-    assert_eq!(&computed_hash, root);
+    computed_hash == root
 }
 
 // for the step circuit the PublicKeyHashes are generic Hashes.
@@ -121,6 +130,18 @@ pub fn uint64_to_le_256(value: u64) -> Vec<u8> {
     bytes
 }
 
+/// Runs `f` in a named cycle tracker span. Inside the zkVM the cycles spent in `f` are added
+/// to the `cycle_tracker` of the execution report, spans with the same name accumulate.
+#[cfg_attr(not(target_os = "zkvm"), allow(unused_variables))]
+pub fn track<T>(span: &str, f: impl FnOnce() -> T) -> T {
+    #[cfg(target_os = "zkvm")]
+    println!("cycle-tracker-report-start: {}", span);
+    let result = f();
+    #[cfg(target_os = "zkvm")]
+    println!("cycle-tracker-report-end: {}", span);
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::{decode_pubkeys_x, load_circuit_args_env};
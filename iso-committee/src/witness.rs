@@ -0,0 +1,375 @@
+//! Versioned, self-describing witness files.
+//!
+//! A [`WitnessFile`] wraps the input of a circuit together with the beacon chain it was taken from.
+//! It is stored as JSON with hex encoded byte fields, or as borsh behind [`BORSH_MAGIC`].
+//! Raw witnesses written before the envelope existed, e.g. `data/sync_step_512.json`,
+//! are still loaded and reported as [`LEGACY_VERSION`].
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{fmt, fs, path::Path};
+
+/// Version written by [`WitnessFile::new`].
+pub const WITNESS_VERSION: u32 = 1;
+/// Version reported for raw witnesses without metadata.
+pub const LEGACY_VERSION: u32 = 0;
+/// Prefix of borsh encoded witness files, JSON files never start with it.
+pub const BORSH_MAGIC: [u8; 4] = *b"SPWT";
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitKind {
+    /// `SyncStepArgs` of the step iso
+    Step,
+    /// `SyncStepCircuitInput` of the step program
+    StepInput,
+    /// `CommitteeUpdateArgs` of the committee iso
+    Committee,
+    /// `RotationCircuitInputs` of the rotation program
+    Rotation,
+}
+
+impl fmt::Display for CircuitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Step => "step",
+            Self::StepInput => "step_input",
+            Self::Committee => "committee",
+            Self::Rotation => "rotation",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WitnessEncoding {
+    Json,
+    Borsh,
+}
+
+#[derive(Debug)]
+pub enum WitnessError {
+    Io(std::io::Error),
+    Decode(String),
+    UnsupportedVersion(u32),
+    UnexpectedKind {
+        expected: CircuitKind,
+        actual: CircuitKind,
+    },
+    Invalid(String),
+}
+
+impl fmt::Display for WitnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to access witness file: {}", e),
+            Self::Decode(reason) => write!(f, "failed to decode witness: {}", reason),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported witness version {}", version)
+            }
+            Self::UnexpectedKind { expected, actual } => {
+                write!(f, "expected a {} witness, got {}", expected, actual)
+            }
+            Self::Invalid(reason) => write!(f, "invalid witness: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for WitnessError {}
+
+impl From<std::io::Error> for WitnessError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// A circuit input that can be stored in a [`WitnessFile`].
+pub trait Witness: Serialize + DeserializeOwned + BorshSerialize + BorshDeserialize {
+    const KIND: CircuitKind;
+
+    /// Checks the witness for inconsistencies the circuit would fail on.
+    fn validate(&self) -> Result<(), WitnessError>;
+}
+
+/// Where and when a witness was taken.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct WitnessMetadata {
+    pub version: u32,
+    pub kind: CircuitKind,
+    /// Name of the beacon chain preset, e.g. `testnet`
+    pub spec: String,
+    /// Fork active at the attested slot
+    pub fork: String,
+    pub network: String,
+    /// Seconds since the unix epoch
+    pub created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
+pub struct WitnessFile<T> {
+    #[serde(flatten)]
+    pub metadata: WitnessMetadata,
+    pub witness: T,
+}
+
+impl<T: Witness> WitnessFile<T> {
+    pub fn new(
+        witness: T,
+        spec: impl Into<String>,
+        fork: impl Into<String>,
+        network: impl Into<String>,
+    ) -> Self {
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Self {
+            metadata: WitnessMetadata {
+                version: WITNESS_VERSION,
+                kind: T::KIND,
+                spec: spec.into(),
+                fork: fork.into(),
+                network: network.into(),
+                created_at,
+            },
+            witness,
+        }
+    }
+
+    /// Wraps a raw witness that carries no metadata.
+    pub fn legacy(witness: T) -> Self {
+        Self {
+            metadata: WitnessMetadata {
+                version: LEGACY_VERSION,
+                kind: T::KIND,
+                spec: String::new(),
+                fork: String::new(),
+                network: String::new(),
+                created_at: 0,
+            },
+            witness,
+        }
+    }
+
+    /// Decodes a witness in any supported encoding, including raw legacy JSON.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, WitnessError> {
+        let file = if let Some(body) = bytes.strip_prefix(&BORSH_MAGIC) {
+            borsh::from_slice::<Self>(body).map_err(|e| WitnessError::Decode(e.to_string()))?
+        } else {
+            let value: serde_json::Value =
+                serde_json::from_slice(bytes).map_err(|e| WitnessError::Decode(e.to_string()))?;
+            if value.get("version").is_some() && value.get("witness").is_some() {
+                serde_json::from_value::<Self>(value)
+            } else {
+                serde_json::from_value::<T>(value).map(Self::legacy)
+            }
+            .map_err(|e| WitnessError::Decode(e.to_string()))?
+        };
+        if file.metadata.version > WITNESS_VERSION {
+            return Err(WitnessError::UnsupportedVersion(file.metadata.version));
+        }
+        if file.metadata.kind != T::KIND {
+            return Err(WitnessError::UnexpectedKind {
+                expected: T::KIND,
+                actual: file.metadata.kind,
+            });
+        }
+        Ok(file)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WitnessError> {
+        Self::from_slice(&fs::read(path)?)
+    }
+
+    pub fn to_vec(&self, encoding: WitnessEncoding) -> Result<Vec<u8>, WitnessError> {
+        match encoding {
+            WitnessEncoding::Json => {
+                serde_json::to_vec_pretty(self).map_err(|e| WitnessError::Decode(e.to_string()))
+            }
+            WitnessEncoding::Borsh => {
+                let mut bytes = BORSH_MAGIC.to_vec();
+                bytes.extend(borsh::to_vec(self)?);
+                Ok(bytes)
+            }
+        }
+    }
+
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        encoding: WitnessEncoding,
+    ) -> Result<(), WitnessError> {
+        Ok(fs::write(path, self.to_vec(encoding)?)?)
+    }
+
+    pub fn validate(&self) -> Result<(), WitnessError> {
+        self.witness.validate()
+    }
+}
+
+/// Loads and validates the witness at `input`, then writes it to `output` in `encoding`.
+/// The metadata is kept as is, legacy files stay at [`LEGACY_VERSION`].
+pub fn convert_witness<T: Witness>(
+    input: impl AsRef<Path>,
+    output: impl AsRef<Path>,
+    encoding: WitnessEncoding,
+) -> Result<WitnessMetadata, WitnessError> {
+    let file = WitnessFile::<T>::load(input)?;
+    file.validate()?;
+    file.save(output, encoding)?;
+    Ok(file.metadata)
+}
+
+/// Checks that every entry of `bytes` is `len` bytes long.
+pub fn check_lengths(what: &str, bytes: &[Vec<u8>], len: usize) -> Result<(), WitnessError> {
+    match bytes.iter().position(|b| b.len() != len) {
+        Some(i) => Err(WitnessError::Invalid(format!(
+            "{} {} has {} bytes, expected {}",
+            what,
+            i,
+            bytes[i].len(),
+            len
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Serializes byte fields as `0x` prefixed hex in human readable formats,
+/// and accepts both hex and the plain number arrays of legacy files.
+pub mod hex {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub(crate) enum Repr {
+        Hex(String),
+        Raw(Vec<u8>),
+    }
+
+    impl Repr {
+        pub(crate) fn into_bytes<E: serde::de::Error>(self) -> Result<Vec<u8>, E> {
+            match self {
+                Self::Hex(s) => {
+                    ::hex::decode(s.strip_prefix("0x").unwrap_or(&s)).map_err(E::custom)
+                }
+                Self::Raw(bytes) => Ok(bytes),
+            }
+        }
+    }
+
+    pub fn serialize<S, T>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: AsRef<[u8]> + Serialize,
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format!("0x{}", ::hex::encode(bytes)))
+        } else {
+            bytes.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TryFrom<Vec<u8>> + Deserialize<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return T::deserialize(deserializer);
+        }
+        let bytes = Repr::deserialize(deserializer)?.into_bytes()?;
+        let len = bytes.len();
+        T::try_from(bytes)
+            .map_err(|_| serde::de::Error::custom(format!("unexpected length {}", len)))
+    }
+}
+
+/// [`hex`] for lists of byte strings, such as keys and merkle branches.
+pub mod hex_list {
+    use super::hex::Repr;
+    use serde::{ser::SerializeSeq, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(list: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if !serializer.is_human_readable() {
+            return list.serialize(serializer);
+        }
+        let mut seq = serializer.serialize_seq(Some(list.len()))?;
+        for bytes in list {
+            seq.serialize_element(&format!("0x{}", ::hex::encode(bytes)))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if !deserializer.is_human_readable() {
+            return Vec::<Vec<u8>>::deserialize(deserializer);
+        }
+        Vec::<Repr>::deserialize(deserializer)?
+            .into_iter()
+            .map(Repr::into_bytes)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{types::CommitteeUpdateArgs, utils::load_circuit_args_env};
+
+    #[test]
+    fn test_witness_roundtrip() {
+        let legacy = WitnessFile::<CommitteeUpdateArgs>::load("../data/rotation_512.json").unwrap();
+        assert_eq!(legacy.metadata.version, LEGACY_VERSION);
+        legacy.validate().unwrap();
+
+        let file = WitnessFile::new(legacy.witness, "testnet", "deneb", "sepolia");
+        let json = file.to_vec(WitnessEncoding::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value["kind"], "committee");
+        assert!(value["witness"]["sync_committee_branch"][0]
+            .as_str()
+            .unwrap()
+            .starts_with("0x"));
+
+        for encoding in [WitnessEncoding::Json, WitnessEncoding::Borsh] {
+            let bytes = file.to_vec(encoding).unwrap();
+            let decoded = WitnessFile::<CommitteeUpdateArgs>::from_slice(&bytes).unwrap();
+            assert_eq!(decoded.metadata, file.metadata);
+            assert_eq!(
+                decoded.witness.pubkeys_compressed,
+                file.witness.pubkeys_compressed
+            );
+        }
+    }
+
+    #[test]
+    fn test_witness_rejected() {
+        let file = WitnessFile::new(load_circuit_args_env(), "testnet", "deneb", "sepolia");
+        let mut value = serde_json::to_value(&file).unwrap();
+        value["kind"] = "step".into();
+        assert!(matches!(
+            WitnessFile::<CommitteeUpdateArgs>::from_slice(value.to_string().as_bytes()),
+            Err(WitnessError::UnexpectedKind {
+                expected: CircuitKind::Committee,
+                actual: CircuitKind::Step
+            })
+        ));
+        value["version"] = (WITNESS_VERSION + 1).into();
+        assert!(matches!(
+            WitnessFile::<CommitteeUpdateArgs>::from_slice(value.to_string().as_bytes()),
+            Err(WitnessError::UnsupportedVersion(_))
+        ));
+
+        let mut tampered = file.clone();
+        tampered.witness.sync_committee_branch[0][0] ^= 1;
+        assert!(matches!(tampered.validate(), Err(WitnessError::Invalid(_))));
+    }
+}
@@ -1 +1,58 @@
 pub mod types;
+use committee_iso::witness::{CircuitKind, Witness, WitnessError};
+use types::RotationCircuitInputs;
+
+impl Witness for RotationCircuitInputs {
+    const KIND: CircuitKind = CircuitKind::Rotation;
+
+    fn validate(&self) -> Result<(), WitnessError> {
+        self.committee.validate()?;
+        self.step.validate()?;
+        let (committee, step) = (
+            &self.committee.finalized_header,
+            &self.step.args.finalized_header,
+        );
+        if committee.slot != step.slot
+            || committee.proposer_index != step.proposer_index
+            || committee.parent_root != step.parent_root
+            || committee.state_root != step.state_root
+            || committee.body_root != step.body_root
+        {
+            return Err(WitnessError::Invalid(
+                "committee and step witnesses finalize different headers".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::RotationCircuitInputs;
+    use committee_iso::witness::Witness;
+    use eth_types::{Minimal, Spec};
+    use step_iso::{
+        testgen::{generate_rotation, StepParams, SyntheticCommittee},
+        types::SyncStepCircuitInput,
+    };
+
+    #[test]
+    fn test_synthetic_rotation() {
+        let committee = SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 1);
+        let next = SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 2);
+        let params = StepParams::new::<Minimal>(&committee, 2000, [7; 32]);
+        let (args, update) = generate_rotation(&committee, &next, &params);
+        let mut inputs = RotationCircuitInputs {
+            committee: update,
+            step: SyncStepCircuitInput {
+                args,
+                commitment: committee.commitment(),
+            },
+        };
+        inputs.validate().unwrap();
+
+        // the finalized state must hold the next committee
+        inputs.committee.pubkeys_compressed = committee.pubkeys_compressed();
+        assert!(inputs.validate().is_err());
+    }
+}
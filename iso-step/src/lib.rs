@@ -2,12 +2,18 @@ use bls12_381::{
     hash_to_curve::{ExpandMsgXmd, HashToCurve},
     pairing, G1Affine, G1Projective, G2Affine, G2Projective,
 };
+use committee_iso::constants::{
+    EXECUTION_PAYLOAD_DEPTH, EXECUTION_PAYLOAD_INDEX, FINALIZED_HEADER_INDEX,
+};
 use committee_iso::utils::{
-    add_left_right, commit_to_keys_with_sign, compute_digest, decode_pubkeys_x, merkleize_keys,
-    uint64_to_le_256, Sha256,
+    add_left_right, commit_to_keys_with_sign, compute_digest, decode_pubkeys_x,
+    is_valid_merkle_proof, merkleize_keys, track, uint64_to_le_256, Sha256,
 };
+use committee_iso::witness::{check_lengths, CircuitKind, Witness, WitnessError};
 use types::Commitment;
-use types::SyncStepArgs;
+use types::{SyncStepArgs, SyncStepCircuitInput};
+#[cfg(any(test, feature = "test-gen"))]
+pub mod testgen;
 pub mod types;
 pub mod utils;
 
@@ -40,48 +46,65 @@ pub fn decompress_keys(keys: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
         .collect()
 }
 
-fn aggregate_pubkey(args: SyncStepArgs) -> (G1Affine, Commitment) {
-    let pubkey_affines: Vec<G1Affine> = args
-        .pubkeys_uncompressed
-        .as_slice()
-        .iter()
+/// Like [`decompress_keys`], but checks that every key is a valid point instead of panicking.
+pub fn try_decompress_keys(keys: &[Vec<u8>]) -> Option<Vec<Vec<u8>>> {
+    keys.iter()
         .map(|bytes| {
-            G1Affine::from_uncompressed_unchecked(&bytes.as_slice().try_into().unwrap()).unwrap()
+            let bytes: &[u8; 48] = bytes.as_slice().try_into().ok()?;
+            Option::<G1Affine>::from(G1Affine::from_compressed(bytes))
+                .map(|affine| affine.to_uncompressed().to_vec())
         })
-        .collect();
+        .collect()
+}
 
-    let pubkeys_compressed: Vec<Vec<u8>> = pubkey_affines
-        .iter()
-        .map(|uncompressed| uncompressed.to_compressed().to_vec())
-        .collect();
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+fn aggregate_pubkey(args: SyncStepArgs) -> (G1Affine, Commitment) {
+    let pubkey_affines: Vec<G1Affine> = track("key decompression", || {
+        args.pubkeys_uncompressed
+            .as_slice()
+            .iter()
+            .map(|bytes| {
+                G1Affine::from_uncompressed_unchecked(&bytes.as_slice().try_into().unwrap())
+                    .unwrap()
+            })
+            .collect()
+    });
 
-    let pubkeys_decoded = decode_pubkeys_x(pubkeys_compressed);
-    let pubkey_commitment: Commitment =
-        commit_to_keys_with_sign(&pubkeys_decoded.0, &pubkeys_decoded.1);
+    let pubkey_commitment: Commitment = track("key commitment", || {
+        let pubkeys_compressed: Vec<Vec<u8>> = pubkey_affines
+            .iter()
+            .map(|uncompressed| uncompressed.to_compressed().to_vec())
+            .collect();
+        let pubkeys_decoded = decode_pubkeys_x(pubkeys_compressed);
+        commit_to_keys_with_sign(&pubkeys_decoded.0, &pubkeys_decoded.1)
+    });
 
-    let mut generator = G1Projective::identity();
     let participation_bits = args.pariticipation_bits;
-    for (affine, bits) in itertools::multizip((pubkey_affines, participation_bits)) {
-        let affine_projective = G1Projective::from(affine);
-        if !bits {
-            continue;
+    // the committee size is set by the preset, but every member needs a participation bit
+    assert_eq!(participation_bits.len(), pubkey_affines.len());
+    let aggregate_key = track("aggregation", || {
+        let mut generator = G1Projective::identity();
+        for (affine, bits) in itertools::multizip((pubkey_affines, participation_bits)) {
+            let affine_projective = G1Projective::from(affine);
+            if !bits {
+                continue;
+            }
+            // double if equal, add if unequal
+            if generator == affine_projective {
+                generator = generator.double().into();
+            } else {
+                generator = (generator + G1Projective::from(affine)).into();
+            }
         }
-        // double if equal, add if unequal
-        if generator == affine_projective {
-            generator = generator.double().into();
-        } else {
-            generator = (generator + G1Projective::from(affine)).into();
-        }
-    }
+        generator
+    });
 
-    (generator.into(), pubkey_commitment)
+    (aggregate_key.into(), pubkey_commitment)
 }
 
-#[sp1_derive::cycle_tracker]
-pub fn verify_aggregate_signature(args: SyncStepArgs, committee_commitment: [u8; 32]) {
-    const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
-    let (aggregate_key, commitment): (G1Affine, Commitment) = aggregate_pubkey(args.clone());
-    assert_eq!(commitment, committee_commitment);
+/// The attested header root signed over in the domain of `args`, hashed to G2.
+fn signing_message(args: &SyncStepArgs) -> G2Projective {
     let attested_header_root = merkleize_keys(vec![
         uint64_to_le_256(args.attested_header.slot.parse::<u64>().unwrap()),
         uint64_to_le_256(args.attested_header.proposer_index.parse::<u64>().unwrap()),
@@ -91,26 +114,148 @@ pub fn verify_aggregate_signature(args: SyncStepArgs, committee_commitment: [u8;
     ]);
 
     let signing_root: Vec<u8> = add_left_right(attested_header_root, &args.domain.to_vec());
-    let message_g2: G2Projective =
+    track("hash to curve", || {
         <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(
             &compute_digest(&signing_root),
             DST,
-        );
+        )
+    })
+}
+
+#[sp1_derive::cycle_tracker]
+pub fn verify_aggregate_signature(args: SyncStepArgs, committee_commitment: [u8; 32]) {
+    let (aggregate_key, commitment): (G1Affine, Commitment) = aggregate_pubkey(args.clone());
+    assert_eq!(commitment, committee_commitment);
+    let message_g2 = signing_message(&args);
     let signature: G2Affine =
         G2Affine::from_compressed_unchecked(&args.signature_compressed.try_into().unwrap())
             .unwrap();
 
     // e(hash_msg,pub_key)=e(signature,g1)
-    assert_eq!(
-        pairing(&aggregate_key, &message_g2.into()),
-        pairing(&G1Affine::generator(), &signature)
-    );
+    track("pairing", || {
+        assert_eq!(
+            pairing(&aggregate_key, &message_g2.into()),
+            pairing(&G1Affine::generator(), &signature)
+        )
+    });
+}
+
+/// Host side variant of [`verify_aggregate_signature`] for updates from untrusted nodes.
+///
+/// Keys and signature are decoded with subgroup checks and malformed arguments are rejected
+/// instead of panicking. Returns the commitment to the committee, `None` if the signature is
+/// malformed or invalid. The programs keep the unchecked decoding of [`verify_aggregate_signature`].
+pub fn try_verify_aggregate_signature(args: &SyncStepArgs) -> Option<Commitment> {
+    let signature: G2Affine = <[u8; 96]>::try_from(args.signature_compressed.as_slice())
+        .ok()
+        .and_then(|bytes| Option::<G2Affine>::from(G2Affine::from_compressed(&bytes)))?;
+    let keys_valid = args.pubkeys_uncompressed.iter().all(|bytes| {
+        <[u8; 96]>::try_from(bytes.as_slice())
+            .is_ok_and(|bytes| G1Affine::from_uncompressed(&bytes).is_some().into())
+    });
+    if !keys_valid
+        || args.pariticipation_bits.len() != args.pubkeys_uncompressed.len()
+        || args.attested_header.slot.parse::<u64>().is_err()
+        || args.attested_header.proposer_index.parse::<u64>().is_err()
+    {
+        return None;
+    }
+    // the checks above rule out the panics of the unchecked path
+    let (aggregate_key, commitment) = aggregate_pubkey(args.clone());
+    let message_g2 = signing_message(args);
+
+    // e(hash_msg,pub_key)=e(signature,g1)
+    (pairing(&aggregate_key, &message_g2.into()) == pairing(&G1Affine::generator(), &signature))
+        .then_some(commitment)
+}
+
+impl Witness for SyncStepArgs {
+    const KIND: CircuitKind = CircuitKind::Step;
+
+    fn validate(&self) -> Result<(), WitnessError> {
+        let invalid = |reason: &str| Err(WitnessError::Invalid(reason.to_string()));
+        check_lengths("Public key", &self.pubkeys_uncompressed, 96)?;
+        check_lengths("Finality branch node", &self.finality_branch, 32)?;
+        check_lengths(
+            "Execution payload branch node",
+            &self.execution_payload_branch,
+            32,
+        )?;
+        if self.execution_payload_root.len() != 32 {
+            return invalid("execution payload root must be 32 bytes");
+        }
+        if self.finality_branch.len() != FINALIZED_HEADER_INDEX.ilog2() as usize
+            || self.execution_payload_branch.len() != EXECUTION_PAYLOAD_DEPTH
+        {
+            return invalid("unexpected merkle branch depth");
+        }
+        let (Ok(slot), Ok(proposer_index)) = (
+            self.finalized_header.slot.parse::<u64>(),
+            self.finalized_header.proposer_index.parse::<u64>(),
+        ) else {
+            return invalid("malformed finalized header");
+        };
+        if !is_valid_merkle_proof(
+            self.execution_payload_branch.clone(),
+            self.execution_payload_root.clone(),
+            self.finalized_header.body_root.as_ref(),
+            EXECUTION_PAYLOAD_INDEX,
+        ) {
+            return invalid("execution payload branch does not match the finalized body root");
+        }
+        let finalized_header_root = merkleize_keys(vec![
+            uint64_to_le_256(slot),
+            uint64_to_le_256(proposer_index),
+            self.finalized_header.parent_root.to_vec(),
+            self.finalized_header.state_root.to_vec(),
+            self.finalized_header.body_root.to_vec(),
+        ]);
+        if !is_valid_merkle_proof(
+            self.finality_branch.clone(),
+            finalized_header_root,
+            self.attested_header.state_root.as_ref(),
+            FINALIZED_HEADER_INDEX,
+        ) {
+            return invalid("finality branch does not match the attested state root");
+        }
+        if try_verify_aggregate_signature(self).is_none() {
+            return invalid("aggregate signature does not verify");
+        }
+        Ok(())
+    }
+}
+
+impl Witness for SyncStepCircuitInput {
+    const KIND: CircuitKind = CircuitKind::StepInput;
+
+    fn validate(&self) -> Result<(), WitnessError> {
+        self.args.validate()?;
+        let (keys, signs) = decode_pubkeys_x(compress_keys(self.args.pubkeys_uncompressed.clone()));
+        if commit_to_keys_with_sign(&keys, &signs) != self.commitment {
+            return Err(WitnessError::Invalid(
+                "commitment does not match the committee".to_string(),
+            ));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{utils::load_circuit_args_env, verify_aggregate_signature};
-    use committee_iso::utils::{merkleize_keys, uint64_to_le_256, verify_merkle_proof};
+    use crate::testgen::{generate_step, StepParams, SyntheticCommittee};
+    use crate::{
+        compress_keys, try_verify_aggregate_signature,
+        types::SyncStepArgs,
+        utils::{load_circuit_args, load_circuit_args_env},
+        verify_aggregate_signature,
+    };
+    use committee_iso::constants::{EXECUTION_PAYLOAD_INDEX, FINALIZED_HEADER_INDEX};
+    use committee_iso::utils::{
+        commit_to_keys_with_sign, decode_pubkeys_x, merkleize_keys, uint64_to_le_256,
+        verify_merkle_proof,
+    };
+    use committee_iso::witness::{Witness, WitnessEncoding, WitnessFile};
+    use eth_types::{domain_at_slot, Chiado, Gnosis, Minimal, Spec};
 
     #[test]
     fn test_aggregate_pubkey_commitment_and_verify_signature() {
@@ -122,15 +267,110 @@ mod tests {
         verify_aggregate_signature(args.clone(), commitment);
     }
 
+    #[test]
+    fn test_try_verify_aggregate_signature() {
+        let mut args = load_circuit_args_env();
+        assert!(try_verify_aggregate_signature(&args).is_some());
+        let participant = args
+            .pariticipation_bits
+            .iter()
+            .position(|bit| *bit)
+            .unwrap();
+        args.pariticipation_bits[participant] = false;
+        assert!(try_verify_aggregate_signature(&args).is_none());
+        // malformed arguments are rejected where the programs would panic
+        args.pariticipation_bits.pop();
+        assert!(try_verify_aggregate_signature(&args).is_none());
+        args.signature_compressed.truncate(48);
+        assert!(try_verify_aggregate_signature(&args).is_none());
+    }
+
     #[test]
     fn test_verify_roots() {
-        let args: crate::types::SyncStepArgs = load_circuit_args_env();
+        verify_roots(load_circuit_args_env());
+    }
+
+    #[test]
+    fn test_synthetic_gnosis_sync_step() {
+        let mut args = load_circuit_args("../data/synthetic_sync_step_gnosis_512.json");
+        let signature_slot = args.attested_header.slot.parse::<u64>().unwrap() + 1;
+        assert_eq!(args.domain, domain_at_slot::<Gnosis>(signature_slot));
+        verify_roots(args.clone());
+
+        let (keys, signs) = decode_pubkeys_x(compress_keys(args.pubkeys_uncompressed.clone()));
+        verify_aggregate_signature(args.clone(), commit_to_keys_with_sign(&keys, &signs));
+
+        // the same update does not verify against another network
+        args.domain = domain_at_slot::<Chiado>(signature_slot);
+        assert!(try_verify_aggregate_signature(&args).is_none());
+    }
+
+    #[test]
+    fn test_step_witness() {
+        let file = WitnessFile::new(load_circuit_args_env(), "testnet", "phase0", "sepolia");
+        let bytes = file.to_vec(WitnessEncoding::Json).unwrap();
+        let decoded = WitnessFile::<SyncStepArgs>::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.witness.domain, file.witness.domain);
+        decoded.validate().unwrap();
+
+        let mut tampered = decoded.witness;
+        tampered.pariticipation_bits[0] = !tampered.pariticipation_bits[0];
+        assert!(tampered.validate().is_err());
+    }
+
+    #[test]
+    fn test_synthetic_sync_step() {
+        let committee = SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 1);
+        let attested_slot = 8 * Minimal::SLOTS_PER_SYNC_COMMITTEE_PERIOD + 40;
+        let params = StepParams::new::<Minimal>(
+            &committee,
+            attested_slot,
+            domain_at_slot::<Minimal>(attested_slot + 1),
+        );
+        let args = generate_step(&committee, &params);
+        args.validate().unwrap();
+        verify_roots(args.clone());
+        verify_aggregate_signature(args.clone(), committee.commitment());
+
+        // generation is deterministic
+        let again = generate_step(
+            &SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 1),
+            &params,
+        );
+        assert_eq!(args.signature_compressed, again.signature_compressed);
+        assert_eq!(args.attested_header, again.attested_header);
+        assert_ne!(
+            committee.pubkeys_compressed(),
+            SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 2).pubkeys_compressed()
+        );
+    }
+
+    #[test]
+    fn test_synthetic_participation() {
+        let committee = SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 1);
+        let mut params =
+            StepParams::new::<Minimal>(&committee, 1000, domain_at_slot::<Minimal>(1001));
+        // a single signer and exactly two thirds of the committee
+        for signers in [1, Minimal::SYNC_COMMITTEE_SIZE * 2 / 3] {
+            params.participation = (0..committee.size()).map(|i| i < signers).collect();
+            let mut args = generate_step(&committee, &params);
+            assert!(try_verify_aggregate_signature(&args).is_some());
+            assert_eq!(
+                args.pariticipation_bits.iter().filter(|bit| **bit).count(),
+                signers
+            );
+
+            args.pariticipation_bits[signers] = true;
+            assert!(try_verify_aggregate_signature(&args).is_none());
+        }
+    }
 
+    fn verify_roots(args: SyncStepArgs) {
         verify_merkle_proof(
             args.execution_payload_branch.to_vec(),
             args.execution_payload_root,
             &args.finalized_header.body_root.to_vec(),
-            9,
+            EXECUTION_PAYLOAD_INDEX,
         );
 
         // equivalent to a block hash
@@ -146,7 +386,7 @@ mod tests {
             args.finality_branch,
             finalized_header_root,
             &args.attested_header.state_root.to_vec(),
-            105,
+            FINALIZED_HEADER_INDEX,
         );
     }
 }
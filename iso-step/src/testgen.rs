@@ -0,0 +1,259 @@
+//! Synthetic sync committees and witnesses, consistent without a beacon node.
+//!
+//! Keys, headers and sibling nodes are derived from a seed, so the same parameters
+//! always produce the same witness. Branches are built bottom up for the gindices the
+//! programs check, and the attested header is signed by the participating members.
+
+use crate::{
+    compress_keys,
+    types::{BeaconBlockHeader, SyncStepArgs},
+};
+use bls12_381::{
+    hash_to_curve::{ExpandMsgXmd, HashToCurve},
+    G1Affine, G2Affine, G2Projective, Scalar,
+};
+use committee_iso::{
+    constants::{
+        EXECUTION_PAYLOAD_DEPTH, EXECUTION_PAYLOAD_INDEX, FINALIZED_HEADER_INDEX,
+        SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+    },
+    types::{BeaconBlockHeader as CommitteeBeaconBlockHeader, CommitteeUpdateArgs},
+    utils::{
+        add_left_right, commit_to_keys_with_sign, compute_digest, decode_pubkeys_x, hash_keys,
+        merkleize_keys, uint64_to_le_256, Sha256,
+    },
+};
+use eth_types::Spec;
+use ssz_rs::Node;
+
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+fn derive(seed: u64, tag: &str, index: u64) -> Vec<u8> {
+    compute_digest(&[&seed.to_le_bytes(), tag.as_bytes(), &index.to_le_bytes()].concat())
+}
+
+fn node(bytes: Vec<u8>) -> Node {
+    Node::try_from(bytes.as_slice()).unwrap()
+}
+
+/// Derives `depth` sibling nodes and folds `leaf` with them into the root at `gindex`.
+fn branch(
+    seed: u64,
+    tag: &str,
+    leaf: Vec<u8>,
+    depth: usize,
+    gindex: usize,
+) -> (Vec<Vec<u8>>, Vec<u8>) {
+    let siblings = (0..depth as u64)
+        .map(|i| derive(seed, tag, i))
+        .collect::<Vec<_>>();
+    let mut root = leaf;
+    let mut index = gindex;
+    for sibling in &siblings {
+        root = if index & 1 == 0 {
+            compute_digest(&add_left_right(root, sibling))
+        } else {
+            compute_digest(&add_left_right(sibling.clone(), &root))
+        };
+        index /= 2;
+    }
+    (siblings, root)
+}
+
+fn header_root(header: &BeaconBlockHeader) -> Vec<u8> {
+    merkleize_keys(vec![
+        uint64_to_le_256(header.slot.parse::<u64>().unwrap()),
+        uint64_to_le_256(header.proposer_index.parse::<u64>().unwrap()),
+        header.parent_root.to_vec(),
+        header.state_root.to_vec(),
+        header.body_root.to_vec(),
+    ])
+}
+
+/// A sync committee with deterministic secret keys.
+#[derive(Debug, Clone)]
+pub struct SyntheticCommittee {
+    secret_keys: Vec<Scalar>,
+}
+
+impl SyntheticCommittee {
+    /// Committee of `size` members, different seeds give disjoint committees.
+    pub fn new(size: usize, seed: u64) -> Self {
+        let secret_keys = (0..size as u64)
+            .map(|i| {
+                let digest = derive(seed, "secret key", i);
+                let mut limbs = [0u64; 4];
+                for (limb, bytes) in limbs.iter_mut().zip(digest.chunks(8)) {
+                    *limb = u64::from_le_bytes(bytes.try_into().unwrap());
+                }
+                // stay below the scalar modulus
+                limbs[3] &= 0x0fff_ffff_ffff_ffff;
+                Scalar::from_raw(limbs)
+            })
+            .collect();
+        Self { secret_keys }
+    }
+
+    pub fn size(&self) -> usize {
+        self.secret_keys.len()
+    }
+
+    pub fn pubkeys_uncompressed(&self) -> Vec<Vec<u8>> {
+        self.secret_keys
+            .iter()
+            .map(|sk| {
+                G1Affine::from(G1Affine::generator() * sk)
+                    .to_uncompressed()
+                    .to_vec()
+            })
+            .collect()
+    }
+
+    pub fn pubkeys_compressed(&self) -> Vec<Vec<u8>> {
+        compress_keys(self.pubkeys_uncompressed())
+    }
+
+    /// The commitment the programs compute over the committee.
+    pub fn commitment(&self) -> [u8; 32] {
+        let (keys, signs) = decode_pubkeys_x(self.pubkeys_compressed());
+        commit_to_keys_with_sign(&keys, &signs)
+    }
+
+    /// SSZ root of the committee pubkeys, as proven by a committee update.
+    pub fn pubkeys_root(&self) -> Vec<u8> {
+        merkleize_keys(hash_keys(self.pubkeys_compressed()))
+    }
+
+    /// Aggregate signature of the members set in `participation` over `signing_root`.
+    pub fn sign(&self, signing_root: &[u8], participation: &[bool]) -> Vec<u8> {
+        assert_eq!(participation.len(), self.size());
+        let message =
+            <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(signing_root, DST);
+        let secret_key = self
+            .secret_keys
+            .iter()
+            .zip(participation)
+            .filter(|(_, bit)| **bit)
+            .fold(Scalar::zero(), |acc, (sk, _)| acc + sk);
+        G2Affine::from(message * secret_key)
+            .to_compressed()
+            .to_vec()
+    }
+}
+
+/// Parameters of a synthetic sync step.
+#[derive(Debug, Clone)]
+pub struct StepParams {
+    pub attested_slot: u64,
+    pub finalized_slot: u64,
+    /// One bit per member of the signing committee
+    pub participation: Vec<bool>,
+    pub domain: [u8; 32],
+    /// Derives all roots and siblings that are not fixed by the proofs
+    pub seed: u64,
+}
+
+impl StepParams {
+    /// Full participation at a finalized slot two epochs of `S` behind `attested_slot`.
+    pub fn new<S: Spec>(
+        committee: &SyntheticCommittee,
+        attested_slot: u64,
+        domain: [u8; 32],
+    ) -> Self {
+        Self {
+            attested_slot,
+            finalized_slot: attested_slot.saturating_sub(2 * S::SLOTS_PER_EPOCH),
+            participation: vec![true; committee.size()],
+            domain,
+            seed: 0,
+        }
+    }
+}
+
+/// Builds a step witness whose finalized header state root is `finalized_state_root`.
+fn step_with_state_root(
+    committee: &SyntheticCommittee,
+    params: &StepParams,
+    finalized_state_root: Vec<u8>,
+) -> SyncStepArgs {
+    let seed = params.seed;
+    let execution_payload_root = derive(seed, "execution payload", 0);
+    let (execution_payload_branch, body_root) = branch(
+        seed,
+        "execution payload branch",
+        execution_payload_root.clone(),
+        EXECUTION_PAYLOAD_DEPTH,
+        EXECUTION_PAYLOAD_INDEX,
+    );
+    let finalized_header = BeaconBlockHeader {
+        slot: params.finalized_slot.to_string(),
+        proposer_index: (params.finalized_slot % 1024).to_string(),
+        parent_root: node(derive(seed, "finalized parent", 0)),
+        state_root: node(finalized_state_root),
+        body_root: node(body_root),
+    };
+    let (finality_branch, attested_state_root) = branch(
+        seed,
+        "finality branch",
+        header_root(&finalized_header),
+        FINALIZED_HEADER_INDEX.ilog2() as usize,
+        FINALIZED_HEADER_INDEX,
+    );
+    let attested_header = BeaconBlockHeader {
+        slot: params.attested_slot.to_string(),
+        proposer_index: (params.attested_slot % 1024).to_string(),
+        parent_root: node(derive(seed, "attested parent", 0)),
+        state_root: node(attested_state_root),
+        body_root: node(derive(seed, "attested body", 0)),
+    };
+    let signing_root = compute_digest(&add_left_right(
+        header_root(&attested_header),
+        &params.domain.to_vec(),
+    ));
+    SyncStepArgs {
+        signature_compressed: committee.sign(&signing_root, &params.participation),
+        pubkeys_uncompressed: committee.pubkeys_uncompressed(),
+        pariticipation_bits: params.participation.clone(),
+        attested_header,
+        finalized_header,
+        finality_branch,
+        execution_payload_root,
+        execution_payload_branch,
+        domain: params.domain,
+    }
+}
+
+/// A sync step signed by `committee`.
+pub fn generate_step(committee: &SyntheticCommittee, params: &StepParams) -> SyncStepArgs {
+    step_with_state_root(committee, params, derive(params.seed, "finalized state", 0))
+}
+
+/// A sync step signed by `committee` whose finalized state holds `next` as next sync committee,
+/// with the committee update proving it.
+pub fn generate_rotation(
+    committee: &SyntheticCommittee,
+    next: &SyntheticCommittee,
+    params: &StepParams,
+) -> (SyncStepArgs, CommitteeUpdateArgs) {
+    let (sync_committee_branch, finalized_state_root) = branch(
+        params.seed,
+        "sync committee branch",
+        next.pubkeys_root(),
+        SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX.ilog2() as usize,
+        SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+    );
+    let step = step_with_state_root(committee, params, finalized_state_root);
+    let finalized_header = &step.finalized_header;
+    let committee_update = CommitteeUpdateArgs {
+        pubkeys_compressed: next.pubkeys_compressed(),
+        finalized_header: CommitteeBeaconBlockHeader {
+            slot: finalized_header.slot.clone(),
+            proposer_index: finalized_header.proposer_index.clone(),
+            parent_root: finalized_header.parent_root,
+            state_root: finalized_header.state_root,
+            body_root: finalized_header.body_root,
+        },
+        sync_committee_branch,
+    };
+    (step, committee_update)
+}
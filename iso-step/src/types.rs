@@ -2,6 +2,7 @@ pub type Node = ssz_rs::Node;
 pub type ValidatorIndex = usize;
 use alloy_sol_types::sol;
 use borsh::{BorshDeserialize, BorshSerialize};
+use committee_iso::witness::{hex, hex_list};
 use serde::{Deserialize, Serialize};
 
 #[derive(
@@ -23,20 +24,27 @@ pub type Commitment = [u8; 32];
 
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct SyncStepArgs {
+    #[serde(with = "hex")]
     pub signature_compressed: SignatureCompressed,
+    #[serde(with = "hex_list")]
     pub pubkeys_uncompressed: PublicKeysUncompressed,
     pub pariticipation_bits: Vec<bool>,
     pub attested_header: BeaconBlockHeader,
     pub finalized_header: BeaconBlockHeader,
+    #[serde(with = "hex_list")]
     pub finality_branch: Branch,
+    #[serde(with = "hex")]
     pub execution_payload_root: Vec<u8>,
+    #[serde(with = "hex_list")]
     pub execution_payload_branch: Branch,
+    #[serde(with = "hex")]
     pub domain: [u8; 32],
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, BorshSerialize, BorshDeserialize)]
 pub struct SyncStepCircuitInput {
     pub args: SyncStepArgs,
+    #[serde(with = "hex")]
     pub commitment: [u8; 32],
 }
 
@@ -1,13 +1,18 @@
 use crate::types::SyncStepArgs;
-use std::{env, fs};
+use committee_iso::witness::WitnessFile;
+use std::env;
 
+/// Loads a step witness in any format of [`WitnessFile`], panicking on failure.
 pub fn load_circuit_args(path: &str) -> SyncStepArgs {
-    return serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+    match WitnessFile::load(path) {
+        Ok(file) => file.witness,
+        Err(e) => panic!("Failed to load {}: {}", path, e),
+    }
 }
 
 pub fn load_circuit_args_env() -> SyncStepArgs {
     let path = env::var("SYNC_STEP_TEST_PATH").unwrap_or("../data/sync_step_512.json".to_string());
-    return serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    load_circuit_args(&path)
 }
 
 #[cfg(test)]
@@ -0,0 +1,35 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, StoreError>;
+
+/// Reasons for rejecting a bootstrap or update, following the asserts of the consensus spec.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum StoreError {
+    #[error("bootstrap header does not match the trusted block root")]
+    UntrustedBootstrap,
+    #[error("malformed {0} header")]
+    InvalidHeader(&'static str),
+    #[error("{0} merkle proof verification failed")]
+    InvalidBranch(&'static str),
+    #[error("expected a sync committee of {expected} members, got {actual}")]
+    UnexpectedCommitteeSize { expected: usize, actual: usize },
+    #[error("{participants} participants are not enough to sign an update")]
+    InsufficientParticipation { participants: usize },
+    #[error("slots must satisfy current >= signature > attested >= finalized")]
+    InvalidSlots,
+    #[error("update signed in period {update_period} cannot be applied in period {store_period}")]
+    UnexpectedPeriod {
+        update_period: u64,
+        store_period: u64,
+    },
+    #[error("update does not advance the finalized header or the known committees")]
+    Irrelevant,
+    #[error("next sync committee does not match the known one")]
+    CommitteeMismatch,
+    #[error("invalid sync committee signature")]
+    InvalidSignature,
+}
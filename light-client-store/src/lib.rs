@@ -0,0 +1,647 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Off-chain light client following the sync protocol of the consensus spec
+//! (`specs/altair/light-client/sync-protocol.md`), built on the checks of the iso crates.
+//!
+//! The prover uses it to track the current and next sync committee and to pick the best
+//! update of every period before spending time on a proof.
+
+mod error;
+mod types;
+
+use committee_iso::{
+    types::PublicKeys,
+    utils::{hash_keys, is_valid_merkle_proof, merkleize_keys, uint64_to_le_256},
+};
+use eth_types::{domain_at_slot, Slot, Spec};
+use std::marker::PhantomData;
+use step_iso::{
+    try_decompress_keys, try_verify_aggregate_signature,
+    types::{BeaconBlockHeader, SyncStepArgs},
+};
+use types::slot;
+
+pub use error::{Result, StoreError};
+pub use types::{FinalityProof, LightClientBootstrap, LightClientUpdate, SyncCommitteeProof};
+
+pub const MIN_SYNC_COMMITTEE_PARTICIPANTS: usize = 1;
+
+fn period_at_slot<S: Spec>(slot: u64) -> u64 {
+    Slot(slot).period::<S>().0
+}
+
+/// Hash tree root of a beacon block header.
+pub fn header_root(header: &BeaconBlockHeader) -> Result<Vec<u8>> {
+    let (Ok(slot), Ok(proposer_index)) = (
+        header.slot.parse::<u64>(),
+        header.proposer_index.parse::<u64>(),
+    ) else {
+        return Err(StoreError::InvalidHeader("beacon block"));
+    };
+    Ok(merkleize_keys(vec![
+        uint64_to_le_256(slot),
+        uint64_to_le_256(proposer_index),
+        header.parent_root.to_vec(),
+        header.state_root.to_vec(),
+        header.body_root.to_vec(),
+    ]))
+}
+
+fn verify_branch(
+    branch: &[Vec<u8>],
+    leaf: Vec<u8>,
+    root: &[u8],
+    gindex: usize,
+    what: &'static str,
+) -> Result<()> {
+    if branch.len() != gindex.ilog2() as usize
+        || !is_valid_merkle_proof(branch.to_vec(), leaf, root, gindex)
+    {
+        return Err(StoreError::InvalidBranch(what));
+    }
+    Ok(())
+}
+
+fn verify_committee<S: Spec>(
+    committee: &SyncCommitteeProof,
+    state_root: &[u8],
+    gindex: usize,
+    what: &'static str,
+) -> Result<()> {
+    if committee.pubkeys.len() != S::SYNC_COMMITTEE_SIZE {
+        return Err(StoreError::UnexpectedCommitteeSize {
+            expected: S::SYNC_COMMITTEE_SIZE,
+            actual: committee.pubkeys.len(),
+        });
+    }
+    let pubkeys_root = merkleize_keys(hash_keys(committee.pubkeys.clone()));
+    verify_branch(&committee.branch, pubkeys_root, state_root, gindex, what)
+}
+
+#[derive(Debug, Clone)]
+pub struct LightClientStore<S: Spec> {
+    finalized_header: BeaconBlockHeader,
+    current_sync_committee: PublicKeys,
+    next_sync_committee: Option<PublicKeys>,
+    best_valid_update: Option<LightClientUpdate>,
+    optimistic_header: BeaconBlockHeader,
+    previous_max_active_participants: usize,
+    current_max_active_participants: usize,
+    _spec: PhantomData<S>,
+}
+
+impl<S: Spec> LightClientStore<S> {
+    /// Initializes the store from a bootstrap of the block with root `trusted_block_root`.
+    pub fn new(trusted_block_root: [u8; 32], bootstrap: LightClientBootstrap) -> Result<Self> {
+        if header_root(&bootstrap.header)? != trusted_block_root {
+            return Err(StoreError::UntrustedBootstrap);
+        }
+        // the current sync committee is the left sibling of the next one
+        let gindex = (S::SYNC_COMMITTEE_ROOT_INDEX - 1) * 2;
+        verify_committee::<S>(
+            &bootstrap.current_sync_committee,
+            bootstrap.header.state_root.as_ref(),
+            gindex,
+            "Current sync committee",
+        )?;
+        Ok(Self {
+            finalized_header: bootstrap.header.clone(),
+            current_sync_committee: bootstrap.current_sync_committee.pubkeys,
+            next_sync_committee: None,
+            best_valid_update: None,
+            optimistic_header: bootstrap.header,
+            previous_max_active_participants: 0,
+            current_max_active_participants: 0,
+            _spec: PhantomData,
+        })
+    }
+
+    pub fn finalized_header(&self) -> &BeaconBlockHeader {
+        &self.finalized_header
+    }
+
+    pub fn optimistic_header(&self) -> &BeaconBlockHeader {
+        &self.optimistic_header
+    }
+
+    pub fn current_sync_committee(&self) -> &PublicKeys {
+        &self.current_sync_committee
+    }
+
+    pub fn next_sync_committee(&self) -> Option<&PublicKeys> {
+        self.next_sync_committee.as_ref()
+    }
+
+    pub fn best_valid_update(&self) -> Option<&LightClientUpdate> {
+        self.best_valid_update.as_ref()
+    }
+
+    pub fn finalized_period(&self) -> u64 {
+        period_at_slot::<S>(slot(&self.finalized_header))
+    }
+
+    fn safety_threshold(&self) -> usize {
+        self.previous_max_active_participants
+            .max(self.current_max_active_participants)
+            / 2
+    }
+
+    /// Checks that `update` is signed by a known committee and can advance the store at `current_slot`.
+    pub fn validate_light_client_update(
+        &self,
+        update: &LightClientUpdate,
+        current_slot: u64,
+    ) -> Result<()> {
+        self.validate_with_next_sync_committee(
+            update,
+            current_slot,
+            self.next_sync_committee.as_ref(),
+        )
+    }
+
+    /// Checks that `update` proves the next sync committee the store follows and that it is
+    /// signed by the current one, with a finalized header in the finalized period of the store.
+    ///
+    /// The update does not have to advance the store, so the best update of the period can be
+    /// used to rotate even if the store took the next committee from an earlier one.
+    pub fn validate_next_sync_committee_update(
+        &self,
+        update: &LightClientUpdate,
+        current_slot: u64,
+    ) -> Result<()> {
+        let Some(next) = &self.next_sync_committee else {
+            return Err(StoreError::Irrelevant);
+        };
+        match &update.next_sync_committee {
+            Some(committee) if &committee.pubkeys == next => {}
+            Some(_) => return Err(StoreError::CommitteeMismatch),
+            None => return Err(StoreError::Irrelevant),
+        }
+        if update.finality.is_none() {
+            return Err(StoreError::Irrelevant);
+        }
+        // the rotation proves the committee against the finalized state, signed by the current one
+        let store_period = self.finalized_period();
+        for slot in [update.finalized_slot(), update.signature_slot] {
+            let update_period = period_at_slot::<S>(slot);
+            if update_period != store_period {
+                return Err(StoreError::UnexpectedPeriod {
+                    update_period,
+                    store_period,
+                });
+            }
+        }
+        // without a next committee the store takes it from an update of its finalized period
+        self.validate_with_next_sync_committee(update, current_slot, None)
+    }
+
+    fn validate_with_next_sync_committee(
+        &self,
+        update: &LightClientUpdate,
+        current_slot: u64,
+        next_sync_committee: Option<&PublicKeys>,
+    ) -> Result<()> {
+        let participants = update.participants();
+        if participants < MIN_SYNC_COMMITTEE_PARTICIPANTS {
+            return Err(StoreError::InsufficientParticipation { participants });
+        }
+        if update.participation.len() != S::SYNC_COMMITTEE_SIZE {
+            return Err(StoreError::UnexpectedCommitteeSize {
+                expected: S::SYNC_COMMITTEE_SIZE,
+                actual: update.participation.len(),
+            });
+        }
+        // rejects malformed headers before their slots are used
+        header_root(&update.attested_header)?;
+        let attested_slot = update.attested_slot();
+        let finalized_slot = update.finalized_slot();
+        if !(current_slot >= update.signature_slot
+            && update.signature_slot > attested_slot
+            && attested_slot >= finalized_slot)
+        {
+            return Err(StoreError::InvalidSlots);
+        }
+
+        let store_period = self.finalized_period();
+        let signature_period = period_at_slot::<S>(update.signature_slot);
+        let known_period = match next_sync_committee {
+            Some(_) => signature_period == store_period || signature_period == store_period + 1,
+            None => signature_period == store_period,
+        };
+        if !known_period {
+            return Err(StoreError::UnexpectedPeriod {
+                update_period: signature_period,
+                store_period,
+            });
+        }
+
+        let attested_period = period_at_slot::<S>(attested_slot);
+        let update_has_next_sync_committee = next_sync_committee.is_none()
+            && update.next_sync_committee.is_some()
+            && attested_period == store_period;
+        if attested_slot <= slot(&self.finalized_header) && !update_has_next_sync_committee {
+            return Err(StoreError::Irrelevant);
+        }
+
+        let attested_state_root = update.attested_header.state_root.to_vec();
+        if let Some(finality) = &update.finality {
+            verify_branch(
+                &finality.branch,
+                header_root(&finality.header)?,
+                &attested_state_root,
+                S::FINALIZED_HEADER_INDEX,
+                "Finality",
+            )?;
+        }
+        if let Some(committee) = &update.next_sync_committee {
+            if attested_period == store_period {
+                if let Some(next) = next_sync_committee {
+                    if next != &committee.pubkeys {
+                        return Err(StoreError::CommitteeMismatch);
+                    }
+                }
+            }
+            verify_committee::<S>(
+                committee,
+                &attested_state_root,
+                S::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+                "Next sync committee",
+            )?;
+        }
+
+        let committee = match next_sync_committee {
+            Some(next) if signature_period != store_period => next,
+            _ => &self.current_sync_committee,
+        };
+        let pubkeys_uncompressed =
+            try_decompress_keys(committee).ok_or(StoreError::InvalidSignature)?;
+        let args = SyncStepArgs {
+            signature_compressed: update.signature.clone(),
+            pubkeys_uncompressed,
+            pariticipation_bits: update.participation.clone(),
+            attested_header: update.attested_header.clone(),
+            finalized_header: BeaconBlockHeader::default(),
+            finality_branch: vec![],
+            execution_payload_root: vec![],
+            execution_payload_branch: vec![],
+            domain: domain_at_slot::<S>(update.signature_slot),
+        };
+        if try_verify_aggregate_signature(&args).is_none() {
+            return Err(StoreError::InvalidSignature);
+        }
+        Ok(())
+    }
+
+    /// Validates `update` and applies it once it is signed by a supermajority and finalizes
+    /// a newer header, otherwise keeps it as a candidate for [`Self::process_force_update`].
+    pub fn process_light_client_update(
+        &mut self,
+        update: LightClientUpdate,
+        current_slot: u64,
+    ) -> Result<()> {
+        self.validate_light_client_update(&update, current_slot)?;
+        let participants = update.participants();
+        if self
+            .best_valid_update
+            .as_ref()
+            .is_none_or(|best| is_better_update::<S>(&update, best))
+        {
+            self.best_valid_update = Some(update.clone());
+        }
+        self.current_max_active_participants =
+            self.current_max_active_participants.max(participants);
+        if participants > self.safety_threshold()
+            && update.attested_slot() > slot(&self.optimistic_header)
+        {
+            self.optimistic_header = update.attested_header.clone();
+        }
+
+        let update_has_finalized_next_sync_committee = self.next_sync_committee.is_none()
+            && update.next_sync_committee.is_some()
+            && update.finality.is_some()
+            && period_at_slot::<S>(update.finalized_slot())
+                == period_at_slot::<S>(update.attested_slot());
+        if participants * 3 >= update.participation.len() * 2
+            && (update.finalized_slot() > slot(&self.finalized_header)
+                || update_has_finalized_next_sync_committee)
+        {
+            if let Some(finality) = &update.finality {
+                self.apply_light_client_update(&update, &finality.header)?;
+                self.best_valid_update = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the best valid update if nothing was finalized for a sync committee period,
+    /// treating its attested header as finalized if needed. Returns whether an update was applied.
+    pub fn process_force_update(&mut self, current_slot: u64) -> Result<bool> {
+        if current_slot <= slot(&self.finalized_header) + S::SLOTS_PER_SYNC_COMMITTEE_PERIOD {
+            return Ok(false);
+        }
+        let Some(best) = self.best_valid_update.take() else {
+            return Ok(false);
+        };
+        let finalized_header = match &best.finality {
+            Some(finality) if slot(&finality.header) > slot(&self.finalized_header) => {
+                &finality.header
+            }
+            _ => &best.attested_header,
+        };
+        self.apply_light_client_update(&best, finalized_header)?;
+        Ok(true)
+    }
+
+    fn apply_light_client_update(
+        &mut self,
+        update: &LightClientUpdate,
+        finalized_header: &BeaconBlockHeader,
+    ) -> Result<()> {
+        let store_period = self.finalized_period();
+        let finalized_period = period_at_slot::<S>(slot(finalized_header));
+        let next_sync_committee = update
+            .next_sync_committee
+            .as_ref()
+            .map(|committee| committee.pubkeys.clone());
+        match self.next_sync_committee.take() {
+            None => {
+                if finalized_period != store_period {
+                    return Err(StoreError::UnexpectedPeriod {
+                        update_period: finalized_period,
+                        store_period,
+                    });
+                }
+                self.next_sync_committee = next_sync_committee;
+            }
+            Some(next) if finalized_period == store_period + 1 => {
+                self.current_sync_committee = next;
+                self.next_sync_committee = next_sync_committee;
+                self.previous_max_active_participants = self.current_max_active_participants;
+                self.current_max_active_participants = 0;
+            }
+            next => self.next_sync_committee = next,
+        }
+        if slot(finalized_header) > slot(&self.finalized_header) {
+            self.finalized_header = finalized_header.clone();
+            if slot(&self.finalized_header) > slot(&self.optimistic_header) {
+                self.optimistic_header = self.finalized_header.clone();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `new` should replace `old` as the best update of a period.
+pub fn is_better_update<S: Spec>(new: &LightClientUpdate, old: &LightClientUpdate) -> bool {
+    // compare supermajority (> 2/3) sync committee participation
+    let max_active_participants = new.participation.len();
+    let (new_participants, old_participants) = (new.participants(), old.participants());
+    let new_has_supermajority = new_participants * 3 >= max_active_participants * 2;
+    let old_has_supermajority = old_participants * 3 >= max_active_participants * 2;
+    if new_has_supermajority != old_has_supermajority {
+        return new_has_supermajority;
+    }
+    if !new_has_supermajority && new_participants != old_participants {
+        return new_participants > old_participants;
+    }
+
+    // compare presence of the relevant sync committee
+    let has_relevant_sync_committee = |update: &LightClientUpdate| {
+        update.next_sync_committee.is_some()
+            && period_at_slot::<S>(update.attested_slot())
+                == period_at_slot::<S>(update.signature_slot)
+    };
+    let new_has_relevant_sync_committee = has_relevant_sync_committee(new);
+    if new_has_relevant_sync_committee != has_relevant_sync_committee(old) {
+        return new_has_relevant_sync_committee;
+    }
+
+    // compare indication of any finality
+    let new_has_finality = new.finality.is_some();
+    if new_has_finality != old.finality.is_some() {
+        return new_has_finality;
+    }
+
+    // compare sync committee finality
+    if new_has_finality {
+        let has_sync_committee_finality = |update: &LightClientUpdate| {
+            period_at_slot::<S>(update.finalized_slot())
+                == period_at_slot::<S>(update.attested_slot())
+        };
+        let new_has_sync_committee_finality = has_sync_committee_finality(new);
+        if new_has_sync_committee_finality != has_sync_committee_finality(old) {
+            return new_has_sync_committee_finality;
+        }
+    }
+
+    // tiebreaker 1: sync committee participation beyond supermajority
+    if new_participants != old_participants {
+        return new_participants > old_participants;
+    }
+    // tiebreaker 2: prefer older data (fewer changes to best)
+    if new.attested_slot() != old.attested_slot() {
+        return new.attested_slot() < old.attested_slot();
+    }
+    new.signature_slot < old.signature_slot
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::{Fork, Testnet};
+    use step_iso::{compress_keys, utils::load_circuit_args};
+
+    /// Chain of `data/sync_step_512.json`, signed with the genesis version of mainnet.
+    #[derive(Copy, Clone, Debug, Default)]
+    struct Sample;
+
+    impl Spec for Sample {
+        const NAME: &'static str = "sample";
+        const NETWORK: &'static str = "sample";
+        const SYNC_COMMITTEE_SIZE: usize = Testnet::SYNC_COMMITTEE_SIZE;
+        const SYNC_COMMITTEE_ROOT_INDEX: usize = Testnet::SYNC_COMMITTEE_ROOT_INDEX;
+        const SYNC_COMMITTEE_DEPTH: usize = Testnet::SYNC_COMMITTEE_DEPTH;
+        const SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX: usize = Testnet::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX;
+        const SYNC_COMMITTEE_PUBKEYS_DEPTH: usize = Testnet::SYNC_COMMITTEE_PUBKEYS_DEPTH;
+        const DST: &'static [u8] = Testnet::DST;
+        const EXECUTION_STATE_ROOT_INDEX: usize = Testnet::EXECUTION_STATE_ROOT_INDEX;
+        const EXECUTION_STATE_ROOT_DEPTH: usize = Testnet::EXECUTION_STATE_ROOT_DEPTH;
+        const FINALIZED_HEADER_INDEX: usize = Testnet::FINALIZED_HEADER_INDEX;
+        const FINALIZED_HEADER_DEPTH: usize = Testnet::FINALIZED_HEADER_DEPTH;
+        const SLOTS_PER_EPOCH: u64 = Testnet::SLOTS_PER_EPOCH;
+        const EPOCHS_PER_SYNC_COMMITTEE_PERIOD: u64 = Testnet::EPOCHS_PER_SYNC_COMMITTEE_PERIOD;
+        const GENESIS_VALIDATORS_ROOT: [u8; 32] = Testnet::GENESIS_VALIDATORS_ROOT;
+        const FORKS: &'static [Fork] = &[Fork::new("phase0", [0, 0, 0, 0], 0)];
+    }
+
+    fn sample() -> (LightClientStore<Sample>, LightClientUpdate) {
+        let args = load_circuit_args("../data/sync_step_512.json");
+        let attested_slot = slot(&args.attested_header);
+        let store = LightClientStore {
+            finalized_header: args.finalized_header.clone(),
+            current_sync_committee: compress_keys(args.pubkeys_uncompressed),
+            next_sync_committee: None,
+            best_valid_update: None,
+            optimistic_header: args.finalized_header.clone(),
+            previous_max_active_participants: 0,
+            current_max_active_participants: 0,
+            _spec: PhantomData,
+        };
+        let update = LightClientUpdate {
+            attested_header: args.attested_header,
+            next_sync_committee: None,
+            finality: Some(FinalityProof {
+                header: args.finalized_header,
+                branch: args.finality_branch,
+            }),
+            participation: args.pariticipation_bits,
+            signature: args.signature_compressed,
+            signature_slot: attested_slot + 1,
+        };
+        (store, update)
+    }
+
+    fn update(attested_slot: u64, participants: usize) -> LightClientUpdate {
+        LightClientUpdate {
+            attested_header: BeaconBlockHeader {
+                slot: attested_slot.to_string(),
+                ..Default::default()
+            },
+            next_sync_committee: None,
+            finality: None,
+            participation: (0..512).map(|i| i < participants).collect(),
+            signature: vec![],
+            signature_slot: attested_slot + 1,
+        }
+    }
+
+    #[test]
+    fn test_validate_light_client_update() {
+        let (store, update) = sample();
+        let current_slot = update.signature_slot;
+        assert_eq!(
+            store.validate_light_client_update(&update, current_slot),
+            Ok(())
+        );
+        assert_eq!(
+            store.validate_light_client_update(&update, current_slot - 1),
+            Err(StoreError::InvalidSlots)
+        );
+
+        let mut tampered = update.clone();
+        tampered.finality.as_mut().unwrap().branch.pop();
+        assert_eq!(
+            store.validate_light_client_update(&tampered, current_slot),
+            Err(StoreError::InvalidBranch("Finality"))
+        );
+
+        let mut tampered = update.clone();
+        let participant = tampered.participation.iter().position(|bit| *bit).unwrap();
+        tampered.participation[participant] = false;
+        assert_eq!(
+            store.validate_light_client_update(&tampered, current_slot),
+            Err(StoreError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn test_process_and_force_update() {
+        let (mut store, update) = sample();
+        let attested_header = update.attested_header.clone();
+        store
+            .process_light_client_update(update.clone(), update.signature_slot)
+            .unwrap();
+        // the finalized header is not newer than the store's, so only the optimistic header moves
+        assert_eq!(store.optimistic_header(), &attested_header);
+        assert_eq!(store.best_valid_update(), Some(&update));
+        assert!(!store
+            .process_force_update(Sample::SLOTS_PER_SYNC_COMMITTEE_PERIOD)
+            .unwrap());
+        assert!(store
+            .process_force_update(Sample::SLOTS_PER_SYNC_COMMITTEE_PERIOD + 1)
+            .unwrap());
+        assert_eq!(store.finalized_header(), &attested_header);
+        assert_eq!(store.best_valid_update(), None);
+    }
+
+    #[test]
+    fn test_validate_next_sync_committee_update() {
+        let (mut store, mut update) = sample();
+        let current_slot = update.signature_slot;
+        let committee = store.current_sync_committee().clone();
+        update.next_sync_committee = Some(SyncCommitteeProof {
+            pubkeys: committee.clone(),
+            branch: vec![vec![0; 32]; Sample::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX.ilog2() as usize],
+        });
+        assert_eq!(
+            store.validate_next_sync_committee_update(&update, current_slot),
+            Err(StoreError::Irrelevant)
+        );
+
+        store.next_sync_committee = Some(committee.iter().rev().cloned().collect());
+        assert_eq!(
+            store.validate_next_sync_committee_update(&update, current_slot),
+            Err(StoreError::CommitteeMismatch)
+        );
+
+        // the update is checked like one the store takes the next committee from
+        store.next_sync_committee = Some(committee);
+        assert_eq!(
+            store.validate_next_sync_committee_update(&update, current_slot),
+            Err(StoreError::InvalidBranch("Next sync committee"))
+        );
+
+        let store_period = store.finalized_period();
+        let mut late = update.clone();
+        late.signature_slot = (store_period + 1) * Sample::SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+        assert_eq!(
+            store.validate_next_sync_committee_update(&late, late.signature_slot),
+            Err(StoreError::UnexpectedPeriod {
+                update_period: store_period + 1,
+                store_period,
+            })
+        );
+    }
+
+    #[test]
+    fn test_is_better_update() {
+        // supermajority wins over participation
+        assert!(is_better_update::<Testnet>(
+            &update(100, 342),
+            &update(100, 341)
+        ));
+        assert!(!is_better_update::<Testnet>(
+            &update(100, 300),
+            &update(100, 341)
+        ));
+
+        // a relevant next sync committee wins over finality
+        let mut with_committee = update(100, 400);
+        with_committee.next_sync_committee = Some(SyncCommitteeProof {
+            pubkeys: vec![],
+            branch: vec![],
+        });
+        let mut with_finality = update(100, 500);
+        with_finality.finality = Some(FinalityProof {
+            header: BeaconBlockHeader::default(),
+            branch: vec![],
+        });
+        assert!(is_better_update::<Testnet>(&with_committee, &with_finality));
+        assert!(is_better_update::<Testnet>(
+            &with_finality,
+            &update(100, 500)
+        ));
+
+        // ties prefer older updates
+        assert!(is_better_update::<Testnet>(
+            &update(100, 400),
+            &update(101, 400)
+        ));
+        assert!(!is_better_update::<Testnet>(
+            &update(101, 400),
+            &update(100, 400)
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+use committee_iso::types::{Branch, PublicKeys};
+use serde::{Deserialize, Serialize};
+use step_iso::types::BeaconBlockHeader;
+
+/// A sync committee together with its proof against a beacon state root.
+///
+/// As in [`committee_iso::types::CommitteeUpdateArgs`], the branch starts at the root of the
+/// compressed `pubkeys`, so its first node is the root of the aggregate public key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncCommitteeProof {
+    pub pubkeys: PublicKeys,
+    pub branch: Branch,
+}
+
+/// A finalized header and its proof against the attested state root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalityProof {
+    pub header: BeaconBlockHeader,
+    pub branch: Branch,
+}
+
+/// The header of a trusted block and its current sync committee.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightClientBootstrap {
+    pub header: BeaconBlockHeader,
+    pub current_sync_committee: SyncCommitteeProof,
+}
+
+/// A light client update, with the optional parts of the spec container as [`Option`]s.
+///
+/// A finality update is an update without `next_sync_committee`,
+/// an optimistic update has neither `next_sync_committee` nor `finality`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LightClientUpdate {
+    pub attested_header: BeaconBlockHeader,
+    pub next_sync_committee: Option<SyncCommitteeProof>,
+    pub finality: Option<FinalityProof>,
+    pub participation: Vec<bool>,
+    pub signature: Vec<u8>,
+    pub signature_slot: u64,
+}
+
+impl LightClientUpdate {
+    pub fn participants(&self) -> usize {
+        self.participation.iter().filter(|bit| **bit).count()
+    }
+
+    pub fn attested_slot(&self) -> u64 {
+        slot(&self.attested_header)
+    }
+
+    /// Slot of the finalized header, the genesis slot if the update carries no finality proof.
+    pub fn finalized_slot(&self) -> u64 {
+        self.finality
+            .as_ref()
+            .map(|finality| slot(&finality.header))
+            .unwrap_or_default()
+    }
+}
+
+/// Slot of a header, headers with malformed slots are rejected before their slot is used.
+pub(crate) fn slot(header: &BeaconBlockHeader) -> u64 {
+    header.slot.parse().unwrap_or_default()
+}
@@ -0,0 +1,29 @@
+#!/bin/bash
+# Fetches the minimal preset light_client vectors of consensus-spec-tests into data/consensus-spec-tests.
+# The release archive is checked against data/consensus-spec-tests.sha256, pass --pin to record its
+# checksum the first time, after comparing it with the one published by the release.
+set -euo pipefail
+
+VERSION=v1.4.0
+URL=https://github.com/ethereum/consensus-spec-tests/releases/download/$VERSION/minimal.tar.gz
+DATA=$(cd "$(dirname "$0")/../data" && pwd)
+CHECKSUM=$DATA/consensus-spec-tests.sha256
+
+archive=$(mktemp)
+trap 'rm -f "$archive"' EXIT
+curl -fL "$URL" -o "$archive"
+actual=$(sha256sum "$archive" | cut -d' ' -f1)
+
+if [ "${1:-}" = "--pin" ]; then
+  echo "$actual  minimal.tar.gz ($VERSION)" > "$CHECKSUM"
+  echo "pinned $actual"
+elif [ ! -f "$CHECKSUM" ]; then
+  echo "no pinned checksum at $CHECKSUM, rerun with --pin once $actual is confirmed" >&2
+  exit 1
+elif [ "$actual" != "$(cut -d' ' -f1 "$CHECKSUM")" ]; then
+  echo "checksum mismatch: $URL is $actual, pinned $(cut -d' ' -f1 "$CHECKSUM")" >&2
+  exit 1
+fi
+
+mkdir -p "$DATA/consensus-spec-tests"
+tar -xz -f "$archive" -C "$DATA/consensus-spec-tests" --wildcards 'tests/minimal/*/light_client/*'
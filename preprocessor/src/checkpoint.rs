@@ -0,0 +1,208 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Bootstraps from a trusted checkpoint instead of the block root reported by the beacon node.
+
+use crate::{
+    get_light_client_bootstrap, get_light_client_update_at_period,
+    store::{store_bootstrap, store_update},
+    BeaconSource, PreprocessorError, Result,
+};
+use committee_iso::utils::{commit_to_keys_with_sign, decode_pubkeys_x};
+use eth_types::{Slot, Spec};
+use ethereum_consensus_types::LightClientBootstrap;
+use itertools::Itertools;
+use light_client_store::LightClientStore;
+use serde::{Deserialize, Serialize};
+use ssz_rs::{Merkleized, Node};
+
+/// Initial state of the verifier contract, every field is verified against the trusted block root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub slot: u64,
+    pub finalized_header_root: [u8; 32],
+    pub active_committee_commitment: [u8; 32],
+    pub next_committee_commitment: [u8; 32],
+}
+
+/// Checks that `bootstrap` belongs to the block with root `trusted_block_root`
+/// and that its current sync committee is part of that block's state.
+pub fn verify_bootstrap<S: Spec>(
+    bootstrap: &LightClientBootstrap<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    >,
+    trusted_block_root: Node,
+) -> Result<()>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+{
+    let header_root = bootstrap
+        .header
+        .beacon
+        .clone()
+        .hash_tree_root()
+        .map_err(|e| PreprocessorError::decode("bootstrap header", e))?;
+    if header_root != trusted_block_root {
+        return Err(PreprocessorError::UntrustedBootstrap {
+            expected: format!("{trusted_block_root:?}"),
+            actual: format!("{header_root:?}"),
+        });
+    }
+
+    // the root over pubkeys and aggregate pubkey, not only the pubkeys
+    let committee_root = bootstrap
+        .current_sync_committee
+        .clone()
+        .hash_tree_root()
+        .map_err(|e| PreprocessorError::decode("current sync committee", e))?;
+    // the current sync committee is the left sibling of the next one
+    if ssz_rs::is_valid_merkle_branch(
+        committee_root,
+        &bootstrap
+            .current_sync_committee_branch
+            .iter()
+            .map(|n| n.as_ref())
+            .collect_vec(),
+        S::SYNC_COMMITTEE_DEPTH,
+        S::SYNC_COMMITTEE_ROOT_INDEX - 1,
+        bootstrap.header.beacon.state_root,
+    )
+    .is_err()
+    {
+        return Err(PreprocessorError::InvalidBranch("Current sync committee"));
+    }
+    Ok(())
+}
+
+/// Builds the [`Checkpoint`] to deploy the verifier contract at the block with root `trusted_block_root`.
+///
+/// The next committee is taken from the best update of the checkpoint's period,
+/// which must be signed by the verified current committee.
+pub async fn bootstrap_from_checkpoint<S: Spec, B: BeaconSource>(
+    source: &B,
+    trusted_block_root: [u8; 32],
+) -> Result<Checkpoint>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let block_root = Node::try_from(trusted_block_root.as_slice())
+        .map_err(|e| PreprocessorError::decode("block root", e))?;
+    let bootstrap = get_light_client_bootstrap::<S, _>(source, block_root).await?;
+    verify_bootstrap::<S>(&bootstrap, block_root)?;
+    let store = LightClientStore::<S>::new(trusted_block_root, store_bootstrap::<S>(&bootstrap)?)?;
+
+    let slot = bootstrap.header.beacon.slot;
+    let update =
+        get_light_client_update_at_period::<S, _>(source, Slot(slot).period::<S>().0).await?;
+    let update = store_update::<S>(&update)?;
+    store.validate_light_client_update(&update, update.signature_slot)?;
+    let next_sync_committee = update
+        .next_sync_committee
+        .ok_or(PreprocessorError::InvalidBranch("Next sync committee"))?;
+
+    Ok(Checkpoint {
+        slot,
+        finalized_header_root: trusted_block_root,
+        active_committee_commitment: commitment(store.current_sync_committee().clone()),
+        next_committee_commitment: commitment(next_sync_committee.pubkeys),
+    })
+}
+
+fn commitment(pubkeys_compressed: Vec<Vec<u8>>) -> [u8; 32] {
+    let (keys, signs) = decode_pubkeys_x(pubkeys_compressed);
+    commit_to_keys_with_sign(&keys, &signs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_committee_from_bytes;
+    use committee_iso::utils::{add_left_right, compute_digest};
+    use eth_types::Minimal;
+    use step_iso::testgen::SyntheticCommittee;
+
+    type Bootstrap = LightClientBootstrap<
+        { Minimal::SYNC_COMMITTEE_SIZE },
+        { Minimal::SYNC_COMMITTEE_DEPTH },
+        { Minimal::BYTES_PER_LOGS_BLOOM },
+        { Minimal::MAX_EXTRA_DATA_BYTES },
+    >;
+
+    /// A bootstrap of `committee` whose header commits to it, with the root of the header.
+    fn bootstrap(committee: &SyntheticCommittee) -> (Bootstrap, Node) {
+        let mut bootstrap = Bootstrap::default();
+        let pubkeys =
+            sync_committee_from_bytes::<Minimal>(&committee.pubkeys_compressed()).unwrap();
+        bootstrap.current_sync_committee.aggregate_pubkey = pubkeys[0].clone();
+        bootstrap.current_sync_committee.pubkeys = pubkeys;
+        let mut root = bootstrap
+            .current_sync_committee
+            .clone()
+            .hash_tree_root()
+            .unwrap()
+            .to_vec();
+        let mut index = Minimal::SYNC_COMMITTEE_ROOT_INDEX - 1;
+        for (i, sibling) in bootstrap
+            .current_sync_committee_branch
+            .iter_mut()
+            .enumerate()
+        {
+            *sibling = Node::try_from([i as u8 + 1; 32].as_slice()).unwrap();
+            root = if index & 1 == 0 {
+                compute_digest(&add_left_right(root, &sibling.to_vec()))
+            } else {
+                compute_digest(&add_left_right(sibling.to_vec(), &root))
+            };
+            index /= 2;
+        }
+        bootstrap.header.beacon.slot = 8 * Minimal::SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+        bootstrap.header.beacon.state_root = Node::try_from(root.as_slice()).unwrap();
+        let block_root = bootstrap.header.beacon.clone().hash_tree_root().unwrap();
+        (bootstrap, block_root)
+    }
+
+    #[test]
+    fn test_verify_bootstrap() {
+        let committee = SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 0);
+        let (bootstrap, block_root) = bootstrap(&committee);
+        assert!(verify_bootstrap::<Minimal>(&bootstrap, block_root).is_ok());
+
+        let mut other_header = bootstrap.header.beacon.clone();
+        other_header.proposer_index += 1;
+        let other_block_root = other_header.hash_tree_root().unwrap();
+        assert!(matches!(
+            verify_bootstrap::<Minimal>(&bootstrap, other_block_root),
+            Err(PreprocessorError::UntrustedBootstrap { .. })
+        ));
+
+        // the header still commits to the committee the bootstrap was built for
+        let other = SyntheticCommittee::new(Minimal::SYNC_COMMITTEE_SIZE, 1);
+        let mut wrong_committee = bootstrap.clone();
+        wrong_committee.current_sync_committee.pubkeys =
+            sync_committee_from_bytes::<Minimal>(&other.pubkeys_compressed()).unwrap();
+        assert!(matches!(
+            verify_bootstrap::<Minimal>(&wrong_committee, block_root),
+            Err(PreprocessorError::InvalidBranch("Current sync committee"))
+        ));
+
+        let mut tampered = bootstrap;
+        tampered.current_sync_committee_branch[2] = Node::default();
+        assert!(matches!(
+            verify_bootstrap::<Minimal>(&tampered, block_root),
+            Err(PreprocessorError::InvalidBranch("Current sync committee"))
+        ));
+    }
+}
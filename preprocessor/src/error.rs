@@ -0,0 +1,65 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+use std::fmt::Display;
+
+pub type Result<T> = std::result::Result<T, PreprocessorError>;
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PreprocessorError {
+    /// The beacon node has no data for this request (yet), e.g. an update for a future period.
+    #[error("no data available at {route}")]
+    NotFound { route: String },
+    #[error("failed to read {path}: {reason}")]
+    Io { path: String, reason: String },
+    #[error("request to {route} failed: {reason}")]
+    Http { route: String, reason: String },
+    /// The beacon node does not serve `route` in the requested encoding.
+    #[error("{route} is not available as {encoding}")]
+    UnsupportedEncoding { route: String, encoding: String },
+    /// Light client objects of this fork do not have the layout the preprocessor decodes.
+    #[error("{route} served a {fork} object, which is not supported")]
+    UnsupportedFork { route: String, fork: String },
+    #[error("failed to decode {what}: {reason}")]
+    Decode { what: String, reason: String },
+    #[error("{0} merkle proof verification failed")]
+    InvalidBranch(&'static str),
+    #[error("bootstrap header root {actual} does not match the trusted block root {expected}")]
+    UntrustedBootstrap { expected: String, actual: String },
+    #[error("expected an update for period {expected}, got period {actual}")]
+    NonContiguous { expected: u64, actual: u64 },
+    /// The light client store neither applied the update nor keeps it as its best valid update.
+    #[error("light client store has not validated the update attested at slot {slot}")]
+    Unvalidated { slot: u64 },
+    #[error("light client store rejected the update: {0}")]
+    Store(#[from] light_client_store::StoreError),
+    #[error("expected a sync committee of {expected} members, got {actual}")]
+    UnexpectedCommitteeSize { expected: usize, actual: usize },
+    /// Not enough beacon nodes agreed on the response.
+    #[error("beacon sources disagree on {route}: {}", responses.join("; "))]
+    Mismatch {
+        route: String,
+        responses: Vec<String>,
+    },
+}
+
+impl PreprocessorError {
+    pub fn http(route: impl Into<String>, reason: impl Display) -> Self {
+        Self::Http {
+            route: route.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn decode(what: impl Into<String>, reason: impl Display) -> Self {
+        Self::Decode {
+            what: what.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound { .. })
+    }
+}
@@ -0,0 +1,298 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Subscription to the light client topics of the beacon node event stream.
+
+use crate::{PreprocessorError, Result};
+use eth_types::{Slot, Spec};
+use log::warn;
+use reqwest::{header, Url};
+use serde_json::Value;
+use std::{marker::PhantomData, time::Duration};
+use tokio::sync::mpsc;
+
+const EVENTS_ROUTE: &str = "eth/v1/events";
+pub const FINALITY_UPDATE_TOPIC: &str = "light_client_finality_update";
+pub const OPTIMISTIC_UPDATE_TOPIC: &str = "light_client_optimistic_update";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightClientEvent {
+    FinalityUpdate {
+        attested_slot: u64,
+        finalized_slot: u64,
+        signature_slot: u64,
+    },
+    OptimisticUpdate {
+        attested_slot: u64,
+        signature_slot: u64,
+    },
+}
+
+impl LightClientEvent {
+    /// Parses the `data` of an event on one of the light client topics.
+    pub fn parse(topic: &str, data: &str) -> Result<Self> {
+        let value: Value =
+            serde_json::from_str(data).map_err(|e| PreprocessorError::decode(topic, e))?;
+        // some nodes wrap the update as `{"version": .., "data": ..}`
+        let update = value.get("data").unwrap_or(&value);
+        let slot = |pointer: &str| {
+            update
+                .pointer(pointer)
+                .and_then(Value::as_str)
+                .and_then(|slot| slot.parse::<u64>().ok())
+                .ok_or_else(|| PreprocessorError::decode(topic, format!("missing {pointer}")))
+        };
+        match topic {
+            FINALITY_UPDATE_TOPIC => Ok(Self::FinalityUpdate {
+                attested_slot: slot("/attested_header/beacon/slot")?,
+                finalized_slot: slot("/finalized_header/beacon/slot")?,
+                signature_slot: slot("/signature_slot")?,
+            }),
+            OPTIMISTIC_UPDATE_TOPIC => Ok(Self::OptimisticUpdate {
+                attested_slot: slot("/attested_header/beacon/slot")?,
+                signature_slot: slot("/signature_slot")?,
+            }),
+            _ => Err(PreprocessorError::decode(topic, "unknown topic")),
+        }
+    }
+
+    pub fn attested_slot(&self) -> u64 {
+        match self {
+            Self::FinalityUpdate { attested_slot, .. }
+            | Self::OptimisticUpdate { attested_slot, .. } => *attested_slot,
+        }
+    }
+}
+
+/// Why new witnesses should be generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// The chain finalized a new header.
+    Finality { finalized_slot: u64 },
+    /// The chain entered a new sync committee period, so a rotation may be due.
+    PeriodBoundary { period: u64 },
+}
+
+/// Turns the stream of [`LightClientEvent`]s into [`Trigger`]s, skipping repeated updates.
+#[derive(Debug, Default)]
+pub struct TriggerFilter<S: Spec> {
+    finalized_slot: u64,
+    period: Option<u64>,
+    _spec: PhantomData<S>,
+}
+
+impl<S: Spec> TriggerFilter<S> {
+    pub fn on_event(&mut self, event: &LightClientEvent) -> Option<Trigger> {
+        let period = Slot(event.attested_slot()).period::<S>().0;
+        let new_period = self.period.is_some_and(|previous| period > previous);
+        self.period = Some(self.period.map_or(period, |previous| previous.max(period)));
+        let new_finality = match *event {
+            LightClientEvent::FinalityUpdate { finalized_slot, .. }
+                if finalized_slot > self.finalized_slot =>
+            {
+                self.finalized_slot = finalized_slot;
+                Some(Trigger::Finality { finalized_slot })
+            }
+            _ => None,
+        };
+        if new_period {
+            return Some(Trigger::PeriodBoundary { period });
+        }
+        new_finality
+    }
+}
+
+/// Reconnect delays of the event stream, doubled after every failed attempt.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub initial: Duration,
+    pub max: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Subscribes to the light client topics of `endpoints`.
+///
+/// Connection losses are retried with `backoff`, moving on to the next endpoint on every attempt.
+/// The subscription ends once the returned receiver is dropped.
+pub fn subscribe(endpoints: Vec<Url>, backoff: Backoff) -> mpsc::Receiver<LightClientEvent> {
+    assert!(!endpoints.is_empty(), "at least one endpoint is required");
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let http = reqwest::Client::new();
+        let mut delay = backoff.initial;
+        for endpoint in endpoints.iter().cycle() {
+            let result = forward_events(&http, endpoint, &tx).await;
+            if tx.is_closed() {
+                return;
+            }
+            match result {
+                Ok(0) => warn!("Event stream of {} closed without events", endpoint),
+                Ok(_) => {
+                    warn!("Event stream of {} closed", endpoint);
+                    delay = backoff.initial;
+                }
+                Err(e) => warn!("Event stream of {} failed: {}", endpoint, e),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(backoff.max);
+        }
+    });
+    rx
+}
+
+/// Forwards events until the stream ends, returns how many were forwarded.
+async fn forward_events(
+    http: &reqwest::Client,
+    endpoint: &Url,
+    tx: &mpsc::Sender<LightClientEvent>,
+) -> Result<usize> {
+    let url = endpoint
+        .join(EVENTS_ROUTE)
+        .map_err(|e| PreprocessorError::http(EVENTS_ROUTE, e))?;
+    let mut response = http
+        .get(url)
+        .query(&[(
+            "topics",
+            format!("{FINALITY_UPDATE_TOPIC},{OPTIMISTIC_UPDATE_TOPIC}"),
+        )])
+        .header(header::ACCEPT, "text/event-stream")
+        .send()
+        .await
+        .map_err(|e| PreprocessorError::http(EVENTS_ROUTE, e))?;
+    if !response.status().is_success() {
+        return Err(PreprocessorError::http(EVENTS_ROUTE, response.status()));
+    }
+
+    let mut parser = SseParser::default();
+    let mut forwarded = 0;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| PreprocessorError::http(EVENTS_ROUTE, e))?
+    {
+        for (topic, data) in parser.push(&chunk) {
+            match LightClientEvent::parse(&topic, &data) {
+                Ok(event) => {
+                    if tx.send(event).await.is_err() {
+                        return Ok(forwarded);
+                    }
+                    forwarded += 1;
+                }
+                Err(e) => warn!("Skipping malformed event: {}", e),
+            }
+        }
+    }
+    Ok(forwarded)
+}
+
+/// Incremental parser for `text/event-stream` bodies.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: Vec<String>,
+}
+
+impl SseParser {
+    /// Consumes `chunk` and returns the `(event, data)` pairs completed by it.
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, String)> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = vec![];
+        while let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                if !self.data.is_empty() {
+                    events.push((std::mem::take(&mut self.event), self.data.join("\n")));
+                }
+                self.event.clear();
+                self.data.clear();
+                continue;
+            }
+            // lines starting with a colon are comments, e.g. keep-alives
+            let (field, value) = line.split_once(':').unwrap_or((line, ""));
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match field {
+                "event" => self.event = value.to_string(),
+                "data" => self.data.push(value.to_string()),
+                _ => {}
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eth_types::Testnet;
+
+    fn finality_update(attested_slot: u64, finalized_slot: u64) -> LightClientEvent {
+        LightClientEvent::FinalityUpdate {
+            attested_slot,
+            finalized_slot,
+            signature_slot: attested_slot + 1,
+        }
+    }
+
+    #[test]
+    fn test_sse_parser() {
+        let mut parser = SseParser::default();
+        assert!(parser
+            .push(b": keep-alive\n\nevent: light_client_fin")
+            .is_empty());
+        let events = parser.push(b"ality_update\r\ndata: {\"a\":\ndata: 1}\r\n\r\n");
+        assert_eq!(
+            events,
+            vec![(FINALITY_UPDATE_TOPIC.to_string(), "{\"a\":\n1}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let data = r#"{"version":"deneb","data":{
+            "attested_header":{"beacon":{"slot":"6897700"}},
+            "finalized_header":{"beacon":{"slot":"6897632"}},
+            "signature_slot":"6897701"}}"#;
+        assert_eq!(
+            LightClientEvent::parse(FINALITY_UPDATE_TOPIC, data).unwrap(),
+            LightClientEvent::FinalityUpdate {
+                attested_slot: 6897700,
+                finalized_slot: 6897632,
+                signature_slot: 6897701,
+            }
+        );
+        assert!(LightClientEvent::parse(OPTIMISTIC_UPDATE_TOPIC, "{}").is_err());
+    }
+
+    #[test]
+    fn test_trigger_filter() {
+        let mut filter = TriggerFilter::<Testnet>::default();
+        assert_eq!(
+            filter.on_event(&finality_update(6897600, 6897536)),
+            Some(Trigger::Finality {
+                finalized_slot: 6897536
+            })
+        );
+        assert_eq!(filter.on_event(&finality_update(6897601, 6897536)), None);
+        let optimistic = LightClientEvent::OptimisticUpdate {
+            attested_slot: 6897664,
+            signature_slot: 6897665,
+        };
+        assert_eq!(
+            filter.on_event(&optimistic),
+            Some(Trigger::PeriodBoundary { period: 842 })
+        );
+        assert_eq!(filter.on_event(&optimistic), None);
+    }
+}
@@ -5,12 +5,18 @@
 #![allow(incomplete_features)]
 #![feature(generic_const_exprs)]
 
+pub mod checkpoint;
+mod error;
+pub mod events;
+pub mod offline;
 mod rotation;
+mod source;
 mod step;
+pub mod store;
 
-use beacon_api_client::{BeaconHeaderSummary, BlockId, Client, ClientTypes, Value, VersionedValue};
+use beacon_api_client::{BeaconHeaderSummary, BlockId, Value};
 use committee_iso::utils::{commit_to_keys_with_sign, decode_pubkeys_x};
-use eth_types::Spec;
+use eth_types::{domain_at_slot, fork_at_slot, fork_by_digest, NetworkConfig, Slot, Spec};
 use ethereum_consensus_types::bls::BlsSignature;
 use ethereum_consensus_types::{
     BeaconBlockHeader, BlsPublicKey, ByteVector, LightClientBootstrap, LightClientFinalityUpdate,
@@ -21,21 +27,25 @@ use committee_iso::types::{BeaconBlockHeader as CommitteeBeaconBlockHeader, Comm
 use itertools::Itertools;
 use step_iso::types::SyncStepArgs;
 
-use beacon_api_client::mainnet::Client as MainnetClient;
-use beacon_api_client::StateId;
-use eth_types::Testnet;
-use ethereum_consensus_types::signing::{compute_domain, DomainType};
-use ethereum_consensus_types::ForkData;
-use reqwest::Url;
+pub use error::{PreprocessorError, Result};
 pub use rotation::*;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use source::{
+    combined_root, decode_ssz, fetch_json, fetch_ssz_or_json, json_root, split_response_chunks,
+    ssz_root,
+};
+pub use source::{BeaconSource, Encoding, MultiSource, Policy, RootFn};
 use ssz_rs::{Node, Vector};
+use std::future::Future;
 pub use step::*;
 
-pub async fn get_light_client_update_at_period<S: Spec, C: ClientTypes>(
-    client: &Client<C>,
+/// Beacon nodes serve at most this many updates per request.
+pub const MAX_REQUEST_LIGHT_CLIENT_UPDATES: u64 = 128;
+
+pub async fn get_light_client_update_at_period<S: Spec, B: BeaconSource>(
+    source: &B,
     period: u64,
-) -> eyre::Result<
+) -> Result<
     LightClientUpdateCapella<
         { S::SYNC_COMMITTEE_SIZE },
         { S::SYNC_COMMITTEE_ROOT_INDEX },
@@ -55,23 +65,200 @@ where
     [(); S::SYNC_COMMITTEE_DEPTH]:,
     [(); S::FINALIZED_HEADER_INDEX]:,
 {
-    let route = "eth/v1/beacon/light_client/updates";
-    let mut updates: Vec<VersionedValue<_>> = client
-        .http
-        .get(client.endpoint.join(route)?)
-        .query(&[("start_period", period), ("count", 1)])
-        .send()
+    get_light_client_updates::<S, _>(source, period, 1)
         .await?
-        .json()
-        .await?;
-    assert!(updates.len() == 1, "should only get one update");
-    Ok(updates.pop().unwrap().data)
+        .pop()
+        .ok_or(PreprocessorError::NotFound {
+            route: format!("eth/v1/beacon/light_client/updates?start_period={period}"),
+        })
+}
+
+/// Fetches the best updates of `count` consecutive periods starting at `start_period`,
+/// in pages of [`MAX_REQUEST_LIGHT_CLIENT_UPDATES`].
+///
+/// The result is ordered by period and stops early at the first period the node has no update for.
+pub async fn get_light_client_updates<S: Spec, B: BeaconSource>(
+    source: &B,
+    start_period: u64,
+    count: u64,
+) -> Result<
+    Vec<
+        LightClientUpdateCapella<
+            { S::SYNC_COMMITTEE_SIZE },
+            { S::SYNC_COMMITTEE_ROOT_INDEX },
+            { S::SYNC_COMMITTEE_DEPTH },
+            { S::FINALIZED_HEADER_INDEX },
+            { S::FINALIZED_HEADER_DEPTH },
+            { S::BYTES_PER_LOGS_BLOOM },
+            { S::MAX_EXTRA_DATA_BYTES },
+        >,
+    >,
+>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let route = "eth/v1/beacon/light_client/updates";
+    paginate(
+        start_period,
+        count,
+        |update: &LightClientUpdateCapella<_, _, _, _, _, _, _>| {
+            Slot(update.attested_header.beacon.slot).period::<S>().0
+        },
+        |period, page_count| async move {
+            fetch_ssz_or_json(
+                source,
+                route,
+                &[("start_period", period), ("count", page_count)],
+                |body| {
+                    split_response_chunks(route, body)?
+                        .into_iter()
+                        .map(|(digest, chunk)| {
+                            let fork = fork_by_digest::<S>(digest).ok_or_else(|| {
+                                PreprocessorError::decode(
+                                    route,
+                                    format!("unknown fork digest 0x{}", hex::encode(digest)),
+                                )
+                            })?;
+                            check_light_client_fork(route, fork.name)?;
+                            let update: LightClientUpdateCapella<_, _, _, _, _, _, _> =
+                                decode_ssz(route, chunk)?;
+                            // the context is the digest of the fork at the attested slot
+                            let expected = fork_at_slot::<S>(update.attested_header.beacon.slot);
+                            if fork != expected {
+                                return Err(PreprocessorError::decode(
+                                    route,
+                                    format!(
+                                        "fork digest of {} does not match {}",
+                                        fork.name, expected.name
+                                    ),
+                                ));
+                            }
+                            Ok(update)
+                        })
+                        .collect::<Result<Vec<_>>>()
+                },
+                |updates: Vec<Versioned>| {
+                    updates
+                        .into_iter()
+                        .map(|update| update.decode(route))
+                        .collect()
+                },
+                |updates: &Vec<_>| combined_root(updates.iter().map(ssz_root)),
+            )
+            .await
+        },
+    )
+    .await
+}
+
+/// Forks whose light client objects have the layout of the decoded types, i.e. the Deneb
+/// execution payload header and the branch depths of a [`Spec`], which Electra changed.
+///
+/// Updates, finality updates and bootstraps of other forks, Electra included, are rejected
+/// with [`PreprocessorError::UnsupportedFork`] until the Electra gindices are decoded.
+pub const LIGHT_CLIENT_FORKS: [&str; 1] = ["deneb"];
+
+fn check_light_client_fork(route: &str, fork: &str) -> Result<()> {
+    if !LIGHT_CLIENT_FORKS.contains(&fork) {
+        return Err(PreprocessorError::UnsupportedFork {
+            route: route.to_string(),
+            fork: fork.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Checks the fork of a single SSZ light client object, which carries no version, at the slot
+/// its header starts with: the variable sized header comes first and starts with its slot.
+fn check_ssz_light_client_fork<S: Spec>(route: &str, body: &[u8]) -> Result<()> {
+    let truncated = || PreprocessorError::decode(route, "truncated light client header");
+    let offset = body
+        .get(..4)
+        .and_then(|offset| offset.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or_else(truncated)? as usize;
+    let slot = body
+        .get(offset..offset.saturating_add(8))
+        .and_then(|slot| slot.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(truncated)?;
+    check_light_client_fork(route, fork_at_slot::<S>(slot).name)
+}
+
+/// A JSON light client object, decoded once its `version` is known to be supported.
+#[derive(Deserialize)]
+struct Versioned {
+    version: String,
+    data: serde_json::Value,
+}
+
+impl Versioned {
+    fn decode<T: DeserializeOwned>(self, route: &str) -> Result<T> {
+        check_light_client_fork(route, &self.version)?;
+        serde_json::from_value(self.data).map_err(|e| PreprocessorError::decode(route, e))
+    }
+}
+
+/// Requests `count` consecutive periods starting at `start_period` in pages of at most
+/// [`MAX_REQUEST_LIGHT_CLIENT_UPDATES`], until a page comes back short.
+async fn paginate<T, F, Fut>(
+    start_period: u64,
+    count: u64,
+    period_of: impl Fn(&T) -> u64,
+    mut fetch_page: F,
+) -> Result<Vec<T>>
+where
+    F: FnMut(u64, u64) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>>,
+{
+    let route = "eth/v1/beacon/light_client/updates";
+    let mut updates = vec![];
+    while (updates.len() as u64) < count {
+        let period = start_period + updates.len() as u64;
+        let page_count = (count - updates.len() as u64).min(MAX_REQUEST_LIGHT_CLIENT_UPDATES);
+        let page = match fetch_page(period, page_count).await {
+            Err(e) if e.is_not_found() => vec![],
+            page => page?,
+        };
+        if page.len() as u64 > page_count {
+            return Err(PreprocessorError::decode(
+                route,
+                format!("expected at most {page_count} updates, got {}", page.len()),
+            ));
+        }
+        check_contiguous(period, page.iter().map(&period_of))?;
+        let complete = page.len() as u64 == page_count;
+        updates.extend(page);
+        if !complete {
+            break;
+        }
+    }
+    Ok(updates)
+}
+
+/// Checks that `periods` counts up from `start_period` without gaps.
+fn check_contiguous(start_period: u64, periods: impl IntoIterator<Item = u64>) -> Result<()> {
+    for (expected, actual) in (start_period..).zip(periods) {
+        if expected != actual {
+            return Err(PreprocessorError::NonContiguous { expected, actual });
+        }
+    }
+    Ok(())
 }
 
-pub async fn get_light_client_bootstrap<S: Spec, C: ClientTypes>(
-    client: &Client<C>,
+/// Fetches the bootstrap of the block with root `block_root` as served, without verifying it.
+///
+/// Use [`checkpoint::verify_bootstrap`] when `block_root` is trusted.
+pub async fn get_light_client_bootstrap<S: Spec, B: BeaconSource>(
+    source: &B,
     block_root: Node,
-) -> eyre::Result<
+) -> Result<
     LightClientBootstrap<
         { S::SYNC_COMMITTEE_SIZE },
         { S::SYNC_COMMITTEE_DEPTH },
@@ -86,13 +273,23 @@ where
     [(); S::SYNC_COMMITTEE_DEPTH]:,
 {
     let route = format!("eth/v1/beacon/light_client/bootstrap/{block_root:?}");
-    let bootstrap = client.get::<VersionedValue<_>>(&route).await?.data;
-    Ok(bootstrap)
+    fetch_ssz_or_json(
+        source,
+        &route,
+        &[],
+        |body| {
+            check_ssz_light_client_fork::<S>(&route, body)?;
+            decode_ssz(&route, body)
+        },
+        |bootstrap: Versioned| bootstrap.decode(&route),
+        ssz_root,
+    )
+    .await
 }
 
-pub async fn get_light_client_finality_update<S: Spec, C: ClientTypes>(
-    client: &Client<C>,
-) -> eyre::Result<
+pub async fn get_light_client_finality_update<S: Spec, B: BeaconSource>(
+    source: &B,
+) -> Result<
     LightClientFinalityUpdate<
         { S::SYNC_COMMITTEE_SIZE },
         { S::FINALIZED_HEADER_DEPTH },
@@ -106,16 +303,50 @@ where
     [(); S::MAX_EXTRA_DATA_BYTES]:,
     [(); S::FINALIZED_HEADER_DEPTH]:,
 {
-    Ok(client
-        .get::<VersionedValue<_>>("eth/v1/beacon/light_client/finality_update")
-        .await?
-        .data)
+    let route = "eth/v1/beacon/light_client/finality_update";
+    fetch_ssz_or_json(
+        source,
+        route,
+        &[],
+        |body| {
+            check_ssz_light_client_fork::<S>(route, body)?;
+            decode_ssz(route, body)
+        },
+        |update: Versioned| update.decode(route),
+        ssz_root,
+    )
+    .await
+}
+
+/// Reads the genesis validators root and fork schedule of the network `source` follows,
+/// which devnets running a shared preset choose at genesis.
+pub async fn get_network_config<S: Spec, B: BeaconSource>(source: &B) -> Result<NetworkConfig> {
+    let root = |response: &Value<serde_json::Value>| json_root(&response.data);
+    let genesis = fetch_json(source, "eth/v1/beacon/genesis", &[], root).await?;
+    let spec = fetch_json(source, "eth/v1/config/spec", &[], root).await?;
+    NetworkConfig::from_beacon_api::<S>(&genesis.data, &spec.data)
+        .map_err(|e| PreprocessorError::decode("network config", e))
 }
 
-pub async fn get_block_header<C: ClientTypes>(
-    client: &Client<C>,
+pub async fn get_block_root<B: BeaconSource>(source: &B, id: BlockId) -> Result<Node> {
+    #[derive(Deserialize)]
+    struct BlockRoot {
+        root: Root,
+    }
+
+    let route = format!("eth/v1/beacon/blocks/{id}/root");
+    let block_root: BlockRoot = fetch_json(source, &route, &[], |block_root: &Value<BlockRoot>| {
+        Ok(block_root.data.root)
+    })
+    .await?
+    .data;
+    Ok(block_root.root)
+}
+
+pub async fn get_block_header<B: BeaconSource>(
+    source: &B,
     id: BlockId,
-) -> eyre::Result<BeaconBlockHeader> {
+) -> Result<BeaconBlockHeader> {
     // TODO: Once the ethereum beacon_api_client is updated, we can avoid this struct definition
     #[derive(Serialize, Deserialize)]
     struct BeaconHeaderSummary {
@@ -130,18 +361,28 @@ pub async fn get_block_header<C: ClientTypes>(
     }
 
     let route = format!("eth/v1/beacon/headers/{id}");
-    let block: BeaconHeaderSummary = client.get::<Value<_>>(&route).await?.data;
+    let block: BeaconHeaderSummary =
+        fetch_json(source, &route, &[], |block: &Value<BeaconHeaderSummary>| {
+            Ok(block.data.root)
+        })
+        .await?
+        .data;
     Ok(block.header.message)
 }
 
-pub async fn get_block_summary<C: ClientTypes>(
-    client: &Client<C>,
+pub async fn get_block_summary<B: BeaconSource>(
+    source: &B,
     id: BlockId,
-) -> eyre::Result<BeaconHeaderSummary> {
+) -> Result<BeaconHeaderSummary> {
     // TODO: Once the ethereum beacon_api_client is updated, we can avoid this struct definition
 
     let route = format!("eth/v1/beacon/headers/{id}");
-    let block: BeaconHeaderSummary = client.get::<Value<_>>(&route).await?.data;
+    let block: BeaconHeaderSummary =
+        fetch_json(source, &route, &[], |block: &Value<BeaconHeaderSummary>| {
+            Ok(block.data.root)
+        })
+        .await?
+        .data;
     Ok(block)
 }
 
@@ -157,7 +398,7 @@ pub async fn light_client_update_to_args<S: Spec>(
     >,
     pubkeys_compressed: Vector<BlsPublicKey, { S::SYNC_COMMITTEE_SIZE }>,
     domain: [u8; 32],
-) -> eyre::Result<(SyncStepArgs, CommitteeUpdateArgs)>
+) -> Result<(SyncStepArgs, CommitteeUpdateArgs)>
 where
     [(); S::SYNC_COMMITTEE_SIZE]:,
     [(); S::FINALIZED_HEADER_DEPTH]:,
@@ -167,17 +408,20 @@ where
     [(); S::SYNC_COMMITTEE_DEPTH]:,
     [(); S::FINALIZED_HEADER_INDEX]:,
 {
+    let finality_branch = update
+        .finality_branch
+        .iter()
+        .map(|v| {
+            Vector::try_from(v.to_vec())
+                .map(ByteVector)
+                .map_err(|_| PreprocessorError::decode("finality branch", "invalid node length"))
+        })
+        .collect::<Result<Vec<_>>>()?;
     let finality_update = LightClientFinalityUpdate {
         attested_header: update.attested_header.clone(),
         finalized_header: update.finalized_header.clone(),
-        finality_branch: Vector::try_from(
-            update
-                .finality_branch
-                .iter()
-                .map(|v| ByteVector(Vector::try_from(v.to_vec()).unwrap()))
-                .collect_vec(),
-        )
-        .unwrap(),
+        finality_branch: Vector::try_from(finality_branch)
+            .map_err(|_| PreprocessorError::decode("finality branch", "invalid depth"))?,
         sync_aggregate: update.sync_aggregate.clone(),
         signature_slot: update.signature_slot,
     };
@@ -190,18 +434,25 @@ where
 }
 
 /// Get the most recent sync step
-pub async fn get_current_sync_step() -> (SyncStepArgs, [u8; 32]) {
-    let client = MainnetClient::new(Url::parse("https://lodestar-sepolia.chainsafe.io").unwrap());
-    let finality_update = get_light_client_finality_update::<Testnet, _>(&client)
-        .await
-        .unwrap();
-    let block_root = client
-        .get_beacon_block_root(BlockId::Slot(finality_update.finalized_header.beacon.slot))
-        .await
-        .unwrap();
-    let bootstrap = get_light_client_bootstrap::<Testnet, _>(&client, block_root)
-        .await
-        .unwrap();
+pub async fn get_current_sync_step<S: Spec, B: BeaconSource>(
+    source: &B,
+) -> Result<(SyncStepArgs, [u8; 32])>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let finality_update = get_light_client_finality_update::<S, _>(source).await?;
+    let block_root = get_block_root(
+        source,
+        BlockId::Slot(finality_update.finalized_header.beacon.slot),
+    )
+    .await?;
+    let bootstrap = get_light_client_bootstrap::<S, _>(source, block_root).await?;
     let active_committee: Vec<Vec<u8>> = bootstrap
         .current_sync_committee
         .pubkeys
@@ -218,132 +469,289 @@ pub async fn get_current_sync_step() -> (SyncStepArgs, [u8; 32]) {
     for bit in bits {
         participation.push(bit);
     }
-    assert_eq!(participation.len(), 512);
+    if participation.len() != S::SYNC_COMMITTEE_SIZE {
+        return Err(PreprocessorError::UnexpectedCommitteeSize {
+            expected: S::SYNC_COMMITTEE_SIZE,
+            actual: participation.len(),
+        });
+    }
 
-    let fork_version = client
-        .get_fork(StateId::Head)
-        .await
-        .unwrap()
-        .current_version;
-    let genesis_validators_root = client
-        .get_genesis_details()
-        .await
-        .unwrap()
-        .genesis_validators_root;
-    let fork_data = ForkData {
-        genesis_validators_root,
-        fork_version,
-    };
-    let domain = compute_domain(DomainType::SyncCommittee, &fork_data).unwrap();
+    let domain = domain_at_slot::<S>(finality_update.signature_slot);
 
-    let sync_args = step::step_args_from_finality_update(
+    let sync_args = step::step_args_from_finality_update::<S>(
         finality_update,
         bootstrap.current_sync_committee.pubkeys,
         domain,
     )
-    .await
-    .unwrap();
-    (sync_args, commitment)
+    .await?;
+    Ok((sync_args, commitment))
+}
+
+/// Re-roots the next sync committee branch of a rotation witness at the finalized state.
+///
+/// `finalized_sync_committee_branch` is the current sync committee branch of a bootstrap
+/// taken at the finalized block of `step`, which shares all upper siblings with the branch we need.
+pub(crate) fn patch_committee_branch(
+    committee: &mut CommitteeUpdateArgs,
+    step: &SyncStepArgs,
+    mut finalized_sync_committee_branch: Vec<Vec<u8>>,
+) {
+    finalized_sync_committee_branch.insert(0, committee.sync_committee_branch[0].clone());
+    finalized_sync_committee_branch[1] = committee.sync_committee_branch[1].clone();
+    committee.sync_committee_branch = finalized_sync_committee_branch;
+    committee.finalized_header = CommitteeBeaconBlockHeader {
+        slot: step.finalized_header.clone().slot,
+        proposer_index: step.finalized_header.clone().proposer_index,
+        parent_root: step.finalized_header.clone().parent_root,
+        state_root: step.finalized_header.clone().state_root,
+        body_root: step.finalized_header.clone().body_root,
+    };
+}
+
+/// Fetches the sync committee of the period of `slot` from the bootstrap of the block at `slot`.
+pub async fn get_sync_committee_at_slot<S: Spec, B: BeaconSource>(
+    source: &B,
+    slot: u64,
+) -> Result<Vector<BlsPublicKey, { S::SYNC_COMMITTEE_SIZE }>>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+{
+    let block_root = get_block_root(source, BlockId::Slot(slot)).await?;
+    let bootstrap = get_light_client_bootstrap::<S, _>(source, block_root).await?;
+    Ok(bootstrap.current_sync_committee.pubkeys)
+}
+
+/// Decodes compressed public keys, e.g. those of a cached committee, into a sync committee.
+pub fn sync_committee_from_bytes<S: Spec>(
+    pubkeys: &[Vec<u8>],
+) -> Result<Vector<BlsPublicKey, { S::SYNC_COMMITTEE_SIZE }>>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+{
+    if pubkeys.len() != S::SYNC_COMMITTEE_SIZE {
+        return Err(PreprocessorError::UnexpectedCommitteeSize {
+            expected: S::SYNC_COMMITTEE_SIZE,
+            actual: pubkeys.len(),
+        });
+    }
+    decode_ssz("sync committee", &pubkeys.concat())
 }
 
 /// Gets the latest light client update
-pub async fn get_light_client_update_at_slot(
+///
+/// Returns [`PreprocessorError::NotFound`] if the node has no update for the period of `slot` yet.
+pub async fn get_light_client_update_at_slot<S: Spec, B: BeaconSource>(
+    source: &B,
     slot: u64,
-) -> (
-    Option<(SyncStepArgs, CommitteeUpdateArgs)>,
-    Option<Vec<Vec<u8>>>,
-) {
-    // hardcoded for now - todo: take client or url as arg
-    let client = MainnetClient::new(Url::parse("https://lodestar-sepolia.chainsafe.io").unwrap());
-    let period = slot / (32 * 256);
+) -> Result<((SyncStepArgs, CommitteeUpdateArgs), Vec<Vec<u8>>)>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let pubkeys_compressed = get_sync_committee_at_slot::<S, _>(source, slot).await?;
+    let oc = pubkeys_compressed
+        .iter()
+        .map(|pk| pk.to_bytes().to_vec())
+        .collect_vec();
+    let sc =
+        get_light_client_update_with_committee::<S, _>(source, slot, pubkeys_compressed).await?;
+    Ok((sc, oc))
+}
+
+/// Like [`get_light_client_update_at_slot`], with the sync committee of the period of `slot`
+/// already known, e.g. from a cache.
+pub async fn get_light_client_update_with_committee<S: Spec, B: BeaconSource>(
+    source: &B,
+    slot: u64,
+    pubkeys_compressed: Vector<BlsPublicKey, { S::SYNC_COMMITTEE_SIZE }>,
+) -> Result<(SyncStepArgs, CommitteeUpdateArgs)>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let period = Slot(slot).period::<S>().0;
     println!(
         "Fetching light client update at current Slot: {} at Period: {}",
         slot, period
     );
-    let (sc, oc): (
-        Option<(SyncStepArgs, CommitteeUpdateArgs)>,
-        Option<Vec<Vec<u8>>>,
-    ) = {
-        let update = match get_light_client_update_at_period(&client, period).await {
-            Ok(update) => Some(update),
-            Err(_) => return (None, None),
-        };
-        let block_root = client
-            .get_beacon_block_root(BlockId::Slot(slot))
-            .await
-            .unwrap();
-        let bootstrap = get_light_client_bootstrap(&client, block_root)
-            .await
-            .unwrap();
-        let pubkeys_compressed = bootstrap.current_sync_committee.pubkeys;
-        let oc = pubkeys_compressed
-            .iter()
-            .map(|pk| pk.to_bytes().to_vec())
-            .collect_vec();
-        let fork_version = client
-            .get_fork(StateId::Head)
-            .await
-            .unwrap()
-            .current_version;
-        let genesis_validators_root = client
-            .get_genesis_details()
-            .await
-            .unwrap()
-            .genesis_validators_root;
-        let fork_data = ForkData {
-            genesis_validators_root,
-            fork_version,
-        };
-        match update {
-            Some(_) => {}
-            None => return (None, None),
-        }
-        let domain = compute_domain(DomainType::SyncCommittee, &fork_data).unwrap();
-        (
-            Some(
-                light_client_update_to_args::<Testnet>(
-                    &update.unwrap(),
-                    pubkeys_compressed,
-                    domain,
-                )
-                .await
-                .unwrap(),
-            ),
-            Some(oc),
-        )
-    };
-
-    let s_unwrapped = sc.clone().unwrap().0;
-    let mut c_unwrapped = sc.unwrap().1;
+    let update = get_light_client_update_at_period::<S, _>(source, period).await?;
+    let domain = domain_at_slot::<S>(update.signature_slot);
+    let (s_unwrapped, mut c_unwrapped) =
+        light_client_update_to_args::<S>(&update, pubkeys_compressed, domain).await?;
 
-    let mut finalized_sync_committee_branch = {
-        let block_root = client
-            .get_beacon_block_root(BlockId::Slot(
-                u64::from_str_radix(&s_unwrapped.finalized_header.slot, 10).unwrap(),
-            ))
-            .await
-            .unwrap();
+    let finalized_sync_committee_branch = {
+        let finalized_slot = s_unwrapped
+            .finalized_header
+            .slot
+            .parse::<u64>()
+            .map_err(|e| PreprocessorError::decode("finalized header slot", e))?;
+        let block_root = get_block_root(source, BlockId::Slot(finalized_slot)).await?;
 
-        get_light_client_bootstrap::<Testnet, _>(&client, block_root)
-            .await
-            .unwrap()
+        get_light_client_bootstrap::<S, _>(source, block_root)
+            .await?
             .current_sync_committee_branch
             .iter()
             .map(|n| n.to_vec())
             .collect_vec()
     };
-    finalized_sync_committee_branch.insert(0, c_unwrapped.sync_committee_branch[0].clone());
-    finalized_sync_committee_branch[1] = c_unwrapped.sync_committee_branch[1].clone();
-    c_unwrapped.sync_committee_branch = finalized_sync_committee_branch;
-    c_unwrapped.finalized_header = CommitteeBeaconBlockHeader {
-        slot: s_unwrapped.finalized_header.clone().slot,
-        proposer_index: s_unwrapped.finalized_header.clone().proposer_index,
-        parent_root: s_unwrapped.finalized_header.clone().parent_root,
-        state_root: s_unwrapped.finalized_header.clone().state_root,
-        body_root: s_unwrapped.finalized_header.clone().body_root,
-    };
-    (Some((s_unwrapped, c_unwrapped)), oc)
+    patch_committee_branch(
+        &mut c_unwrapped,
+        &s_unwrapped,
+        finalized_sync_committee_branch,
+    );
+    Ok((s_unwrapped, c_unwrapped))
 }
 
 #[tokio::test]
 async fn test_query_sync_step() {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use committee_iso::constants::{FINALIZED_HEADER_INDEX, SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX};
+    use eth_types::{Chiado, Gnosis, Mainnet, Minimal, Testnet};
+
+    fn assert_gindices<S: Spec>() {
+        assert_eq!(
+            S::FINALIZED_HEADER_INDEX,
+            FINALIZED_HEADER_INDEX,
+            "{}",
+            S::NAME
+        );
+        assert_eq!(
+            S::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+            SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+            "{}",
+            S::NAME
+        );
+    }
+
+    #[test]
+    fn test_programs_match_presets() {
+        assert_gindices::<Minimal>();
+        assert_gindices::<Testnet>();
+        assert_gindices::<Mainnet>();
+        assert_gindices::<Gnosis>();
+        assert_gindices::<Chiado>();
+    }
+
+    #[test]
+    fn test_sync_committee_size() {
+        assert!(matches!(
+            sync_committee_from_bytes::<Minimal>(&[vec![0; 48]]),
+            Err(PreprocessorError::UnexpectedCommitteeSize {
+                expected: 32,
+                actual: 1
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_paginate() {
+        // the node knows periods up to 760
+        let requests = std::cell::RefCell::new(vec![]);
+        let updates = paginate(
+            600,
+            200,
+            |period: &u64| *period,
+            |period, count| {
+                requests.borrow_mut().push((period, count));
+                async move { Ok((period..(period + count).min(761)).collect()) }
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updates, (600..761).collect_vec());
+        assert_eq!(requests.into_inner(), vec![(600, 128), (728, 72)]);
+
+        // an update for the next period is only available once it started
+        let updates = paginate(
+            761,
+            1,
+            |period: &u64| *period,
+            |_, _| async {
+                Err(PreprocessorError::NotFound {
+                    route: "route".to_string(),
+                })
+            },
+        )
+        .await
+        .unwrap();
+        assert!(updates.is_empty());
+
+        assert!(paginate(
+            600,
+            2,
+            |period: &u64| *period,
+            |_, _| async { Ok(vec![600, 602]) }
+        )
+        .await
+        .is_err());
+        assert!(paginate(
+            600,
+            1,
+            |period: &u64| *period,
+            |_, _| async { Ok(vec![600, 601]) }
+        )
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn test_light_client_forks() {
+        let route = "eth/v1/beacon/light_client/updates";
+        let versioned = |version: &str| Versioned {
+            version: version.to_string(),
+            data: serde_json::json!(1),
+        };
+        assert_eq!(versioned("deneb").decode::<u64>(route).unwrap(), 1);
+        for fork in ["capella", "electra"] {
+            assert!(matches!(
+                versioned(fork).decode::<u64>(route),
+                Err(PreprocessorError::UnsupportedFork { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_ssz_light_client_forks() {
+        let route = "eth/v1/beacon/light_client/finality_update";
+        let at_slot = |slot: u64| [4u32.to_le_bytes().as_slice(), &slot.to_le_bytes()].concat();
+        // Sepolia forked to Electra at epoch 222464
+        assert!(check_ssz_light_client_fork::<Testnet>(route, &at_slot(222463 * 32)).is_ok());
+        assert!(matches!(
+            check_ssz_light_client_fork::<Testnet>(route, &at_slot(222464 * 32)),
+            Err(PreprocessorError::UnsupportedFork { fork, .. }) if fork == "electra"
+        ));
+        assert!(matches!(
+            check_ssz_light_client_fork::<Testnet>(route, &at_slot(0)[..6]),
+            Err(PreprocessorError::Decode { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_contiguous() {
+        assert!(check_contiguous(841, [841, 842, 843]).is_ok());
+        assert!(check_contiguous(841, []).is_ok());
+        assert!(matches!(
+            check_contiguous(841, [841, 843]),
+            Err(PreprocessorError::NonContiguous {
+                expected: 842,
+                actual: 843
+            })
+        ));
+    }
+}
@@ -0,0 +1,186 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Witness generation from light client objects saved to disk, without a beacon node.
+
+use crate::{
+    light_client_update_to_args, patch_committee_branch, step, Encoding, PreprocessorError, Result,
+};
+use committee_iso::{
+    types::CommitteeUpdateArgs,
+    utils::{commit_to_keys_with_sign, decode_pubkeys_x},
+};
+use eth_types::{domain_at_slot, Spec};
+use ethereum_consensus_types::{
+    BeaconBlockHeader, LightClientBootstrap, LightClientFinalityUpdate, LightClientUpdateCapella,
+};
+use itertools::Itertools;
+use serde::de::DeserializeOwned;
+use ssz_rs::{Deserialize as SszDeserialize, Merkleized};
+use std::path::Path;
+use step_iso::types::SyncStepArgs;
+
+impl Encoding {
+    /// Files ending in `.ssz` are read as SSZ, everything else as JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("ssz") => Self::Ssz,
+            _ => Self::Json,
+        }
+    }
+}
+
+/// Reads a light client object from `path`.
+///
+/// JSON files may hold a beacon API response (`{"version": .., "data": ..}`),
+/// a single element list as returned by `light_client/updates`, or the bare object.
+pub fn load<T>(path: impl AsRef<Path>) -> Result<T>
+where
+    T: DeserializeOwned + SszDeserialize,
+{
+    let path = path.as_ref();
+    let what = path.display().to_string();
+    let bytes = std::fs::read(path).map_err(|e| PreprocessorError::Io {
+        path: what.clone(),
+        reason: e.to_string(),
+    })?;
+    match Encoding::from_path(path) {
+        Encoding::Ssz => T::deserialize(&bytes).map_err(|e| PreprocessorError::decode(what, e)),
+        Encoding::Json => {
+            let mut value: serde_json::Value =
+                serde_json::from_slice(&bytes).map_err(|e| PreprocessorError::decode(&what, e))?;
+            if let serde_json::Value::Array(mut list) = value {
+                if list.len() != 1 {
+                    return Err(PreprocessorError::decode(
+                        what,
+                        format!("expected a single object, got {}", list.len()),
+                    ));
+                }
+                value = list.pop().unwrap();
+            }
+            if let Some(data) = value.get_mut("data") {
+                value = data.take();
+            }
+            serde_json::from_value(value).map_err(|e| PreprocessorError::decode(what, e))
+        }
+    }
+}
+
+/// Builds a [`SyncStepArgs`] witness from a `LightClientFinalityUpdate` and a `LightClientBootstrap`
+/// whose current sync committee signed the update.
+/// Also returns the commitment to that committee.
+pub async fn step_args_from_files<S: Spec>(
+    finality_update: impl AsRef<Path>,
+    bootstrap: impl AsRef<Path>,
+) -> Result<(SyncStepArgs, [u8; 32])>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+{
+    let finality_update: LightClientFinalityUpdate<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::FINALIZED_HEADER_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    > = load(finality_update)?;
+    let bootstrap: LightClientBootstrap<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    > = load(bootstrap)?;
+    let pubkeys_compressed = bootstrap.current_sync_committee.pubkeys;
+    let (keys, signs) = decode_pubkeys_x(
+        pubkeys_compressed
+            .iter()
+            .map(|pk| pk.to_bytes().to_vec())
+            .collect_vec(),
+    );
+    let commitment = commit_to_keys_with_sign(&keys, &signs);
+    let domain = domain_at_slot::<S>(finality_update.signature_slot);
+
+    let sync_args =
+        step::step_args_from_finality_update::<S>(finality_update, pubkeys_compressed, domain)
+            .await?;
+    Ok((sync_args, commitment))
+}
+
+/// Builds the witnesses of a committee rotation from a `LightClientUpdate`, mirroring
+/// [`crate::get_light_client_update_at_slot`].
+///
+/// `committee_bootstrap` provides the sync committee that signed the update,
+/// `finalized_bootstrap` must be taken at the finalized block of the update.
+/// Both are usually the same file.
+pub async fn rotation_args_from_files<S: Spec>(
+    update: impl AsRef<Path>,
+    committee_bootstrap: impl AsRef<Path>,
+    finalized_bootstrap: impl AsRef<Path>,
+) -> Result<((SyncStepArgs, CommitteeUpdateArgs), Vec<Vec<u8>>)>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let update: LightClientUpdateCapella<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_ROOT_INDEX },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::FINALIZED_HEADER_INDEX },
+        { S::FINALIZED_HEADER_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    > = load(update)?;
+    let committee_bootstrap: LightClientBootstrap<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    > = load(committee_bootstrap)?;
+    let finalized_bootstrap: LightClientBootstrap<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    > = load(finalized_bootstrap)?;
+    let header_root = |header: &BeaconBlockHeader| {
+        header
+            .clone()
+            .hash_tree_root()
+            .map_err(|e| PreprocessorError::decode("finalized header", e))
+    };
+    if header_root(&finalized_bootstrap.header.beacon)?
+        != header_root(&update.finalized_header.beacon)?
+    {
+        return Err(PreprocessorError::decode(
+            "finalized bootstrap",
+            "not taken at the finalized block of the update",
+        ));
+    }
+
+    let pubkeys_compressed = committee_bootstrap.current_sync_committee.pubkeys;
+    let oc = pubkeys_compressed
+        .iter()
+        .map(|pk| pk.to_bytes().to_vec())
+        .collect_vec();
+    let domain = domain_at_slot::<S>(update.signature_slot);
+    let (sync_args, mut committee_args) =
+        light_client_update_to_args::<S>(&update, pubkeys_compressed, domain).await?;
+    patch_committee_branch(
+        &mut committee_args,
+        &sync_args,
+        finalized_bootstrap
+            .current_sync_committee_branch
+            .iter()
+            .map(|n| n.to_vec())
+            .collect_vec(),
+    );
+    Ok(((sync_args, committee_args), oc))
+}
@@ -1,18 +1,21 @@
 // The Licensed Work is (c) 2023 ChainSafe
 // Code: https://github.com/ChainSafe/Spectre
 // SPDX-License-Identifier: LGPL-3.0-only
-use crate::{get_block_header, get_light_client_update_at_period};
-use beacon_api_client::{BlockId, Client, ClientTypes};
+use crate::{
+    get_block_header, get_light_client_update_at_period, get_light_client_updates, BeaconSource,
+    PreprocessorError, Result,
+};
+use beacon_api_client::BlockId;
 use committee_iso::types::CommitteeUpdateArgs;
-use eth_types::Spec;
+use eth_types::{Slot, Spec};
 use ethereum_consensus_types::LightClientUpdateCapella;
 use itertools::Itertools;
 use log::debug;
 use ssz_rs::{Merkleized, Node};
 /// Fetches LightClientUpdate from the beacon client and converts it to a [`CommitteeUpdateArgs`] witness
-pub async fn fetch_rotation_args<S: Spec, C: ClientTypes>(
-    client: &Client<C>,
-) -> eyre::Result<CommitteeUpdateArgs>
+pub async fn fetch_rotation_args<S: Spec, B: BeaconSource>(
+    source: &B,
+) -> Result<CommitteeUpdateArgs>
 where
     [(); S::SYNC_COMMITTEE_SIZE]:,
     [(); S::FINALIZED_HEADER_DEPTH]:,
@@ -22,18 +25,49 @@ where
     [(); S::SYNC_COMMITTEE_DEPTH]:,
     [(); S::FINALIZED_HEADER_INDEX]:,
 {
-    let block = get_block_header(client, BlockId::Head).await?;
+    let block = get_block_header(source, BlockId::Head).await?;
     let slot = block.slot;
-    let period = slot / (32 * 256);
+    let period = Slot(slot).period::<S>().0;
     debug!(
         "Fetching light client update at current Slot: {} at Period: {}",
         slot, period
     );
 
-    let update = get_light_client_update_at_period(client, period).await?;
+    let update = get_light_client_update_at_period(source, period).await?;
     rotation_args_from_update(&update).await
 }
 
+/// Fetches the updates of `count` consecutive periods starting at `start_period` and converts
+/// each of them to a [`CommitteeUpdateArgs`] witness, in period order.
+///
+/// Stops early at the first period the node has no update for, like [`get_light_client_updates`].
+pub async fn fetch_rotation_args_range<S: Spec, B: BeaconSource>(
+    source: &B,
+    start_period: u64,
+    count: u64,
+) -> Result<Vec<CommitteeUpdateArgs>>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let updates = get_light_client_updates::<S, _>(source, start_period, count).await?;
+    debug!(
+        "Fetched {} light client updates from Period: {}",
+        updates.len(),
+        start_period
+    );
+    let mut args = Vec::with_capacity(updates.len());
+    for update in &updates {
+        args.push(rotation_args_from_update::<S>(update).await?);
+    }
+    Ok(args)
+}
+
 /// Converts a [`LightClientUpdateCapella`] to a [`CommitteeUpdateArgs`] witness.
 pub async fn rotation_args_from_update<S: Spec>(
     update: &LightClientUpdateCapella<
@@ -45,7 +79,7 @@ pub async fn rotation_args_from_update<S: Spec>(
         { S::BYTES_PER_LOGS_BLOOM },
         { S::MAX_EXTRA_DATA_BYTES },
     >,
-) -> eyre::Result<CommitteeUpdateArgs>
+) -> Result<CommitteeUpdateArgs>
 where
     [(); S::SYNC_COMMITTEE_SIZE]:,
     [(); S::FINALIZED_HEADER_DEPTH]:,
@@ -70,23 +104,28 @@ where
             .next_sync_committee
             .aggregate_pubkey
             .hash_tree_root()
-            .unwrap(),
+            .map_err(|e| PreprocessorError::decode("aggregate pubkey", e))?,
     );
 
-    assert!(
-        ssz_rs::is_valid_merkle_branch::<&[u8]>(
-            update.next_sync_committee.pubkeys.hash_tree_root().unwrap(),
-            &sync_committee_branch
-                .iter()
-                .map(|n: &Node| n.as_ref())
-                .collect_vec(),
-            S::SYNC_COMMITTEE_PUBKEYS_DEPTH,
-            S::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
-            update.attested_header.beacon.state_root,
-        )
-        .is_ok(),
-        "Execution payload merkle proof verification failed"
-    );
+    let pubkeys_root = update
+        .next_sync_committee
+        .pubkeys
+        .hash_tree_root()
+        .map_err(|e| PreprocessorError::decode("next sync committee", e))?;
+    if ssz_rs::is_valid_merkle_branch::<&[u8]>(
+        pubkeys_root,
+        &sync_committee_branch
+            .iter()
+            .map(|n: &Node| n.as_ref())
+            .collect_vec(),
+        S::SYNC_COMMITTEE_PUBKEYS_DEPTH,
+        S::SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+        update.attested_header.beacon.state_root,
+    )
+    .is_err()
+    {
+        return Err(PreprocessorError::InvalidBranch("Next sync committee"));
+    }
 
     let args = CommitteeUpdateArgs {
         pubkeys_compressed,
@@ -0,0 +1,493 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+use crate::{PreprocessorError, Result};
+use beacon_api_client::{Client, ClientTypes};
+use committee_iso::utils::compute_digest;
+use eth_types::ForkDigest;
+use itertools::Itertools;
+use log::warn;
+use reqwest::{header, StatusCode};
+use serde::de::DeserializeOwned;
+use ssz_rs::{Deserialize as SszDeserialize, Merkleized, Node};
+use std::fmt;
+
+/// Derives the root that responses of different endpoints are compared by.
+pub type RootFn<'a> = &'a (dyn Fn(&[u8]) -> Result<Node> + Sync);
+
+/// Encoding of a beacon API response or of a light client object on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Beacon API JSON, either the full response or just its `data`.
+    Json,
+    /// Raw SSZ bytes, `application/octet-stream` on the wire.
+    Ssz,
+}
+
+impl Encoding {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Ssz => "application/octet-stream",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json => write!(f, "json"),
+            Self::Ssz => write!(f, "ssz"),
+        }
+    }
+}
+
+/// A provider of beacon API responses, backed by one or more beacon nodes.
+#[allow(async_fn_in_trait)]
+pub trait BeaconSource {
+    /// Human readable name of the source, used when reporting errors.
+    fn name(&self) -> String;
+
+    /// Sends a GET request to `route` and returns the raw response body in `encoding`.
+    /// A `404` response is reported as [`PreprocessorError::NotFound`], a node that cannot
+    /// answer in `encoding` as [`PreprocessorError::UnsupportedEncoding`].
+    async fn fetch(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> Result<Vec<u8>>;
+}
+
+impl<C: ClientTypes> BeaconSource for Client<C> {
+    fn name(&self) -> String {
+        self.endpoint.to_string()
+    }
+
+    async fn fetch(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        _root: RootFn<'_>,
+    ) -> Result<Vec<u8>> {
+        let url = self
+            .endpoint
+            .join(route)
+            .map_err(|e| PreprocessorError::http(route, e))?;
+        let response = self
+            .http
+            .get(url)
+            .query(query)
+            .header(header::ACCEPT, encoding.mime_type())
+            .send()
+            .await
+            .map_err(|e| PreprocessorError::http(route, e))?;
+        let unsupported = || PreprocessorError::UnsupportedEncoding {
+            route: route.to_string(),
+            encoding: encoding.to_string(),
+        };
+        match response.status() {
+            StatusCode::NOT_FOUND => {
+                return Err(PreprocessorError::NotFound {
+                    route: route.to_string(),
+                })
+            }
+            StatusCode::NOT_ACCEPTABLE | StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                return Err(unsupported())
+            }
+            status if !status.is_success() => return Err(PreprocessorError::http(route, status)),
+            _ => {}
+        }
+        // nodes without SSZ support may ignore the accept header
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        if encoding == Encoding::Ssz && !content_type.starts_with(encoding.mime_type()) {
+            return Err(unsupported());
+        }
+        Ok(response
+            .bytes()
+            .await
+            .map_err(|e| PreprocessorError::http(route, e))?
+            .to_vec())
+    }
+}
+
+/// How a [`MultiSource`] combines the answers of its endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Query all endpoints and accept a response once this many of them agree on its root.
+    Quorum(usize),
+    /// Query endpoints in order and return the first successful response.
+    Fallback,
+}
+
+/// Queries several beacon nodes and cross-checks their responses.
+pub struct MultiSource<B: BeaconSource> {
+    sources: Vec<B>,
+    policy: Policy,
+}
+
+impl<B: BeaconSource> MultiSource<B> {
+    pub fn new(sources: Vec<B>, policy: Policy) -> Self {
+        assert!(
+            !sources.is_empty(),
+            "at least one beacon source is required"
+        );
+        if let Policy::Quorum(quorum) = policy {
+            assert!(
+                quorum > 0 && quorum <= sources.len(),
+                "quorum must be between 1 and the number of sources"
+            );
+        }
+        Self { sources, policy }
+    }
+
+    async fn fetch_quorum(
+        &self,
+        quorum: usize,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> Result<Vec<u8>> {
+        let responses = futures::future::join_all(self.sources.iter().map(|source| async move {
+            let body = source.fetch(route, query, encoding, root).await?;
+            let body_root = root(&body)?;
+            Ok::<_, PreprocessorError>((body_root, body))
+        }))
+        .await;
+
+        let agreeing = responses
+            .iter()
+            .filter_map(|response| response.as_ref().ok())
+            .into_group_map_by(|(body_root, _)| body_root.as_ref().to_vec())
+            .into_values()
+            .max_by_key(|group| group.len());
+        let report = || {
+            self.sources
+                .iter()
+                .zip(&responses)
+                .map(|(source, response)| match response {
+                    Ok((body_root, _)) => format!("{}: {:?}", source.name(), body_root),
+                    Err(e) => format!("{}: {}", source.name(), e),
+                })
+                .collect_vec()
+        };
+
+        match agreeing {
+            Some(group) if group.len() >= quorum => {
+                if group.len() < self.sources.len() {
+                    warn!("Beacon sources disagree on {}: {:?}", route, report());
+                }
+                Ok(group[0].1.clone())
+            }
+            _ if responses
+                .iter()
+                .filter(|response| matches!(response, Err(e) if e.is_not_found()))
+                .count()
+                >= quorum =>
+            {
+                Err(PreprocessorError::NotFound {
+                    route: route.to_string(),
+                })
+            }
+            _ => Err(PreprocessorError::Mismatch {
+                route: route.to_string(),
+                responses: report(),
+            }),
+        }
+    }
+
+    async fn fetch_fallback(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> Result<Vec<u8>> {
+        let mut errors = vec![];
+        for source in &self.sources {
+            match source.fetch(route, query, encoding, root).await {
+                Ok(body) => return Ok(body),
+                Err(e) => {
+                    warn!("Beacon source {} failed on {}: {}", source.name(), route, e);
+                    errors.push(e);
+                }
+            }
+        }
+        if errors.iter().all(PreprocessorError::is_not_found) {
+            return Err(PreprocessorError::NotFound {
+                route: route.to_string(),
+            });
+        }
+        Err(errors
+            .pop()
+            .expect("at least one beacon source is required"))
+    }
+}
+
+impl<B: BeaconSource> BeaconSource for MultiSource<B> {
+    fn name(&self) -> String {
+        format!(
+            "[{}]",
+            self.sources.iter().map(BeaconSource::name).join(", ")
+        )
+    }
+
+    async fn fetch(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> Result<Vec<u8>> {
+        match self.policy {
+            Policy::Quorum(quorum) => {
+                self.fetch_quorum(quorum, route, query, encoding, root)
+                    .await
+            }
+            Policy::Fallback => self.fetch_fallback(route, query, encoding, root).await,
+        }
+    }
+}
+
+/// Fetches `route` from `source` and decodes the JSON response as `T`.
+/// `root` maps a decoded response to the root used for cross-checking.
+pub(crate) async fn fetch_json<T, B>(
+    source: &B,
+    route: &str,
+    query: &[(&str, u64)],
+    root: impl Fn(&T) -> Result<Node> + Sync,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+    B: BeaconSource,
+{
+    let body_root = |body: &[u8]| root(&decode_json(route, body)?);
+    let body = source
+        .fetch(route, query, Encoding::Json, &body_root)
+        .await?;
+    decode_json(route, &body)
+}
+
+/// Fetches `route` as SSZ, decoded by `from_ssz`, and falls back to JSON decoded as `J`
+/// if the source cannot serve SSZ.
+pub(crate) async fn fetch_ssz_or_json<T, J, B>(
+    source: &B,
+    route: &str,
+    query: &[(&str, u64)],
+    from_ssz: impl Fn(&[u8]) -> Result<T> + Sync,
+    from_json: impl Fn(J) -> Result<T> + Sync,
+    root: impl Fn(&T) -> Result<Node> + Sync,
+) -> Result<T>
+where
+    J: DeserializeOwned,
+    B: BeaconSource,
+{
+    let body_root = |body: &[u8]| root(&from_ssz(body)?);
+    match source
+        .fetch(route, query, Encoding::Ssz, &body_root)
+        .await
+        .and_then(|body| from_ssz(&body))
+    {
+        Ok(value) => return Ok(value),
+        // the JSON object is of the same fork
+        Err(e @ PreprocessorError::UnsupportedFork { .. }) => return Err(e),
+        Err(e) if e.is_not_found() => return Err(e),
+        Err(e) => warn!("Falling back to JSON for {}: {}", route, e),
+    }
+    let body_root = |body: &[u8]| root(&from_json(decode_json(route, body)?)?);
+    let body = source
+        .fetch(route, query, Encoding::Json, &body_root)
+        .await?;
+    from_json(decode_json(route, &body)?)
+}
+
+fn decode_json<T: DeserializeOwned>(route: &str, body: &[u8]) -> Result<T> {
+    serde_json::from_slice(body).map_err(|e| PreprocessorError::decode(route, e))
+}
+
+pub(crate) fn decode_ssz<T: SszDeserialize>(route: &str, body: &[u8]) -> Result<T> {
+    T::deserialize(body).map_err(|e| PreprocessorError::decode(route, e))
+}
+
+/// Splits a list response into its `response_chunk`s.
+///
+/// Each chunk is a little endian `u64` length, followed by that many bytes
+/// of fork digest context and SSZ payload.
+pub(crate) fn split_response_chunks<'a>(
+    route: &str,
+    mut body: &'a [u8],
+) -> Result<Vec<(ForkDigest, &'a [u8])>> {
+    let truncated = || PreprocessorError::decode(route, "truncated response chunk");
+    let mut chunks = vec![];
+    while !body.is_empty() {
+        let length = body
+            .get(..8)
+            .ok_or_else(truncated)?
+            .try_into()
+            .map(u64::from_le_bytes)
+            .map_err(|_| truncated())?;
+        // the length comes from the server, adding the prefix to it must not overflow
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| length.checked_add(8))
+            .ok_or_else(|| {
+                PreprocessorError::decode(
+                    route,
+                    format!("response chunk length {length} overflows"),
+                )
+            })?;
+        let chunk = body.get(8..end).ok_or_else(truncated)?;
+        let digest: ForkDigest = chunk
+            .get(..4)
+            .ok_or_else(truncated)?
+            .try_into()
+            .map_err(|_| truncated())?;
+        chunks.push((digest, &chunk[4..]));
+        body = &body[end..];
+    }
+    Ok(chunks)
+}
+
+pub(crate) fn ssz_root<T: Merkleized + Clone>(value: &T) -> Result<Node> {
+    value
+        .clone()
+        .hash_tree_root()
+        .map_err(|e| PreprocessorError::decode("hash tree root", e))
+}
+
+/// Commits to a JSON response that has no SSZ root, e.g. the config of the node.
+pub(crate) fn json_root(value: &serde_json::Value) -> Result<Node> {
+    let input = serde_json::to_vec(value).map_err(|e| PreprocessorError::decode("json root", e))?;
+    Node::try_from(compute_digest(&input).as_slice())
+        .map_err(|e| PreprocessorError::decode("json root", e))
+}
+
+/// Commits to a list of roots so that list responses can be compared as a whole.
+pub(crate) fn combined_root(roots: impl IntoIterator<Item = Result<Node>>) -> Result<Node> {
+    let mut input = vec![];
+    for root in roots {
+        input.extend_from_slice(root?.as_ref());
+    }
+    Node::try_from(compute_digest(&input).as_slice())
+        .map_err(|e| PreprocessorError::decode("combined root", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Static(&'static str, Result<Vec<u8>>);
+
+    impl BeaconSource for Static {
+        fn name(&self) -> String {
+            self.0.to_string()
+        }
+
+        async fn fetch(
+            &self,
+            _: &str,
+            _: &[(&str, u64)],
+            _: Encoding,
+            _: RootFn<'_>,
+        ) -> Result<Vec<u8>> {
+            self.1.clone()
+        }
+    }
+
+    fn first_byte_root(body: &[u8]) -> Result<Node> {
+        let mut root = [0u8; 32];
+        root[0] = body[0];
+        Ok(Node::try_from(root.as_slice()).unwrap())
+    }
+
+    fn not_found() -> Result<Vec<u8>> {
+        Err(PreprocessorError::NotFound {
+            route: "route".to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_quorum() {
+        let source = MultiSource::new(
+            vec![
+                Static("a", Ok(vec![1])),
+                Static("b", Ok(vec![2])),
+                Static("c", Ok(vec![1])),
+            ],
+            Policy::Quorum(2),
+        );
+        let body = source
+            .fetch("route", &[], Encoding::Json, &first_byte_root)
+            .await
+            .unwrap();
+        assert_eq!(body, vec![1]);
+
+        let source = MultiSource::new(
+            vec![
+                Static("a", Ok(vec![1])),
+                Static("b", Ok(vec![2])),
+                Static("c", not_found()),
+            ],
+            Policy::Quorum(2),
+        );
+        match source
+            .fetch("route", &[], Encoding::Json, &first_byte_root)
+            .await
+        {
+            Err(PreprocessorError::Mismatch { responses, .. }) => assert_eq!(responses.len(), 3),
+            other => panic!("expected a mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_split_response_chunks() {
+        let mut body = vec![];
+        for (digest, payload) in [([1, 2, 3, 4], vec![5, 6]), ([7, 8, 9, 10], vec![])] {
+            body.extend_from_slice(&(4 + payload.len() as u64).to_le_bytes());
+            body.extend_from_slice(&digest);
+            body.extend_from_slice(&payload);
+        }
+        let chunks = split_response_chunks("route", &body).unwrap();
+        assert_eq!(
+            chunks,
+            vec![
+                ([1, 2, 3, 4], [5, 6].as_slice()),
+                ([7, 8, 9, 10], [].as_slice())
+            ]
+        );
+        assert!(split_response_chunks("route", &body[..body.len() - 1]).is_err());
+        assert!(split_response_chunks("route", &u64::MAX.to_le_bytes()).is_err());
+        assert!(split_response_chunks("route", &(u64::MAX - 7).to_le_bytes()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        let source = MultiSource::new(
+            vec![Static("a", not_found()), Static("b", Ok(vec![2]))],
+            Policy::Fallback,
+        );
+        let body = source
+            .fetch("route", &[], Encoding::Json, &first_byte_root)
+            .await
+            .unwrap();
+        assert_eq!(body, vec![2]);
+
+        let source = MultiSource::new(
+            vec![Static("a", not_found()), Static("b", not_found())],
+            Policy::Fallback,
+        );
+        assert!(source
+            .fetch("route", &[], Encoding::Json, &first_byte_root)
+            .await
+            .unwrap_err()
+            .is_not_found());
+    }
+}
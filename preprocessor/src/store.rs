@@ -0,0 +1,415 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Keeps a [`LightClientStore`] in sync with a beacon node.
+
+use crate::{
+    get_block_header, get_block_root, get_light_client_bootstrap, get_light_client_finality_update,
+    get_light_client_update_at_period, get_light_client_updates, light_client_update_to_args,
+    patch_committee_branch, step::step_args_from_finality_update, sync_committee_from_bytes,
+    BeaconSource, PreprocessorError, Result, MAX_REQUEST_LIGHT_CLIENT_UPDATES,
+};
+use beacon_api_client::BlockId;
+use committee_iso::{
+    types::CommitteeUpdateArgs,
+    utils::{commit_to_keys_with_sign, decode_pubkeys_x},
+};
+use eth_types::{domain_at_slot, Slot, Spec};
+use ethereum_consensus_types::{
+    BeaconBlockHeader, LightClientBootstrap, LightClientFinalityUpdate, LightClientUpdateCapella,
+};
+use itertools::Itertools;
+use light_client_store::{
+    FinalityProof, LightClientStore, LightClientUpdate, StoreError, SyncCommitteeProof,
+};
+use log::{debug, warn};
+use ssz_rs::{Merkleized, Node};
+use step_iso::types::SyncStepArgs;
+
+fn header(beacon: &BeaconBlockHeader) -> step_iso::types::BeaconBlockHeader {
+    step_iso::types::BeaconBlockHeader {
+        slot: beacon.slot.to_string(),
+        proposer_index: beacon.proposer_index.to_string(),
+        parent_root: beacon.parent_root,
+        state_root: beacon.state_root,
+        body_root: beacon.body_root,
+    }
+}
+
+/// Branches of absent fields are zeroed in the beacon API.
+fn is_empty(branch: &[Vec<u8>]) -> bool {
+    branch.iter().flatten().all(|byte| *byte == 0)
+}
+
+/// Converts a `LightClientBootstrap` to the bootstrap of a [`LightClientStore`].
+pub fn store_bootstrap<S: Spec>(
+    bootstrap: &LightClientBootstrap<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    >,
+) -> Result<light_client_store::LightClientBootstrap>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+{
+    let mut branch = bootstrap
+        .current_sync_committee_branch
+        .iter()
+        .map(|n| n.to_vec())
+        .collect_vec();
+    branch.insert(
+        0,
+        bootstrap
+            .current_sync_committee
+            .aggregate_pubkey
+            .hash_tree_root()
+            .map_err(|e| PreprocessorError::decode("aggregate pubkey", e))?
+            .to_vec(),
+    );
+    Ok(light_client_store::LightClientBootstrap {
+        header: header(&bootstrap.header.beacon),
+        current_sync_committee: SyncCommitteeProof {
+            pubkeys: bootstrap
+                .current_sync_committee
+                .pubkeys
+                .iter()
+                .map(|pk| pk.to_bytes().to_vec())
+                .collect_vec(),
+            branch,
+        },
+    })
+}
+
+/// Converts a `LightClientUpdate` to the update of a [`LightClientStore`].
+pub fn store_update<S: Spec>(
+    update: &LightClientUpdateCapella<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::SYNC_COMMITTEE_ROOT_INDEX },
+        { S::SYNC_COMMITTEE_DEPTH },
+        { S::FINALIZED_HEADER_INDEX },
+        { S::FINALIZED_HEADER_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    >,
+) -> Result<LightClientUpdate>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let mut committee_branch = update
+        .next_sync_committee_branch
+        .iter()
+        .map(|n: &Node| n.to_vec())
+        .collect_vec();
+    let next_sync_committee = if is_empty(&committee_branch) {
+        None
+    } else {
+        committee_branch.insert(
+            0,
+            update
+                .next_sync_committee
+                .aggregate_pubkey
+                .hash_tree_root()
+                .map_err(|e| PreprocessorError::decode("aggregate pubkey", e))?
+                .to_vec(),
+        );
+        Some(SyncCommitteeProof {
+            pubkeys: update
+                .next_sync_committee
+                .pubkeys
+                .iter()
+                .map(|pk| pk.to_bytes().to_vec())
+                .collect_vec(),
+            branch: committee_branch,
+        })
+    };
+    let finality_branch = update
+        .finality_branch
+        .iter()
+        .map(|n| n.to_vec())
+        .collect_vec();
+    Ok(LightClientUpdate {
+        attested_header: header(&update.attested_header.beacon),
+        next_sync_committee,
+        finality: (!is_empty(&finality_branch)).then(|| FinalityProof {
+            header: header(&update.finalized_header.beacon),
+            branch: finality_branch,
+        }),
+        participation: update
+            .sync_aggregate
+            .sync_committee_bits
+            .iter()
+            .by_vals()
+            .collect_vec(),
+        signature: update
+            .sync_aggregate
+            .sync_committee_signature
+            .to_bytes()
+            .to_vec(),
+        signature_slot: update.signature_slot,
+    })
+}
+
+/// Converts a `LightClientFinalityUpdate` to the update of a [`LightClientStore`].
+pub fn store_finality_update<S: Spec>(
+    update: &LightClientFinalityUpdate<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::FINALIZED_HEADER_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    >,
+) -> LightClientUpdate
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+{
+    let finality_branch = update
+        .finality_branch
+        .iter()
+        .map(|n| n.0.to_vec())
+        .collect_vec();
+    LightClientUpdate {
+        attested_header: header(&update.attested_header.beacon),
+        next_sync_committee: None,
+        finality: (!is_empty(&finality_branch)).then(|| FinalityProof {
+            header: header(&update.finalized_header.beacon),
+            branch: finality_branch,
+        }),
+        participation: update
+            .sync_aggregate
+            .sync_committee_bits
+            .iter()
+            .by_vals()
+            .collect_vec(),
+        signature: update
+            .sync_aggregate
+            .sync_committee_signature
+            .to_bytes()
+            .to_vec(),
+        signature_slot: update.signature_slot,
+    }
+}
+
+/// Initializes a [`LightClientStore`] from the bootstrap of the block with root `trusted_block_root`.
+pub async fn bootstrap_store<S: Spec, B: BeaconSource>(
+    source: &B,
+    trusted_block_root: [u8; 32],
+) -> Result<LightClientStore<S>>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+{
+    let block_root = Node::try_from(trusted_block_root.as_slice())
+        .map_err(|e| PreprocessorError::decode("block root", e))?;
+    let bootstrap = get_light_client_bootstrap::<S, _>(source, block_root).await?;
+    Ok(LightClientStore::new(
+        trusted_block_root,
+        store_bootstrap::<S>(&bootstrap)?,
+    )?)
+}
+
+/// Feeds `store` with the best updates from its finalized period up to the head,
+/// then with the latest finality update, and forces an update if finality stalled.
+/// Returns the finality update, see [`step_args_from_store`].
+pub async fn sync_store<S: Spec, B: BeaconSource>(
+    source: &B,
+    store: &mut LightClientStore<S>,
+) -> Result<
+    LightClientFinalityUpdate<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::FINALIZED_HEADER_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    >,
+>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let current_slot = get_block_header(source, BlockId::Head).await?.slot;
+    process_updates(
+        source,
+        store,
+        Slot(current_slot).period::<S>().0,
+        current_slot,
+    )
+    .await?;
+
+    let finality_update = get_light_client_finality_update::<S, _>(source).await?;
+    match store
+        .process_light_client_update(store_finality_update::<S>(&finality_update), current_slot)
+    {
+        Ok(()) | Err(StoreError::Irrelevant) => {}
+        Err(e) => return Err(e.into()),
+    }
+    if store.process_force_update(current_slot)? {
+        warn!(
+            "Forced a light client update without finality at Slot: {}",
+            current_slot
+        );
+    }
+    Ok(finality_update)
+}
+
+/// Feeds `store` with the best updates from its finalized period up to `period`, e.g. to rotate
+/// a contract that is more than a period behind, see [`rotation_args_from_store`].
+pub async fn sync_store_to_period<S: Spec, B: BeaconSource>(
+    source: &B,
+    store: &mut LightClientStore<S>,
+    period: u64,
+) -> Result<()>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let current_slot = get_block_header(source, BlockId::Head).await?.slot;
+    process_updates(source, store, period, current_slot).await
+}
+
+async fn process_updates<S: Spec, B: BeaconSource>(
+    source: &B,
+    store: &mut LightClientStore<S>,
+    end_period: u64,
+    current_slot: u64,
+) -> Result<()>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let start_period = store.finalized_period();
+    let count = end_period
+        .saturating_sub(start_period)
+        .saturating_add(1)
+        .min(MAX_REQUEST_LIGHT_CLIENT_UPDATES);
+    for update in get_light_client_updates::<S, _>(source, start_period, count).await? {
+        match store.process_light_client_update(store_update::<S>(&update)?, current_slot) {
+            Ok(()) => {}
+            Err(StoreError::Irrelevant) => debug!("Skipping known light client update"),
+            // the store has to finalize the previous period first
+            Err(e @ StoreError::UnexpectedPeriod { .. }) => {
+                warn!("Stopping at light client update: {}", e);
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// Converts a finality update that `store` validated to a [`SyncStepArgs`] witness, signed by the
+/// sync committee the store follows. Returns the witness with the commitment to that committee.
+///
+/// The update is validated if its finalized header is the one of the store, or if it is the
+/// best valid update the store keeps while finality has no supermajority.
+pub async fn step_args_from_store<S: Spec>(
+    store: &LightClientStore<S>,
+    finality_update: LightClientFinalityUpdate<
+        { S::SYNC_COMMITTEE_SIZE },
+        { S::FINALIZED_HEADER_DEPTH },
+        { S::BYTES_PER_LOGS_BLOOM },
+        { S::MAX_EXTRA_DATA_BYTES },
+    >,
+) -> Result<(SyncStepArgs, [u8; 32])>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+{
+    let update = store_finality_update::<S>(&finality_update);
+    let finalized = update
+        .finality
+        .as_ref()
+        .is_some_and(|finality| &finality.header == store.finalized_header());
+    if !finalized && store.best_valid_update() != Some(&update) {
+        return Err(PreprocessorError::Unvalidated {
+            slot: update.attested_slot(),
+        });
+    }
+    // the next committee signs once the store finalized the last period of the current one
+    let committee = match store.next_sync_committee() {
+        Some(next) if Slot(update.signature_slot).period::<S>().0 != store.finalized_period() => {
+            next
+        }
+        _ => store.current_sync_committee(),
+    };
+    let (keys, signs) = decode_pubkeys_x(committee.clone());
+    let commitment = commit_to_keys_with_sign(&keys, &signs);
+    let args = step_args_from_finality_update::<S>(
+        finality_update,
+        sync_committee_from_bytes::<S>(committee)?,
+        domain_at_slot::<S>(update.signature_slot),
+    )
+    .await?;
+    Ok((args, commitment))
+}
+
+/// Builds the rotation out of the finalized period of `store` from the best update the beacon
+/// node serves for that period, once the store checked that it proves the next sync committee
+/// the store follows. The rotation is signed by the current sync committee of the store.
+///
+/// The committee branch is re-rooted at the finalized state with the bootstrap of the finalized
+/// block, which the rotation program checks against the finalized header of the update.
+pub async fn rotation_args_from_store<S: Spec, B: BeaconSource>(
+    source: &B,
+    store: &LightClientStore<S>,
+) -> Result<(SyncStepArgs, CommitteeUpdateArgs)>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let current_slot = get_block_header(source, BlockId::Head).await?.slot;
+    let update =
+        get_light_client_update_at_period::<S, _>(source, store.finalized_period()).await?;
+    store.validate_next_sync_committee_update(&store_update::<S>(&update)?, current_slot)?;
+    let (step, mut rotation) = light_client_update_to_args::<S>(
+        &update,
+        sync_committee_from_bytes::<S>(store.current_sync_committee())?,
+        domain_at_slot::<S>(update.signature_slot),
+    )
+    .await?;
+    let block_root =
+        get_block_root(source, BlockId::Slot(update.finalized_header.beacon.slot)).await?;
+    let finalized_sync_committee_branch = get_light_client_bootstrap::<S, _>(source, block_root)
+        .await?
+        .current_sync_committee_branch
+        .iter()
+        .map(|n| n.to_vec())
+        .collect_vec();
+    patch_committee_branch(&mut rotation, &step, finalized_sync_committee_branch);
+    Ok((step, rotation))
+}
@@ -0,0 +1,348 @@
+// The Licensed Work is (c) 2023 ChainSafe
+// Code: https://github.com/ChainSafe/Spectre
+// SPDX-License-Identifier: LGPL-3.0-only
+
+//! Runs the `light_client` suites of `ethereum/consensus-spec-tests` against the iso crates.
+//!
+//! - `single_merkle_proof` cases go through [`verify_merkle_proof`] against the root of the object.
+//! - `sync` cases verify the bootstrap and the branches of every update, track the committees
+//!   learned from them and check each signature with [`verify_aggregate_signature`].
+//!   Signatures use the genesis validators root of the vectors, so the expected store headers
+//!   of the `checks` are not compared.
+//! - `update_ranking` cases check that [`is_better_update`] never prefers a later update.
+//!
+//! Only the Capella suites of the minimal preset are run, the forks whose containers the
+//! preprocessor decodes. The vectors are not vendored, `fetch-spec-tests.sh` downloads the
+//! pinned release, so the tests are ignored unless run with `--ignored`.
+
+use committee_iso::{
+    constants::{FINALIZED_HEADER_INDEX, SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX},
+    utils::{
+        commit_to_keys_with_sign, decode_pubkeys_x, hash_keys, merkleize_keys, verify_merkle_proof,
+    },
+};
+use eth_types::{compute_domain, compute_fork_digest, Minimal, Slot, Spec, DOMAIN_SYNC_COMMITTEE};
+use ethereum_consensus::{
+    capella::minimal::{BeaconBlockBody, BeaconState},
+    ssz::prelude::{Deserialize as _, Merkleized as _},
+};
+use ethereum_consensus_types::{LightClientBootstrap, LightClientUpdateCapella};
+use light_client_store::{header_root, is_better_update, LightClientUpdate};
+use preprocessor::{
+    checkpoint::verify_bootstrap,
+    store::{store_bootstrap, store_update},
+};
+use serde::Deserialize;
+use ssz_rs::{Deserialize as SszDeserialize, Node};
+use std::{
+    collections::HashMap,
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::{Path, PathBuf},
+};
+use step_iso::{decompress_keys, types::SyncStepArgs, verify_aggregate_signature};
+
+const SPEC_TESTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../data/consensus-spec-tests");
+const FORK: &str = "capella";
+
+type CaseResult = std::result::Result<(), String>;
+
+type Bootstrap = LightClientBootstrap<
+    { Minimal::SYNC_COMMITTEE_SIZE },
+    { Minimal::SYNC_COMMITTEE_DEPTH },
+    { Minimal::BYTES_PER_LOGS_BLOOM },
+    { Minimal::MAX_EXTRA_DATA_BYTES },
+>;
+
+type Update = LightClientUpdateCapella<
+    { Minimal::SYNC_COMMITTEE_SIZE },
+    { Minimal::SYNC_COMMITTEE_ROOT_INDEX },
+    { Minimal::SYNC_COMMITTEE_DEPTH },
+    { Minimal::FINALIZED_HEADER_INDEX },
+    { Minimal::FINALIZED_HEADER_DEPTH },
+    { Minimal::BYTES_PER_LOGS_BLOOM },
+    { Minimal::MAX_EXTRA_DATA_BYTES },
+>;
+
+#[derive(Deserialize)]
+struct SingleMerkleProof {
+    leaf: String,
+    leaf_index: usize,
+    branch: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SyncMeta {
+    genesis_validators_root: String,
+    trusted_block_root: String,
+}
+
+#[derive(Deserialize)]
+struct ProcessUpdate {
+    update: String,
+    update_fork_digest: Option<String>,
+}
+
+/// A step of `steps.yaml`, forced updates and store upgrades are not replayed.
+#[derive(Deserialize)]
+struct SyncStep {
+    process_update: Option<ProcessUpdate>,
+}
+
+#[derive(Deserialize)]
+struct RankingMeta {
+    updates_count: usize,
+}
+
+/// Directory of the `light_client` suites, `CONSENSUS_SPEC_TESTS` overrides the vendored copy.
+fn suites() -> PathBuf {
+    let root = std::env::var("CONSENSUS_SPEC_TESTS").unwrap_or_else(|_| SPEC_TESTS.to_string());
+    let suites = Path::new(&root).join(format!("tests/minimal/{FORK}/light_client"));
+    assert!(
+        suites.exists(),
+        "missing consensus spec tests at {}, run preprocessor/fetch-spec-tests.sh",
+        suites.display()
+    );
+    suites
+}
+
+/// Case directories of `suite`, grouped by handler as in `<suite>/<handler>/<case>`.
+fn cases(suite: &str) -> Vec<PathBuf> {
+    let mut cases = std::fs::read_dir(suites().join(suite))
+        .into_iter()
+        .flatten()
+        .flat_map(|handler| std::fs::read_dir(handler.unwrap().path()).unwrap())
+        .map(|case| case.unwrap().path())
+        .filter(|case| case.is_dir())
+        .collect::<Vec<_>>();
+    cases.sort();
+    cases
+}
+
+/// Runs every case and reports all failures at once instead of stopping at the first.
+fn run(suite: &str, case: impl Fn(&Path) -> CaseResult) {
+    let cases = cases(suite);
+    assert!(!cases.is_empty(), "no {suite} cases found");
+    let mut failures = vec![];
+    for dir in &cases {
+        let name = dir.strip_prefix(suites()).unwrap().display().to_string();
+        // the iso crates report invalid proofs and signatures by panicking
+        let result = catch_unwind(AssertUnwindSafe(|| case(dir)))
+            .unwrap_or_else(|panic| Err(panic_message(panic)));
+        match result {
+            Ok(()) => println!("{name} ... ok"),
+            Err(reason) => {
+                println!("{name} ... FAILED: {reason}");
+                failures.push(format!("{name}: {reason}"));
+            }
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} {suite} cases failed:\n{}",
+        failures.len(),
+        cases.len(),
+        failures.join("\n")
+    );
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    panic
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_else(|| "panicked".to_string())
+}
+
+fn read(path: &Path) -> std::result::Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))
+}
+
+fn load_yaml<T: serde::de::DeserializeOwned>(path: &Path) -> std::result::Result<T, String> {
+    serde_yaml::from_slice(&read(path)?).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn load_snappy(path: &Path) -> std::result::Result<Vec<u8>, String> {
+    snap::raw::Decoder::new()
+        .decompress_vec(&read(path)?)
+        .map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn load_ssz<T: SszDeserialize>(path: &Path) -> std::result::Result<T, String> {
+    T::deserialize(&load_snappy(path)?).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+fn decode_hex(value: &str) -> std::result::Result<Vec<u8>, String> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|e| format!("{value}: {e}"))
+}
+
+fn decode_root(value: &str) -> std::result::Result<[u8; 32], String> {
+    decode_hex(value)?
+        .try_into()
+        .map_err(|_| format!("{value} is not a 32 byte root"))
+}
+
+fn commitment(pubkeys_compressed: &[Vec<u8>]) -> [u8; 32] {
+    let (keys, signs) = decode_pubkeys_x(pubkeys_compressed.to_vec());
+    commit_to_keys_with_sign(&keys, &signs)
+}
+
+fn single_merkle_proof(dir: &Path) -> CaseResult {
+    let proof: SingleMerkleProof = load_yaml(&dir.join("proof.yaml"))?;
+    let object = load_snappy(&dir.join("object.ssz_snappy"))?;
+    let handler = dir.parent().and_then(Path::file_name).unwrap_or_default();
+    let root = match handler.to_str() {
+        Some("BeaconState") => BeaconState::deserialize(&object)
+            .map_err(|e| e.to_string())?
+            .hash_tree_root(),
+        Some("BeaconBlockBody") => BeaconBlockBody::deserialize(&object)
+            .map_err(|e| e.to_string())?
+            .hash_tree_root(),
+        _ => return Err(format!("unsupported object {handler:?}")),
+    }
+    .map_err(|e| e.to_string())?;
+    let branch = proof
+        .branch
+        .iter()
+        .map(|node| decode_hex(node))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    verify_merkle_proof(
+        branch,
+        decode_hex(&proof.leaf)?,
+        &root.as_ref().to_vec(),
+        proof.leaf_index,
+    );
+    Ok(())
+}
+
+fn sync(dir: &Path) -> CaseResult {
+    let meta: SyncMeta = load_yaml(&dir.join("meta.yaml"))?;
+    let steps: Vec<SyncStep> = load_yaml(&dir.join("steps.yaml"))?;
+    let genesis_validators_root = decode_root(&meta.genesis_validators_root)?;
+    let trusted_block_root = decode_root(&meta.trusted_block_root)?;
+
+    let bootstrap: Bootstrap = load_ssz(&dir.join("bootstrap.ssz_snappy"))?;
+    let block_root = Node::try_from(trusted_block_root.as_slice()).map_err(|e| format!("{e:?}"))?;
+    verify_bootstrap::<Minimal>(&bootstrap, block_root).map_err(|e| e.to_string())?;
+    let bootstrap = store_bootstrap::<Minimal>(&bootstrap).map_err(|e| e.to_string())?;
+
+    // sync committees by period, learned from the bootstrap and the updates
+    let mut committees = HashMap::new();
+    committees.insert(
+        Slot(bootstrap.header.slot.parse().unwrap())
+            .period::<Minimal>()
+            .0,
+        bootstrap.current_sync_committee.pubkeys,
+    );
+    for ProcessUpdate {
+        update: name,
+        update_fork_digest,
+    } in steps.into_iter().filter_map(|step| step.process_update)
+    {
+        let raw: Update = load_ssz(&dir.join(format!("{name}.ssz_snappy")))?;
+        let update = store_update::<Minimal>(&raw).map_err(|e| e.to_string())?;
+        let version = match update_fork_digest {
+            Some(digest) => {
+                let digest = decode_hex(&digest)?;
+                Minimal::FORKS
+                    .iter()
+                    .find(|fork| {
+                        digest == compute_fork_digest(fork.version, genesis_validators_root)
+                    })
+                    .ok_or_else(|| format!("unknown fork digest of {name}"))?
+                    .version
+            }
+            None => {
+                Minimal::FORKS
+                    .iter()
+                    .find(|fork| fork.name == FORK)
+                    .unwrap()
+                    .version
+            }
+        };
+        let domain = compute_domain(DOMAIN_SYNC_COMMITTEE, version, genesis_validators_root);
+        sync_update(&mut committees, &update, domain)?;
+    }
+    Ok(())
+}
+
+/// Checks the branches and the signature of `update` the way the programs do.
+fn sync_update(
+    committees: &mut HashMap<u64, Vec<Vec<u8>>>,
+    update: &LightClientUpdate,
+    domain: [u8; 32],
+) -> CaseResult {
+    let attested_state_root = update.attested_header.state_root.to_vec();
+    let attested_period = Slot(update.attested_slot()).period::<Minimal>().0;
+    if let Some(committee) = &update.next_sync_committee {
+        verify_merkle_proof(
+            committee.branch.clone(),
+            merkleize_keys(hash_keys(committee.pubkeys.clone())),
+            &attested_state_root,
+            SYNC_COMMITTEE_PUBKEYS_ROOT_INDEX,
+        );
+        committees.insert(attested_period + 1, committee.pubkeys.clone());
+    }
+    if let Some(finality) = &update.finality {
+        verify_merkle_proof(
+            finality.branch.clone(),
+            header_root(&finality.header).map_err(|e| e.to_string())?,
+            &attested_state_root,
+            FINALIZED_HEADER_INDEX,
+        );
+    }
+
+    let signature_period = Slot(update.signature_slot).period::<Minimal>().0;
+    let committee = committees
+        .get(&signature_period)
+        .ok_or_else(|| format!("no sync committee known for period {signature_period}"))?;
+    let args = SyncStepArgs {
+        signature_compressed: update.signature.clone(),
+        pubkeys_uncompressed: decompress_keys(committee.clone()),
+        pariticipation_bits: update.participation.clone(),
+        attested_header: update.attested_header.clone(),
+        finalized_header: Default::default(),
+        finality_branch: vec![],
+        execution_payload_root: vec![],
+        execution_payload_branch: vec![],
+        domain,
+    };
+    verify_aggregate_signature(args, commitment(committee));
+    Ok(())
+}
+
+fn update_ranking(dir: &Path) -> CaseResult {
+    let meta: RankingMeta = load_yaml(&dir.join("meta.yaml"))?;
+    let updates = (0..meta.updates_count)
+        .map(|i| {
+            let update: Update = load_ssz(&dir.join(format!("updates_{i}.ssz_snappy")))?;
+            store_update::<Minimal>(&update).map_err(|e| e.to_string())
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    // ordered from best to worst
+    for (i, better) in updates.iter().enumerate() {
+        for (j, worse) in updates.iter().enumerate().skip(i + 1) {
+            if is_better_update::<Minimal>(worse, better) {
+                return Err(format!("update {j} ranked above update {i}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[test]
+#[ignore = "needs the vectors fetched by `fetch-spec-tests.sh`"]
+fn test_single_merkle_proof() {
+    run("single_merkle_proof", single_merkle_proof);
+}
+
+#[test]
+#[ignore = "needs the vectors fetched by `fetch-spec-tests.sh`"]
+fn test_sync() {
+    run("sync", sync);
+}
+
+#[test]
+#[ignore = "needs the vectors fetched by `fetch-spec-tests.sh`"]
+fn test_update_ranking() {
+    run("update_ranking", update_ranking);
+}
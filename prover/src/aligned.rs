@@ -0,0 +1,439 @@
+use crate::{
+    backend::BackendResult,
+    proof::{CompressedProof, Program},
+    SpectreProver,
+};
+use aligned_sdk::{
+    core::types::{
+        AlignedVerificationData, Network, PriceEstimate, ProvingSystemId, VerificationData,
+    },
+    sdk::{estimate_fee, get_next_nonce, is_proof_verified, submit_multiple},
+};
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::Address,
+    utils::keccak256,
+};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1_CIRCUIT_VERSION};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// A compressed proof as Aligned verifies it: against the ELF of its program, with the public
+/// values as public input.
+pub struct AlignedSubmission {
+    pub program: Program,
+    pub proof: SP1ProofWithPublicValues,
+    /// Address that is credited with the proof
+    pub proof_generator: [u8; 20],
+}
+
+impl AlignedSubmission {
+    pub fn new(compressed: &CompressedProof, proof_generator: [u8; 20]) -> Self {
+        Self {
+            program: compressed.program,
+            proof: compressed.proof.clone(),
+            proof_generator,
+        }
+    }
+
+    /// Commitment to the submission, the leaf of the batch it is included in.
+    pub fn commitment(&self) -> BackendResult<VerificationCommitment> {
+        Ok(VerificationCommitment {
+            proof_commitment: keccak256(bincode::serialize(&self.proof)?),
+            pub_input_commitment: keccak256(self.proof.public_values.as_slice()),
+            program_id_commitment: keccak256(self.program.elf()),
+            proof_generator: self.proof_generator,
+        })
+    }
+
+    fn verification_data(&self) -> BackendResult<VerificationData> {
+        Ok(VerificationData {
+            proving_system: ProvingSystemId::SP1,
+            proof: bincode::serialize(&self.proof)?,
+            public_input: Some(self.proof.public_values.to_vec()),
+            verification_key: None,
+            vm_program_code: Some(self.program.elf().to_vec()),
+            proof_generator_addr: Address::from(self.proof_generator),
+        })
+    }
+}
+
+/// What Aligned commits to for every proof in a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationCommitment {
+    pub proof_commitment: [u8; 32],
+    pub pub_input_commitment: [u8; 32],
+    /// Commitment to the ELF of the program, the proving system aux data of an SP1 proof
+    pub program_id_commitment: [u8; 32],
+    pub proof_generator: [u8; 20],
+}
+
+impl VerificationCommitment {
+    pub fn leaf(&self) -> [u8; 32] {
+        keccak256(
+            [
+                self.proof_commitment.as_slice(),
+                &self.pub_input_commitment,
+                &self.program_id_commitment,
+                &self.proof_generator,
+            ]
+            .concat(),
+        )
+    }
+}
+
+/// Where a proof was included in a batch, the arguments of `verifyBatchInclusion`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchInclusion {
+    pub commitment: VerificationCommitment,
+    pub batch_merkle_root: [u8; 32],
+    pub merkle_path: Vec<[u8; 32]>,
+    pub index_in_batch: usize,
+}
+
+impl BatchInclusion {
+    /// Recomputes the batch root from the merkle path, like the Aligned service manager does.
+    pub fn verify(&self) -> bool {
+        let mut index = self.index_in_batch;
+        let root = self
+            .merkle_path
+            .iter()
+            .fold(self.commitment.leaf(), |node, sibling| {
+                let parent = if index % 2 == 1 {
+                    hash_pair(sibling, &node)
+                } else {
+                    hash_pair(&node, sibling)
+                };
+                index /= 2;
+                parent
+            });
+        root == self.batch_merkle_root
+    }
+
+    /// The merkle path as the `merkleProof` bytes of `verifyBatchInclusion`.
+    pub fn merkle_proof(&self) -> Vec<u8> {
+        self.merkle_path.concat()
+    }
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak256([left.as_slice(), right].concat())
+}
+
+/// Batches commitments into a keccak merkle tree, the last leaf is repeated up to a power of two.
+pub fn batch(commitments: &[VerificationCommitment]) -> Vec<BatchInclusion> {
+    let Some(last) = commitments.last() else {
+        return vec![];
+    };
+    let mut layer: Vec<[u8; 32]> = commitments.iter().map(|c| c.leaf()).collect();
+    layer.resize(commitments.len().next_power_of_two(), last.leaf());
+    let mut paths = vec![vec![]; commitments.len()];
+    while layer.len() > 1 {
+        for (index, path) in paths.iter_mut().enumerate() {
+            path.push(layer[(index >> path.len()) ^ 1]);
+        }
+        layer = layer
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], &pair[1]))
+            .collect();
+    }
+    commitments
+        .iter()
+        .zip(paths)
+        .enumerate()
+        .map(
+            |(index_in_batch, (commitment, merkle_path))| BatchInclusion {
+                commitment: *commitment,
+                batch_merkle_root: layer[0],
+                merkle_path,
+                index_in_batch,
+            },
+        )
+        .collect()
+}
+
+/// A batcher that aggregates proofs into batches that are verified on Ethereum.
+#[allow(async_fn_in_trait)]
+pub trait Batcher {
+    /// Submits proofs to be batched, returns where they were included once the batch was sent.
+    async fn submit(&self, submissions: &[AlignedSubmission])
+        -> BackendResult<Vec<BatchInclusion>>;
+
+    /// Whether the batch of an inclusion was verified on Ethereum.
+    async fn is_verified(&self, inclusion: &BatchInclusion) -> BackendResult<bool>;
+}
+
+/// Submits proofs and polls every `poll_interval` until their batches are verified.
+pub async fn submit_and_wait<B: Batcher>(
+    batcher: &B,
+    submissions: &[AlignedSubmission],
+    poll_interval: Duration,
+    timeout: Duration,
+) -> BackendResult<Vec<BatchInclusion>> {
+    let inclusions = batcher.submit(submissions).await?;
+    let deadline = Instant::now() + timeout;
+    for inclusion in &inclusions {
+        let root = hex::encode(inclusion.batch_merkle_root);
+        if !inclusion.verify() {
+            return Err(format!("invalid inclusion proof for batch 0x{}", root).into());
+        }
+        while !batcher.is_verified(inclusion).await? {
+            if Instant::now() >= deadline {
+                return Err(format!("batch 0x{} not verified within {:?}", root, timeout).into());
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+    Ok(inclusions)
+}
+
+/// SP1 major version whose proofs the operators of the pinned `aligned-sdk` release verify.
+/// Update it together with the `aligned-sdk` tag in `prover/Cargo.toml`.
+pub const ALIGNED_SP1_VERSION: &str = "v3";
+
+/// Fails unless proofs of SP1 `prover` verify with the SP1 `aligned` of the Aligned operators.
+fn check_sp1_version(aligned: &str, prover: &str) -> BackendResult<()> {
+    let major = |version: &str| version.split('.').next().unwrap_or_default().to_string();
+    if major(aligned) != major(prover) {
+        return Err(format!(
+            "Aligned operators verify SP1 {} proofs, the programs are proven with SP1 {}; \
+             pin an aligned-sdk release for SP1 {} before submitting",
+            aligned,
+            prover,
+            major(prover)
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Submits to an Aligned batcher through aligned-sdk.
+pub struct AlignedBatcher {
+    batcher_url: String,
+    eth_rpc_url: String,
+    network: Network,
+    wallet: LocalWallet,
+    /// Verification data returned for every leaf, needed to ask whether it was verified
+    submitted: Mutex<HashMap<[u8; 32], AlignedVerificationData>>,
+}
+
+impl AlignedBatcher {
+    pub fn new(
+        batcher_url: &str,
+        eth_rpc_url: &str,
+        network: Network,
+        wallet: LocalWallet,
+    ) -> Self {
+        Self {
+            batcher_url: batcher_url.to_string(),
+            eth_rpc_url: eth_rpc_url.to_string(),
+            network,
+            wallet,
+            submitted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Configured by `ALIGNED_NETWORK` (`devnet`, `holesky` or `holesky-stage`),
+    /// `ALIGNED_BATCHER_URL`, `ALIGNED_RPC_URL` and `PRIVATE_KEY`.
+    ///
+    /// Refuses to submit until [`ALIGNED_SP1_VERSION`] matches the SP1 version of the workspace.
+    pub async fn from_env() -> BackendResult<Self> {
+        check_sp1_version(ALIGNED_SP1_VERSION, SP1_CIRCUIT_VERSION)?;
+        let network = match dotenv::var("ALIGNED_NETWORK")
+            .unwrap_or("holesky".to_string())
+            .as_str()
+        {
+            "devnet" => Network::Devnet,
+            "holesky" => Network::Holesky,
+            "holesky-stage" => Network::HoleskyStage,
+            network => return Err(format!("unknown Aligned network {}", network).into()),
+        };
+        let batcher_url = dotenv::var("ALIGNED_BATCHER_URL")
+            .map_err(|_| "Missing environment variable ALIGNED_BATCHER_URL")?;
+        let eth_rpc_url = dotenv::var("ALIGNED_RPC_URL")
+            .map_err(|_| "Missing environment variable ALIGNED_RPC_URL")?;
+        let chain_id = Provider::<Http>::try_from(eth_rpc_url.as_str())?
+            .get_chainid()
+            .await?;
+        let wallet = dotenv::var("PRIVATE_KEY")
+            .map_err(|_| "Missing environment variable PRIVATE_KEY")?
+            .parse::<LocalWallet>()?
+            .with_chain_id(chain_id.as_u64());
+        Ok(Self::new(&batcher_url, &eth_rpc_url, network, wallet))
+    }
+
+    pub fn address(&self) -> [u8; 20] {
+        self.wallet.address().0
+    }
+}
+
+impl Batcher for AlignedBatcher {
+    async fn submit(
+        &self,
+        submissions: &[AlignedSubmission],
+    ) -> BackendResult<Vec<BatchInclusion>> {
+        let verification_data = submissions
+            .iter()
+            .map(AlignedSubmission::verification_data)
+            .collect::<BackendResult<Vec<_>>>()?;
+        let nonce = get_next_nonce(&self.eth_rpc_url, self.wallet.address(), self.network)
+            .await
+            .map_err(|e| format!("Failed to get nonce: {:?}", e))?;
+        let max_fee = estimate_fee(&self.eth_rpc_url, PriceEstimate::Default)
+            .await
+            .map_err(|e| format!("Failed to estimate fee: {:?}", e))?;
+        let aligned_data = submit_multiple(
+            &self.batcher_url,
+            self.network,
+            &verification_data,
+            max_fee,
+            self.wallet.clone(),
+            nonce,
+        )
+        .await
+        .map_err(|e| format!("Aligned batcher rejected the proofs: {:?}", e))?;
+
+        let mut submitted = self.submitted.lock().unwrap();
+        Ok(aligned_data
+            .into_iter()
+            .map(|data| {
+                let commitment = &data.verification_data_commitment;
+                let inclusion = BatchInclusion {
+                    commitment: VerificationCommitment {
+                        proof_commitment: commitment.proof_commitment,
+                        pub_input_commitment: commitment.pub_input_commitment,
+                        program_id_commitment: commitment.proving_system_aux_data_commitment,
+                        proof_generator: commitment.proof_generator_addr,
+                    },
+                    batch_merkle_root: data.batch_merkle_root,
+                    merkle_path: data.batch_inclusion_proof.merkle_path.clone(),
+                    index_in_batch: data.index_in_batch,
+                };
+                submitted.insert(inclusion.commitment.leaf(), data);
+                inclusion
+            })
+            .collect())
+    }
+
+    async fn is_verified(&self, inclusion: &BatchInclusion) -> BackendResult<bool> {
+        let data = self
+            .submitted
+            .lock()
+            .unwrap()
+            .get(&inclusion.commitment.leaf())
+            .cloned()
+            .ok_or("proof was not submitted by this batcher")?;
+        is_proof_verified(&data, self.network, &self.eth_rpc_url)
+            .await
+            .map_err(|e| format!("Failed to check verification: {:?}", e).into())
+    }
+}
+
+/// In-process stand-in for the Aligned batcher, for tests and local development.
+///
+/// Proofs are verified with the backend of a [`SpectreProver`] before they are batched, like the
+/// batcher rejects invalid proofs. A batch counts as verified on Ethereum once
+/// `verification_delay` has passed since it was sent.
+pub struct LocalBatcher {
+    spectre_prover: Arc<SpectreProver>,
+    verification_delay: Duration,
+    /// Roots of the batches sent so far and when they were sent
+    batches: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+impl LocalBatcher {
+    pub fn new(spectre_prover: Arc<SpectreProver>, verification_delay: Duration) -> Self {
+        Self {
+            spectre_prover,
+            verification_delay,
+            batches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn batches(&self) -> usize {
+        self.batches.lock().unwrap().len()
+    }
+}
+
+impl Batcher for LocalBatcher {
+    async fn submit(
+        &self,
+        submissions: &[AlignedSubmission],
+    ) -> BackendResult<Vec<BatchInclusion>> {
+        if submissions.is_empty() {
+            return Err("no proofs to submit".into());
+        }
+        let mut commitments = vec![];
+        for submission in submissions {
+            let (_, vk) = self.spectre_prover.keys(submission.program);
+            self.spectre_prover
+                .verify(&submission.proof, vk)
+                .map_err(|e| format!("rejected {} proof: {}", submission.program, e))?;
+            commitments.push(submission.commitment()?);
+        }
+        let inclusions = batch(&commitments);
+        self.batches
+            .lock()
+            .unwrap()
+            .insert(inclusions[0].batch_merkle_root, Instant::now());
+        Ok(inclusions)
+    }
+
+    async fn is_verified(&self, inclusion: &BatchInclusion) -> BackendResult<bool> {
+        let sent = *self
+            .batches
+            .lock()
+            .unwrap()
+            .get(&inclusion.batch_merkle_root)
+            .ok_or("unknown batch")?;
+        Ok(inclusion.verify() && sent.elapsed() >= self.verification_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{batch, check_sp1_version, VerificationCommitment};
+
+    #[test]
+    fn test_batch_inclusion() {
+        let commitments: Vec<VerificationCommitment> = (0..3)
+            .map(|i| VerificationCommitment {
+                proof_commitment: [i; 32],
+                pub_input_commitment: [i + 1; 32],
+                program_id_commitment: [7; 32],
+                proof_generator: [9; 20],
+            })
+            .collect();
+        let inclusions = batch(&commitments);
+        assert_eq!(inclusions.len(), 3);
+        for inclusion in &inclusions {
+            assert_eq!(inclusion.merkle_path.len(), 2);
+            assert_eq!(inclusion.batch_merkle_root, inclusions[0].batch_merkle_root);
+            assert!(inclusion.verify());
+            assert_eq!(inclusion.merkle_proof().len(), 64);
+        }
+
+        let mut tampered = inclusions[1].clone();
+        tampered.commitment.pub_input_commitment = [0; 32];
+        assert!(!tampered.verify());
+        let mut moved = inclusions[1].clone();
+        moved.index_in_batch = 0;
+        assert!(!moved.verify());
+
+        let single = batch(&commitments[..1]);
+        assert!(single[0].merkle_path.is_empty());
+        assert_eq!(single[0].batch_merkle_root, commitments[0].leaf());
+        assert!(batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_check_sp1_version() {
+        assert!(check_sp1_version("v4", "v4.0.0-rc.3").is_ok());
+        assert!(check_sp1_version("v3", "v4.0.0-rc.3").is_err());
+        assert!(check_sp1_version("v4.1.0", "v4.0.0-rc.3").is_ok());
+    }
+}
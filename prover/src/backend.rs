@@ -0,0 +1,294 @@
+use num_bigint::BigUint;
+use sp1_sdk::{
+    install::try_install_circuit_artifacts, CpuProver, EnvProver, ExecutionReport,
+    Groth16Bn254Proof, HashableKey, MockProver, NetworkProver, PlonkBn254Proof, Prover,
+    ProverClient, SP1Proof, SP1ProofMode, SP1ProofWithPublicValues, SP1Prover, SP1ProvingKey,
+    SP1PublicValues, SP1Stdin, SP1VerifyingKey,
+};
+use std::{fmt::Display, str::FromStr};
+
+pub type BackendResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Produces the proofs of the Spectre programs.
+pub trait ProverBackend: Send + Sync {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey);
+
+    /// Runs the program without proving it.
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> BackendResult<(SP1PublicValues, ExecutionReport)>;
+
+    fn prove(
+        &self,
+        pk: &SP1ProvingKey,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> BackendResult<SP1ProofWithPublicValues>;
+
+    fn verify(&self, proof: &SP1ProofWithPublicValues, vk: &SP1VerifyingKey) -> BackendResult<()>;
+
+    /// Wraps a compressed proof into a Groth16 or Plonk proof that can be verified on-chain.
+    fn wrap(
+        &self,
+        vk: &SP1VerifyingKey,
+        proof: &SP1ProofWithPublicValues,
+        mode: SP1ProofMode,
+    ) -> BackendResult<SP1ProofWithPublicValues>;
+}
+
+macro_rules! sp1_backend {
+    ($($prover:ty => $wrap:ident),*) => {
+        $(
+            impl ProverBackend for $prover {
+                fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+                    Prover::setup(self, elf)
+                }
+
+                fn execute(
+                    &self,
+                    elf: &[u8],
+                    stdin: &SP1Stdin,
+                ) -> BackendResult<(SP1PublicValues, ExecutionReport)> {
+                    Ok(Prover::execute(self, elf, stdin)?)
+                }
+
+                fn prove(
+                    &self,
+                    pk: &SP1ProvingKey,
+                    stdin: &SP1Stdin,
+                    mode: SP1ProofMode,
+                ) -> BackendResult<SP1ProofWithPublicValues> {
+                    Ok(Prover::prove(self, pk, stdin, mode)?)
+                }
+
+                fn verify(
+                    &self,
+                    proof: &SP1ProofWithPublicValues,
+                    vk: &SP1VerifyingKey,
+                ) -> BackendResult<()> {
+                    Ok(Prover::verify(self, proof, vk)?)
+                }
+
+                fn wrap(
+                    &self,
+                    vk: &SP1VerifyingKey,
+                    proof: &SP1ProofWithPublicValues,
+                    mode: SP1ProofMode,
+                ) -> BackendResult<SP1ProofWithPublicValues> {
+                    $wrap(Prover::inner(self), vk, proof, mode)
+                }
+            }
+        )*
+    };
+}
+
+sp1_backend!(
+    CpuProver => wrap_local,
+    MockProver => wrap_mock,
+    NetworkProver => wrap_local,
+    EnvProver => wrap_env
+);
+
+/// Shrinks a compressed proof and wraps it on this machine, whichever backend produced it.
+fn wrap_local(
+    prover: &SP1Prover,
+    _vk: &SP1VerifyingKey,
+    proof: &SP1ProofWithPublicValues,
+    mode: SP1ProofMode,
+) -> BackendResult<SP1ProofWithPublicValues> {
+    let SP1Proof::Compressed(reduced) = &proof.proof else {
+        return Err("only compressed proofs can be wrapped".into());
+    };
+    if !matches!(mode, SP1ProofMode::Groth16 | SP1ProofMode::Plonk) {
+        return Err(format!("cannot wrap into a {:?} proof", mode).into());
+    }
+    let shrunk = prover.shrink(*reduced.clone(), Default::default())?;
+    let outer = prover.wrap_bn254(shrunk, Default::default())?;
+    let mut wrapped = proof.clone();
+    wrapped.proof = match mode {
+        SP1ProofMode::Groth16 => SP1Proof::Groth16(
+            prover.wrap_groth16_bn254(outer, &try_install_circuit_artifacts("groth16")),
+        ),
+        _ => {
+            SP1Proof::Plonk(prover.wrap_plonk_bn254(outer, &try_install_circuit_artifacts("plonk")))
+        }
+    };
+    Ok(wrapped)
+}
+
+/// The SDK builds a mock prover for `SP1_PROVER=mock`, whose compressed proofs are fake too.
+fn wrap_env(
+    prover: &SP1Prover,
+    vk: &SP1VerifyingKey,
+    proof: &SP1ProofWithPublicValues,
+    mode: SP1ProofMode,
+) -> BackendResult<SP1ProofWithPublicValues> {
+    match std::env::var("SP1_PROVER").as_deref() {
+        Ok("mock") => wrap_mock(prover, vk, proof, mode),
+        _ => wrap_local(prover, vk, proof, mode),
+    }
+}
+
+/// Replaces the proof with the fake one the mock prover would have produced in `mode`.
+fn wrap_mock(
+    _prover: &SP1Prover,
+    vk: &SP1VerifyingKey,
+    proof: &SP1ProofWithPublicValues,
+    mode: SP1ProofMode,
+) -> BackendResult<SP1ProofWithPublicValues> {
+    let vk_hash = BigUint::parse_bytes(vk.bytes32().trim_start_matches("0x").as_bytes(), 16)
+        .ok_or("invalid verifying key hash")?;
+    let public_inputs = [
+        vk_hash.to_string(),
+        proof.public_values.hash_bn254().to_string(),
+    ];
+    let mut wrapped = proof.clone();
+    wrapped.proof = match mode {
+        SP1ProofMode::Groth16 => SP1Proof::Groth16(Groth16Bn254Proof {
+            public_inputs,
+            encoded_proof: String::new(),
+            raw_proof: String::new(),
+            groth16_vkey_hash: [0; 32],
+        }),
+        SP1ProofMode::Plonk => SP1Proof::Plonk(PlonkBn254Proof {
+            public_inputs,
+            encoded_proof: String::new(),
+            raw_proof: String::new(),
+            plonk_vkey_hash: [0; 32],
+        }),
+        _ => return Err(format!("cannot wrap into a {:?} proof", mode).into()),
+    };
+    Ok(wrapped)
+}
+
+/// Runs the programs without ever proving them, to check their outputs and cycle counts.
+pub struct ExecuteOnly(CpuProver);
+
+impl ProverBackend for ExecuteOnly {
+    fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+        Prover::setup(&self.0, elf)
+    }
+
+    fn execute(
+        &self,
+        elf: &[u8],
+        stdin: &SP1Stdin,
+    ) -> BackendResult<(SP1PublicValues, ExecutionReport)> {
+        Ok(Prover::execute(&self.0, elf, stdin)?)
+    }
+
+    fn prove(
+        &self,
+        _pk: &SP1ProvingKey,
+        _stdin: &SP1Stdin,
+        _mode: SP1ProofMode,
+    ) -> BackendResult<SP1ProofWithPublicValues> {
+        Err("the execute backend does not produce proofs".into())
+    }
+
+    fn verify(
+        &self,
+        _proof: &SP1ProofWithPublicValues,
+        _vk: &SP1VerifyingKey,
+    ) -> BackendResult<()> {
+        Err("the execute backend does not verify proofs".into())
+    }
+
+    fn wrap(
+        &self,
+        _vk: &SP1VerifyingKey,
+        _proof: &SP1ProofWithPublicValues,
+        _mode: SP1ProofMode,
+    ) -> BackendResult<SP1ProofWithPublicValues> {
+        Err("the execute backend does not wrap proofs".into())
+    }
+}
+
+/// Backend selection, read from `PROVER_BACKEND`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackendKind {
+    /// Chosen by the `SP1_PROVER` variables of the SP1 SDK
+    #[default]
+    Env,
+    /// Executes the programs and returns fake proofs, accepted by the SP1 mock verifier
+    Mock,
+    /// Proves on the local CPU
+    Cpu,
+    /// Proves on the Succinct prover network, authenticated by `NETWORK_PRIVATE_KEY`
+    Network,
+    /// Executes the programs only, see [`ExecuteOnly`]
+    Execute,
+}
+
+impl BackendKind {
+    /// Reads `PROVER_BACKEND`, falling back to `default` if it is not set.
+    pub fn from_env_or(default: Self) -> Self {
+        match dotenv::var("PROVER_BACKEND") {
+            Ok(kind) => kind.parse().expect("Invalid PROVER_BACKEND"),
+            Err(_) => default,
+        }
+    }
+
+    pub fn build(self) -> Box<dyn ProverBackend> {
+        match self {
+            Self::Env => Box::new(ProverClient::from_env()),
+            Self::Mock => Box::new(ProverClient::builder().mock().build()),
+            Self::Cpu => Box::new(ProverClient::builder().cpu().build()),
+            Self::Network => Box::new(ProverClient::builder().network().build()),
+            Self::Execute => Box::new(ExecuteOnly(ProverClient::builder().cpu().build())),
+        }
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "env" => Ok(Self::Env),
+            "mock" => Ok(Self::Mock),
+            "cpu" | "local" => Ok(Self::Cpu),
+            "network" => Ok(Self::Network),
+            "execute" => Ok(Self::Execute),
+            _ => Err(format!(
+                "unknown prover backend {}, expected env, mock, cpu, network or execute",
+                s
+            )),
+        }
+    }
+}
+
+impl Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Env => "env",
+            Self::Mock => "mock",
+            Self::Cpu => "cpu",
+            Self::Network => "network",
+            Self::Execute => "execute",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BackendKind;
+
+    #[test]
+    fn test_backend_kind() {
+        for kind in [
+            BackendKind::Env,
+            BackendKind::Mock,
+            BackendKind::Cpu,
+            BackendKind::Network,
+            BackendKind::Execute,
+        ] {
+            assert_eq!(kind.to_string().parse::<BackendKind>(), Ok(kind));
+        }
+        assert_eq!("Local".parse::<BackendKind>(), Ok(BackendKind::Cpu));
+        assert!("gpu".parse::<BackendKind>().is_err());
+    }
+}
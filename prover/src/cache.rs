@@ -0,0 +1,667 @@
+use eth_types::{Slot, Spec};
+use preprocessor::{BeaconSource, Encoding, PreprocessorError, RootFn};
+use rusqlite::{params, types::Type, Connection, OptionalExtension};
+use std::{
+    marker::PhantomData,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+const UPDATES_ROUTE: &str = "eth/v1/beacon/light_client/updates";
+const BOOTSTRAP_ROUTE: &str = "eth/v1/beacon/light_client/bootstrap/";
+/// Bumped whenever a table changes, `PRAGMA user_version` of the cache file.
+const SCHEMA_VERSION: u32 = 1;
+
+fn invalid_blob(column: usize, reason: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Blob, reason.into())
+}
+
+/// A sync committee that was decoded and committed to before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedCommittee {
+    pub pubkeys: Vec<Vec<u8>>,
+    pub commitment: [u8; 32],
+}
+
+/// On-disk cache of light client updates (keyed by period), bootstraps (keyed by block root)
+/// and the decoded committees of each period.
+pub struct LightClientCache {
+    conn: Mutex<Connection>,
+}
+
+impl LightClientCache {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> rusqlite::Result<Self> {
+        // everything cached can be fetched again, so older layouts are dropped instead of migrated
+        let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version != SCHEMA_VERSION {
+            let tx = conn.transaction()?;
+            tx.execute_batch(
+                "DROP TABLE IF EXISTS updates;
+                DROP TABLE IF EXISTS bootstraps;
+                DROP TABLE IF EXISTS committees;",
+            )?;
+            tx.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            tx.commit()?;
+        }
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS updates (
+                period INTEGER NOT NULL,
+                encoding TEXT NOT NULL,
+                body BLOB NOT NULL,
+                PRIMARY KEY (period, encoding)
+            );
+            CREATE TABLE IF NOT EXISTS bootstraps (
+                block_root TEXT NOT NULL,
+                encoding TEXT NOT NULL,
+                period INTEGER,
+                body BLOB NOT NULL,
+                PRIMARY KEY (block_root, encoding)
+            );
+            CREATE TABLE IF NOT EXISTS committees (
+                period INTEGER PRIMARY KEY,
+                pubkeys BLOB NOT NULL,
+                commitment BLOB NOT NULL
+            );",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn update(&self, period: u64, encoding: Encoding) -> rusqlite::Result<Option<Vec<u8>>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT body FROM updates WHERE period = ?1 AND encoding = ?2",
+                params![period, encoding.to_string()],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn put_update(&self, period: u64, encoding: Encoding, body: &[u8]) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO updates (period, encoding, body) VALUES (?1, ?2, ?3)",
+            params![period, encoding.to_string(), body],
+        )?;
+        Ok(())
+    }
+
+    pub fn bootstrap(
+        &self,
+        block_root: &str,
+        encoding: Encoding,
+    ) -> rusqlite::Result<Option<Vec<u8>>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT body FROM bootstraps WHERE block_root = ?1 AND encoding = ?2",
+                params![block_root, encoding.to_string()],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+
+    pub fn put_bootstrap(
+        &self,
+        block_root: &str,
+        encoding: Encoding,
+        period: Option<u64>,
+        body: &[u8],
+    ) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO bootstraps (block_root, encoding, period, body)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![block_root, encoding.to_string(), period, body],
+        )?;
+        Ok(())
+    }
+
+    pub fn committee(&self, period: u64) -> rusqlite::Result<Option<CachedCommittee>> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT pubkeys, commitment FROM committees WHERE period = ?1",
+                params![period],
+                |row| {
+                    let pubkeys: Vec<u8> = row.get(0)?;
+                    let commitment: Vec<u8> = row.get(1)?;
+                    if !pubkeys.chunks_exact(48).remainder().is_empty() {
+                        return Err(invalid_blob(
+                            0,
+                            format!("{} bytes are not 48 byte public keys", pubkeys.len()),
+                        ));
+                    }
+                    Ok(CachedCommittee {
+                        pubkeys: pubkeys.chunks(48).map(|key| key.to_vec()).collect(),
+                        commitment: commitment.try_into().map_err(|commitment: Vec<u8>| {
+                            invalid_blob(
+                                1,
+                                format!("{} byte commitment, expected 32", commitment.len()),
+                            )
+                        })?,
+                    })
+                },
+            )
+            .optional()
+    }
+
+    pub fn put_committee(&self, period: u64, committee: &CachedCommittee) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO committees (period, pubkeys, commitment) VALUES (?1, ?2, ?3)",
+            params![
+                period,
+                committee.pubkeys.concat(),
+                committee.commitment.to_vec()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Drops everything cached for `period`, e.g. when a better update became available.
+    pub fn invalidate_period(&self, period: u64) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM updates WHERE period = ?1", params![period])?;
+        conn.execute("DELETE FROM bootstraps WHERE period = ?1", params![period])?;
+        conn.execute("DELETE FROM committees WHERE period = ?1", params![period])?;
+        Ok(())
+    }
+
+    pub fn invalidate_block_root(&self, block_root: &str) -> rusqlite::Result<()> {
+        self.conn.lock().unwrap().execute(
+            "DELETE FROM bootstraps WHERE block_root = ?1",
+            params![block_root],
+        )?;
+        Ok(())
+    }
+
+    /// Removes all entries of periods before `period`, and bootstraps whose period is unknown.
+    pub fn prune_before(&self, period: u64) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM updates WHERE period < ?1", params![period])?;
+        conn.execute(
+            "DELETE FROM bootstraps WHERE period < ?1 OR period IS NULL",
+            params![period],
+        )?;
+        conn.execute("DELETE FROM committees WHERE period < ?1", params![period])?;
+        Ok(())
+    }
+}
+
+/// A [`BeaconSource`] that answers light client updates and bootstraps from a
+/// [`LightClientCache`] and only queries `inner` on a miss.
+///
+/// The best update of a period can still change until the period is finalized,
+/// so only updates of periods before [`CachedSource::set_finalized_period`] are cached.
+/// Each update of a range response is cached by the period of its attested header, and a range
+/// request is answered from the cached periods it starts with, fetching only the rest.
+/// Bootstraps are keyed by the sync committee period of `S`.
+pub struct CachedSource<B: BeaconSource, S: Spec> {
+    inner: B,
+    cache: LightClientCache,
+    finalized_period: AtomicU64,
+    _spec: PhantomData<S>,
+}
+
+enum CacheKey {
+    Updates { start_period: u64, count: u64 },
+    Bootstrap(String),
+}
+
+impl<B: BeaconSource, S: Spec> CachedSource<B, S> {
+    pub fn new(inner: B, cache: LightClientCache) -> Self {
+        Self {
+            inner,
+            cache,
+            finalized_period: AtomicU64::new(0),
+            _spec: PhantomData,
+        }
+    }
+
+    pub fn cache(&self) -> &LightClientCache {
+        &self.cache
+    }
+
+    /// Sets the period of the latest finalized header, updates of earlier periods are final.
+    pub fn set_finalized_period(&self, period: u64) {
+        self.finalized_period.fetch_max(period, Ordering::Relaxed);
+    }
+
+    fn is_final(&self, period: u64) -> bool {
+        period < self.finalized_period.load(Ordering::Relaxed)
+    }
+
+    fn key(route: &str, query: &[(&str, u64)]) -> Option<CacheKey> {
+        if route == UPDATES_ROUTE {
+            let start_period = query.iter().find(|(k, _)| *k == "start_period")?.1;
+            let count = query.iter().find(|(k, _)| *k == "count")?.1;
+            return Some(CacheKey::Updates {
+                start_period,
+                count,
+            });
+        }
+        route
+            .strip_prefix(BOOTSTRAP_ROUTE)
+            .map(|block_root| CacheKey::Bootstrap(block_root.to_string()))
+    }
+
+    /// Cached updates of the periods `start_period` counts up from, until the first miss.
+    fn cached_updates(&self, start_period: u64, count: u64, encoding: Encoding) -> Vec<Vec<u8>> {
+        let mut updates = vec![];
+        for period in start_period..start_period.saturating_add(count) {
+            match self.cache.update(period, encoding) {
+                Ok(Some(update)) => updates.push(update),
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("Light client cache lookup failed: {}", e);
+                    break;
+                }
+            }
+        }
+        updates
+    }
+
+    async fn fetch_updates(
+        &self,
+        route: &str,
+        start_period: u64,
+        count: u64,
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> preprocessor::Result<Vec<u8>> {
+        let mut updates = self.cached_updates(start_period, count, encoding);
+        let cached = updates.len() as u64;
+        if cached == count {
+            return join_updates(encoding, &updates).ok_or_else(|| corrupt_updates(route));
+        }
+        let query = [
+            ("start_period", start_period + cached),
+            ("count", count - cached),
+        ];
+        let body = match self.inner.fetch(route, &query, encoding, root).await {
+            // the node has nothing after the cached periods
+            Err(e) if e.is_not_found() && cached > 0 => {
+                return join_updates(encoding, &updates).ok_or_else(|| corrupt_updates(route))
+            }
+            body => body?,
+        };
+        // a response that does not split is passed on, the caller reports what is wrong with it
+        let Some(fetched) = split_updates(encoding, &body) else {
+            return Ok(body);
+        };
+        for update in &fetched {
+            let Some(period) = update_period::<S>(encoding, update) else {
+                continue;
+            };
+            if !self.is_final(period) {
+                continue;
+            }
+            if let Err(e) = self.cache.put_update(period, encoding, update) {
+                eprintln!("Failed to write light client cache: {}", e);
+            }
+        }
+        if cached == 0 {
+            return Ok(body);
+        }
+        updates.extend(fetched);
+        join_updates(encoding, &updates).ok_or_else(|| corrupt_updates(route))
+    }
+
+    async fn fetch_bootstrap(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        block_root: &str,
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> preprocessor::Result<Vec<u8>> {
+        match self.cache.bootstrap(block_root, encoding) {
+            Ok(Some(body)) => return Ok(body),
+            Ok(None) => {}
+            Err(e) => eprintln!("Light client cache lookup failed: {}", e),
+        }
+        let body = self.inner.fetch(route, query, encoding, root).await?;
+        if let Err(e) = self.cache.put_bootstrap(
+            block_root,
+            encoding,
+            bootstrap_period::<S>(encoding, &body),
+            &body,
+        ) {
+            eprintln!("Failed to write light client cache: {}", e);
+        }
+        Ok(body)
+    }
+}
+
+fn corrupt_updates(route: &str) -> PreprocessorError {
+    PreprocessorError::decode(route, "cached light client updates do not join")
+}
+
+/// Splits a light client updates response into single update responses, i.e. the
+/// `response_chunk`s of an SSZ response or the items of a JSON array.
+fn split_updates(encoding: Encoding, mut body: &[u8]) -> Option<Vec<Vec<u8>>> {
+    match encoding {
+        Encoding::Json => {
+            let updates: Vec<serde_json::Value> = serde_json::from_slice(body).ok()?;
+            updates
+                .into_iter()
+                .map(|update| serde_json::to_vec(&[update]).ok())
+                .collect()
+        }
+        Encoding::Ssz => {
+            let mut updates = vec![];
+            while !body.is_empty() {
+                let length = u64::from_le_bytes(body.get(..8)?.try_into().ok()?);
+                let end = usize::try_from(length).ok()?.checked_add(8)?;
+                updates.push(body.get(..end)?.to_vec());
+                body = &body[end..];
+            }
+            Some(updates)
+        }
+    }
+}
+
+/// Joins single update responses into one light client updates response.
+fn join_updates(encoding: Encoding, updates: &[Vec<u8>]) -> Option<Vec<u8>> {
+    match encoding {
+        Encoding::Json => {
+            let mut joined = vec![];
+            for update in updates {
+                joined.extend(serde_json::from_slice::<Vec<serde_json::Value>>(update).ok()?);
+            }
+            serde_json::to_vec(&joined).ok()
+        }
+        Encoding::Ssz => Some(updates.concat()),
+    }
+}
+
+/// Reads the sync committee period of the slot at `pointer` of a JSON object, or of the slot
+/// an SSZ light client object starts with: its variable sized header comes first and starts
+/// with the slot of its beacon header.
+fn slot_period<S: Spec>(encoding: Encoding, body: &[u8], pointer: &str) -> Option<u64> {
+    let slot = match encoding {
+        Encoding::Json => {
+            let object: serde_json::Value = serde_json::from_slice(body).ok()?;
+            let slot = object.pointer(pointer)?.as_str()?;
+            slot.parse::<u64>().ok()?
+        }
+        Encoding::Ssz => {
+            let offset = u32::from_le_bytes(body.get(..4)?.try_into().ok()?) as usize;
+            u64::from_le_bytes(body.get(offset..offset + 8)?.try_into().ok()?)
+        }
+    };
+    Some(Slot(slot).period::<S>().0)
+}
+
+fn bootstrap_period<S: Spec>(encoding: Encoding, body: &[u8]) -> Option<u64> {
+    slot_period::<S>(encoding, body, "/data/header/beacon/slot")
+}
+
+/// Period of the attested header of a single update response, which the beacon API assigns it to.
+fn update_period<S: Spec>(encoding: Encoding, update: &[u8]) -> Option<u64> {
+    match encoding {
+        Encoding::Json => slot_period::<S>(encoding, update, "/0/data/attested_header/beacon/slot"),
+        // skips the length and fork digest of the response chunk
+        Encoding::Ssz => slot_period::<S>(encoding, update.get(12..)?, ""),
+    }
+}
+
+impl<B: BeaconSource, S: Spec> BeaconSource for CachedSource<B, S> {
+    fn name(&self) -> String {
+        format!("cached {}", self.inner.name())
+    }
+
+    async fn fetch(
+        &self,
+        route: &str,
+        query: &[(&str, u64)],
+        encoding: Encoding,
+        root: RootFn<'_>,
+    ) -> preprocessor::Result<Vec<u8>> {
+        match Self::key(route, query) {
+            Some(CacheKey::Updates {
+                start_period,
+                count,
+            }) => {
+                self.fetch_updates(route, start_period, count, encoding, root)
+                    .await
+            }
+            Some(CacheKey::Bootstrap(block_root)) => {
+                self.fetch_bootstrap(route, query, &block_root, encoding, root)
+                    .await
+            }
+            None => self.inner.fetch(route, query, encoding, root).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bootstrap_period, join_updates, split_updates, update_period, CachedCommittee,
+        CachedSource, LightClientCache, UPDATES_ROUTE,
+    };
+    use eth_types::{Spec, Testnet};
+    use preprocessor::{BeaconSource, Encoding, RootFn};
+    use rusqlite::{params, Connection};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves an SSZ update for every requested period and counts the requests.
+    #[derive(Default)]
+    struct Counting(AtomicUsize);
+
+    impl BeaconSource for Counting {
+        fn name(&self) -> String {
+            "counting".to_string()
+        }
+
+        async fn fetch(
+            &self,
+            _: &str,
+            query: &[(&str, u64)],
+            _: Encoding,
+            _: RootFn<'_>,
+        ) -> preprocessor::Result<Vec<u8>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            let (start_period, count) = (query[0].1, query[1].1);
+            Ok((start_period..start_period + count)
+                .flat_map(update)
+                .collect())
+        }
+    }
+
+    /// A response chunk of an update whose attested header is at the start of `period`.
+    fn update(period: u64) -> Vec<u8> {
+        let mut payload = 4u32.to_le_bytes().to_vec();
+        payload
+            .extend_from_slice(&(period * Testnet::SLOTS_PER_SYNC_COMMITTEE_PERIOD).to_le_bytes());
+        let mut chunk = (4 + payload.len() as u64).to_le_bytes().to_vec();
+        chunk.extend_from_slice(&[0; 4]);
+        chunk.extend(payload);
+        chunk
+    }
+
+    async fn fetch_updates(
+        source: &CachedSource<Counting, Testnet>,
+        start_period: u64,
+        count: u64,
+    ) -> Vec<u8> {
+        source
+            .fetch(
+                UPDATES_ROUTE,
+                &[("start_period", start_period), ("count", count)],
+                Encoding::Ssz,
+                &|_| unreachable!(),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cache_roundtrip_and_prune() {
+        let cache = LightClientCache::open_in_memory().unwrap();
+        cache.put_update(841, Encoding::Ssz, b"update").unwrap();
+        cache
+            .put_bootstrap("0xaa", Encoding::Json, Some(841), b"bootstrap")
+            .unwrap();
+        let committee = CachedCommittee {
+            pubkeys: vec![vec![1; 48], vec![2; 48]],
+            commitment: [3; 32],
+        };
+        cache.put_committee(841, &committee).unwrap();
+        cache
+            .put_bootstrap("0xbb", Encoding::Ssz, None, b"bootstrap")
+            .unwrap();
+
+        assert_eq!(
+            cache.update(841, Encoding::Ssz).unwrap(),
+            Some(b"update".to_vec())
+        );
+        assert_eq!(cache.update(841, Encoding::Json).unwrap(), None);
+        assert_eq!(
+            cache.bootstrap("0xaa", Encoding::Json).unwrap(),
+            Some(b"bootstrap".to_vec())
+        );
+        assert_eq!(cache.committee(841).unwrap(), Some(committee));
+
+        cache.prune_before(842).unwrap();
+        assert_eq!(cache.update(841, Encoding::Ssz).unwrap(), None);
+        assert_eq!(cache.bootstrap("0xaa", Encoding::Json).unwrap(), None);
+        assert_eq!(cache.bootstrap("0xbb", Encoding::Ssz).unwrap(), None);
+        assert_eq!(cache.committee(841).unwrap(), None);
+    }
+
+    #[test]
+    fn test_schema_upgrade() {
+        // the layout before updates were keyed by encoding, without a schema version
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE updates (period INTEGER PRIMARY KEY, body BLOB NOT NULL);
+            INSERT INTO updates (period, body) VALUES (841, x'00');",
+        )
+        .unwrap();
+        let cache = LightClientCache::init(conn).unwrap();
+        assert_eq!(cache.update(841, Encoding::Ssz).unwrap(), None);
+        cache.put_update(841, Encoding::Ssz, b"update").unwrap();
+        assert_eq!(
+            cache.update(841, Encoding::Ssz).unwrap(),
+            Some(b"update".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_corrupt_committee() {
+        let cache = LightClientCache::open_in_memory().unwrap();
+        cache
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO committees (period, pubkeys, commitment) VALUES (?1, ?2, ?3)",
+                params![841, vec![1u8; 96], vec![3u8; 31]],
+            )
+            .unwrap();
+        assert!(cache.committee(841).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_only_finalized_periods_are_cached() {
+        let source = CachedSource::<_, Testnet>::new(
+            Counting::default(),
+            LightClientCache::open_in_memory().unwrap(),
+        );
+        // the best update of the ongoing period can still change
+        fetch_updates(&source, 842, 1).await;
+        fetch_updates(&source, 842, 1).await;
+        assert_eq!(source.inner.0.load(Ordering::Relaxed), 2);
+        assert_eq!(source.cache().update(842, Encoding::Ssz).unwrap(), None);
+
+        source.set_finalized_period(843);
+        fetch_updates(&source, 842, 1).await;
+        fetch_updates(&source, 842, 1).await;
+        assert_eq!(source.inner.0.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            source.cache().update(842, Encoding::Ssz).unwrap(),
+            Some(update(842))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_range_responses_are_cached_per_period() {
+        let source = CachedSource::<_, Testnet>::new(
+            Counting::default(),
+            LightClientCache::open_in_memory().unwrap(),
+        );
+        source.set_finalized_period(845);
+        let range = |start_period: u64, count: u64| {
+            (start_period..start_period + count)
+                .flat_map(update)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(fetch_updates(&source, 840, 4).await, range(840, 4));
+        assert_eq!(source.inner.0.load(Ordering::Relaxed), 1);
+        for period in 840..844 {
+            assert_eq!(
+                source.cache().update(period, Encoding::Ssz).unwrap(),
+                Some(update(period))
+            );
+        }
+
+        assert_eq!(fetch_updates(&source, 841, 1).await, update(841));
+        assert_eq!(source.inner.0.load(Ordering::Relaxed), 1);
+
+        // only the periods after the cached ones are fetched, the ongoing one is not cached
+        assert_eq!(fetch_updates(&source, 842, 4).await, range(842, 4));
+        assert_eq!(source.inner.0.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            source.cache().update(844, Encoding::Ssz).unwrap(),
+            Some(update(844))
+        );
+        assert_eq!(source.cache().update(845, Encoding::Ssz).unwrap(), None);
+    }
+
+    #[test]
+    fn test_split_and_join_json_updates() {
+        let body = br#"[{"version":"deneb","data":{"attested_header":{"beacon":{"slot":"6897664"}}}},{"version":"deneb","data":{"attested_header":{"beacon":{"slot":"6905856"}}}}]"#;
+        let updates = split_updates(Encoding::Json, body).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(
+            updates
+                .iter()
+                .map(|update| update_period::<Testnet>(Encoding::Json, update))
+                .collect::<Vec<_>>(),
+            vec![Some(842), Some(843)]
+        );
+        assert_eq!(
+            join_updates(Encoding::Json, &updates),
+            Some(
+                serde_json::to_vec(&serde_json::from_slice::<serde_json::Value>(body).unwrap())
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_bootstrap_period() {
+        let json = br#"{"data":{"header":{"beacon":{"slot":"6897664"}}}}"#;
+        assert_eq!(bootstrap_period::<Testnet>(Encoding::Json, json), Some(842));
+
+        let mut ssz = 4u32.to_le_bytes().to_vec();
+        ssz.extend_from_slice(&6897664u64.to_le_bytes());
+        assert_eq!(bootstrap_period::<Testnet>(Encoding::Ssz, &ssz), Some(842));
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+use sp1_sdk::ExecutionReport;
+use std::{collections::BTreeMap, path::Path};
+
+/// Cycles spent executing one of the Spectre programs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CycleReport {
+    /// `step` or `rotation`
+    pub program: String,
+    pub total: u64,
+    pub syscalls: u64,
+    /// Cycles per tracked span, e.g. `pairing` or `merkle proofs`
+    pub spans: BTreeMap<String, u64>,
+}
+
+impl CycleReport {
+    pub fn new(program: &str, report: &ExecutionReport) -> Self {
+        Self {
+            program: program.to_string(),
+            total: report.total_instruction_count(),
+            syscalls: report.total_syscall_count(),
+            spans: report
+                .cycle_tracker
+                .iter()
+                .map(|(span, cycles)| (span.clone(), *cycles))
+                .collect(),
+        }
+    }
+}
+
+/// Measured cycles of a program, spans without a count are not checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CycleBudget {
+    pub total: u64,
+    #[serde(default)]
+    pub spans: BTreeMap<String, u64>,
+}
+
+/// Cycle counts per program as checked in at `data/cycle_budget.json`, a program is over
+/// budget once it spends more than `margin_percent` above them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CycleBudgets {
+    /// SP1 version the counts were measured with, `None` while they are estimates
+    #[serde(default)]
+    pub sp1_version: Option<String>,
+    pub margin_percent: u64,
+    pub programs: BTreeMap<String, CycleBudget>,
+}
+
+impl CycleBudgets {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        crate::write_atomically(path.as_ref(), format!("{}\n", json).as_bytes())
+    }
+
+    /// The most cycles a program may spend that was measured at `cycles`.
+    pub fn limit(&self, cycles: u64) -> u64 {
+        cycles.saturating_mul(100 + self.margin_percent) / 100
+    }
+
+    /// Replaces the counts of the program of `report` with the measured ones, including every
+    /// span the program tracked.
+    pub fn record(&mut self, report: &CycleReport) {
+        let budget = self.programs.entry(report.program.clone()).or_default();
+        budget.total = report.total;
+        budget.spans = report.spans.clone();
+        self.sp1_version = Some(sp1_sdk::SP1_CIRCUIT_VERSION.to_string());
+    }
+
+    /// Lists every total or span of `report` that is over budget.
+    pub fn check(&self, report: &CycleReport) -> Result<(), Vec<String>> {
+        let Some(budget) = self.programs.get(&report.program) else {
+            return Err(vec![format!(
+                "no budget for the {} program",
+                report.program
+            )]);
+        };
+        let mut exceeded = vec![];
+        if report.total > self.limit(budget.total) {
+            exceeded.push(format!(
+                "{}: {} cycles, budget {}",
+                report.program,
+                report.total,
+                self.limit(budget.total)
+            ));
+        }
+        for (span, measured) in &budget.spans {
+            let cycles = report.spans.get(span).copied().unwrap_or_default();
+            if cycles > self.limit(*measured) {
+                exceeded.push(format!(
+                    "{} {}: {} cycles, budget {}",
+                    report.program,
+                    span,
+                    cycles,
+                    self.limit(*measured)
+                ));
+            }
+        }
+        if exceeded.is_empty() {
+            Ok(())
+        } else {
+            Err(exceeded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CycleBudget, CycleBudgets, CycleReport};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_check_budget() {
+        let mut budgets = CycleBudgets {
+            sp1_version: None,
+            margin_percent: 10,
+            programs: BTreeMap::from([(
+                "step".to_string(),
+                CycleBudget {
+                    total: 100,
+                    spans: BTreeMap::from([("pairing".to_string(), 50)]),
+                },
+            )]),
+        };
+        let mut report = CycleReport {
+            program: "step".to_string(),
+            total: 110,
+            syscalls: 0,
+            spans: BTreeMap::from([("pairing".to_string(), 55), ("aggregation".to_string(), 30)]),
+        };
+        assert!(budgets.check(&report).is_ok());
+
+        report.total = 111;
+        report.spans.insert("pairing".to_string(), 56);
+        assert_eq!(budgets.check(&report).unwrap_err().len(), 2);
+
+        budgets.record(&report);
+        assert!(budgets.check(&report).is_ok());
+        assert_eq!(budgets.programs["step"].total, 111);
+        assert_eq!(budgets.programs["step"].spans["aggregation"], 30);
+        assert!(budgets.sp1_version.is_some());
+
+        report.program = "rotation".to_string();
+        assert!(budgets.check(&report).is_err());
+    }
+}
@@ -38,6 +38,21 @@ impl SpectreContractClient {
         value
     }
 
+    pub async fn read_finalized_header_root(&self) -> [u8; 32] {
+        let provider = Provider::<Http>::try_from(&self.rpc_url).unwrap();
+        let contract = Contract::new(
+            H160::from_str(&self.contract).unwrap(),
+            self.abi.clone(),
+            std::sync::Arc::new(provider),
+        );
+        contract
+            .method::<_, [u8; 32]>("finalizedHeaderRoot", ())
+            .unwrap()
+            .call()
+            .await
+            .unwrap()
+    }
+
     pub async fn call_with_args(
         &self,
         method_name: &str,
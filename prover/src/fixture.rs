@@ -1,99 +1,305 @@
-use std::path::PathBuf;
-
-use crate::ProverOps;
-use alloy_sol_types::SolType;
-use rotation_iso::types::WrappedOutput as RotatoinWrappedOutput;
+use crate::{
+    backend::BackendResult,
+    inspect::{decode_hex, verify_fixture, FixtureProof},
+    proof::{Program, PublicOutputs},
+    ProverOps, SpectreProver,
+};
 use serde::{Deserialize, Serialize};
-use sp1_sdk::{HashableKey, SP1ProofWithPublicValues, SP1VerifyingKey};
-use step_iso::types::WrappedOutput as StepWrappedOutput;
+use sp1_sdk::{HashableKey, SP1ProofWithPublicValues};
+use std::path::{Path, PathBuf};
+
+/// The fixtures read by the Foundry tests in `spectre-verifier`.
+pub const FOUNDRY_FIXTURES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../spectre-verifier/test/fixtures"
+);
+
+fn encode(bytes: impl AsRef<[u8]>) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Verifying keys of both programs, the constructor arguments of the verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramVkeys {
+    pub step: String,
+    pub rotation: String,
+}
+
+impl ProgramVkeys {
+    pub fn new(spectre_prover: &SpectreProver) -> Self {
+        Self {
+            step: spectre_prover.step_keys().1.bytes32(),
+            rotation: spectre_prover.rotation_keys().1.bytes32(),
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+    pub fn get(&self, program: Program) -> &str {
+        match program {
+            Program::Step => &self.step,
+            Program::Rotation => &self.rotation,
+        }
+    }
+}
+
+/// State of `LightClientVerifier`, hex encoded like the rest of the fixture.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct StepUpdateFixture {
-    slot: u32,
-    root: String,
-    commitment: String,
-    vkey: String,
-    public_values: String,
-    proof: String,
+pub struct ContractState {
+    pub finalized_header_root: String,
+    pub active_committee_commitment: String,
+    pub next_committee_commitment: String,
+    pub active_slot: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ContractState {
+    /// The state a proof of `outputs` is applied to in the Foundry tests: the committee that
+    /// signed it and the slot before it, everything else zero.
+    pub fn before(outputs: &PublicOutputs) -> Self {
+        let (active, next) = match outputs.next_commitment {
+            Some(_) => ([0; 32], outputs.commitment),
+            None => (outputs.commitment, [0; 32]),
+        };
+        Self {
+            finalized_header_root: encode([0; 32]),
+            active_committee_commitment: encode(active),
+            next_committee_commitment: encode(next),
+            active_slot: outputs.slot.saturating_sub(1),
+        }
+    }
+
+    /// Applies a proof of `outputs` like `verifyStepProof` and `verifyRotationProof` do.
+    pub fn apply(&self, outputs: &PublicOutputs) -> Result<Self, String> {
+        if outputs.slot <= self.active_slot {
+            return Err(format!(
+                "slot {} is not after the active slot {}",
+                outputs.slot, self.active_slot
+            ));
+        }
+        let commitment = encode(outputs.commitment);
+        let finalized_header_root = encode(outputs.finalized_header_root);
+        match outputs.next_commitment {
+            None if commitment != self.active_committee_commitment => Err(format!(
+                "step is signed by {}, not the active committee",
+                commitment
+            )),
+            None => Ok(Self {
+                finalized_header_root,
+                ..self.clone()
+            }),
+            Some(_) if commitment != self.next_committee_commitment => Err(format!(
+                "rotation is signed by {}, not the next committee",
+                commitment
+            )),
+            Some(next_commitment) => Ok(Self {
+                finalized_header_root,
+                active_committee_commitment: commitment,
+                next_committee_commitment: encode(next_commitment),
+                active_slot: outputs.slot,
+            }),
+        }
+    }
+}
+
+/// A Groth16 or Plonk proof of either program as the Foundry tests read it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct RotationUpdateFixture {
-    slot: u32,
-    root: String,
-    commitment: String,
-    next_commitment: String,
-    vkey: String,
-    public_values: String,
-    proof: String,
+pub struct ProofFixture {
+    pub slot: u32,
+    pub root: String,
+    pub commitment: String,
+    /// Only committed to by a rotation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_commitment: Option<String>,
+    /// Verifying key of the program that was proven
+    pub vkey: String,
+    pub step_vkey: String,
+    pub rotation_vkey: String,
+    pub public_values: String,
+    pub proof: String,
+    pub pre_state: ContractState,
+    /// State of the verifier after the proof was applied to `pre_state`
+    pub post_state: ContractState,
 }
 
-pub fn create_step_proof_fixture(
-    proof: &SP1ProofWithPublicValues,
-    vk: &SP1VerifyingKey,
-    ops: &ProverOps,
-) {
-    let bytes = proof.public_values.as_slice();
-    let StepWrappedOutput {
-        slot,
-        finalized_header_root,
-        commitment,
-    } = StepWrappedOutput::abi_decode(bytes, false).unwrap();
-    let fixture = StepUpdateFixture {
-        slot,
-        root: format!("0x{}", hex::encode(finalized_header_root)),
-        commitment: format!("0x{}", hex::encode(commitment)),
-        vkey: vk.bytes32().to_string(),
-        public_values: format!("0x{}", hex::encode(bytes)),
-        proof: format!("0x{}", hex::encode(proof.bytes())),
-    };
-    let prefix = match ops {
-        ProverOps::Default => panic!("No point in generating a fixture for a default proof!"),
-        ProverOps::Groth16 => "groth16-fixture.json",
-        ProverOps::Plonk => "plonk-fixture.json",
-    };
-    let fixture_path = PathBuf::from("./");
-    std::fs::create_dir_all(&fixture_path).expect("failed to create fixture path");
-    std::fs::write(
-        fixture_path.join(prefix),
-        serde_json::to_string_pretty(&fixture).unwrap(),
-    )
-    .expect("failed to write fixture");
+impl ProofFixture {
+    pub fn new(proof: &SP1ProofWithPublicValues, vkeys: &ProgramVkeys) -> BackendResult<Self> {
+        let (program, outputs) = PublicOutputs::detect(proof.public_values.as_slice())?;
+        let pre_state = ContractState::before(&outputs);
+        Ok(Self {
+            slot: outputs.slot,
+            root: encode(outputs.finalized_header_root),
+            commitment: encode(outputs.commitment),
+            next_commitment: outputs.next_commitment.map(encode),
+            vkey: vkeys.get(program).to_string(),
+            step_vkey: vkeys.step.clone(),
+            rotation_vkey: vkeys.rotation.clone(),
+            public_values: encode(proof.public_values.as_slice()),
+            proof: encode(proof.bytes()),
+            post_state: pre_state.apply(&outputs)?,
+            pre_state,
+        })
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> BackendResult<Self> {
+        Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+    }
+
+    pub fn program(&self) -> Program {
+        match self.next_commitment {
+            Some(_) => Program::Rotation,
+            None => Program::Step,
+        }
+    }
+
+    /// Checks that the public values decode to the fields of the fixture, and that applying
+    /// them to the pre-state results in the post-state.
+    pub fn check(&self) -> BackendResult<()> {
+        let program = self.program();
+        let outputs = PublicOutputs::decode(program, &decode_hex(&self.public_values)?)?;
+        let decoded = (
+            outputs.slot,
+            encode(outputs.finalized_header_root),
+            encode(outputs.commitment),
+            outputs.next_commitment.map(encode),
+        );
+        let fields = (
+            self.slot,
+            self.root.clone(),
+            self.commitment.clone(),
+            self.next_commitment.clone(),
+        );
+        if decoded != fields {
+            return Err(format!("public values decode to {:?}, not {:?}", decoded, fields).into());
+        }
+        let program_vkey = match program {
+            Program::Step => &self.step_vkey,
+            Program::Rotation => &self.rotation_vkey,
+        };
+        if self.vkey != *program_vkey {
+            return Err(format!("vkey {} is not the {} vkey", self.vkey, program).into());
+        }
+        let post_state = self.pre_state.apply(&outputs)?;
+        if post_state != self.post_state {
+            return Err(format!(
+                "expected post-state {:?}, the proof results in {:?}",
+                self.post_state, post_state
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Checks the fixture like [`Self::check`], that it is for the programs of `vkeys`, i.e. the
+    /// current ELFs, and verifies the proof with the Groth16 and Plonk keys of the SP1 verifier.
+    pub fn verify(&self, vkeys: &ProgramVkeys) -> BackendResult<()> {
+        self.check()?;
+        for (program, vkey) in [
+            (Program::Step, &self.step_vkey),
+            (Program::Rotation, &self.rotation_vkey),
+        ] {
+            if vkey != vkeys.get(program) {
+                return Err(format!(
+                    "{} vkey {} is not the vkey {} of the current program",
+                    program,
+                    vkey,
+                    vkeys.get(program)
+                )
+                .into());
+            }
+        }
+        verify_fixture(&FixtureProof {
+            vkey: self.vkey.clone(),
+            public_values: self.public_values.clone(),
+            proof: self.proof.clone(),
+        })
+    }
+}
+
+/// How fixtures are named.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureNaming {
+    /// `step-groth16.json`, the names the Foundry tests read
+    Program,
+    /// `step-groth16-6889472.json`, keeps one fixture per slot
+    Slot,
 }
 
-pub fn create_rotation_proof_fixture(
-    proof: &SP1ProofWithPublicValues,
-    vk: &SP1VerifyingKey,
-    ops: &ProverOps,
-) {
-    let bytes = proof.public_values.as_slice();
-    let RotatoinWrappedOutput {
-        slot,
-        finalized_header_root,
-        commitment,
-        next_commitment,
-    } = RotatoinWrappedOutput::abi_decode(bytes, false).unwrap();
-    let fixture = RotationUpdateFixture {
-        slot,
-        root: format!("0x{}", hex::encode(finalized_header_root)),
-        commitment: format!("0x{}", hex::encode(commitment)),
-        next_commitment: format!("0x{}", hex::encode(next_commitment)),
-        vkey: vk.bytes32().to_string(),
-        public_values: format!("0x{}", hex::encode(bytes)),
-        proof: format!("0x{}", hex::encode(proof.bytes())),
-    };
-    let prefix = match ops {
-        ProverOps::Default => panic!("No point in generating a fixture for a default proof!"),
-        ProverOps::Groth16 => "groth16-fixture.json",
-        ProverOps::Plonk => "plonk-fixture.json",
-    };
-    let fixture_path = PathBuf::from("./");
-    std::fs::create_dir_all(&fixture_path).expect("failed to create fixture path");
-    std::fs::write(
-        fixture_path.join(prefix),
-        serde_json::to_string_pretty(&fixture).unwrap(),
-    )
-    .expect("failed to write fixture");
+/// Writes fixtures into a directory, atomically so that Foundry never reads half a fixture.
+pub struct FixtureWriter {
+    dir: PathBuf,
+    naming: FixtureNaming,
+}
+
+impl FixtureWriter {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            naming: FixtureNaming::Program,
+        }
+    }
+
+    /// Replaces the fixtures read by the Foundry tests.
+    pub fn foundry() -> Self {
+        Self::new(FOUNDRY_FIXTURES)
+    }
+
+    pub fn with_naming(mut self, naming: FixtureNaming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    pub fn path(&self, program: Program, ops: &ProverOps, slot: u32) -> BackendResult<PathBuf> {
+        let mode = match ops {
+            ProverOps::Default => return Err("fixtures need a Groth16 or Plonk proof".into()),
+            ProverOps::Groth16 => "groth16",
+            ProverOps::Plonk => "plonk",
+        };
+        let name = match self.naming {
+            FixtureNaming::Program => format!("{}-{}.json", program, mode),
+            FixtureNaming::Slot => format!("{}-{}-{}.json", program, mode, slot),
+        };
+        Ok(self.dir.join(name))
+    }
+
+    /// Writes the fixture of a Groth16 or Plonk proof of either program and returns its path.
+    pub fn write(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vkeys: &ProgramVkeys,
+        ops: &ProverOps,
+    ) -> BackendResult<PathBuf> {
+        let fixture = ProofFixture::new(proof, vkeys)?;
+        let path = self.path(fixture.program(), ops, fixture.slot)?;
+        crate::write_atomically(&path, serde_json::to_string_pretty(&fixture)?.as_bytes())?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixtureWriter, ProgramVkeys, ProofFixture};
+    use crate::{backend::BackendKind, proof::Program, ProverOps, SpectreProver};
+
+    #[test]
+    fn test_foundry_fixtures() {
+        // only the verifying keys are needed, which do not depend on the backend
+        let vkeys = ProgramVkeys::new(&SpectreProver::with_backend(BackendKind::Mock.build()));
+        let writer = FixtureWriter::foundry();
+        let [step, rotation] = [Program::Step, Program::Rotation].map(|program| {
+            let path = writer.path(program, &ProverOps::Groth16, 0).unwrap();
+            let fixture = ProofFixture::load(&path).unwrap();
+            assert_eq!(fixture.program(), program, "{}", path.display());
+            if let Err(e) = fixture.verify(&vkeys) {
+                panic!(
+                    "{}: {}, regenerate the fixtures as described in the README",
+                    path.display(),
+                    e
+                );
+            }
+            fixture
+        });
+        // the verifier is deployed with the vkeys of both fixtures
+        assert_eq!(step.rotation_vkey, rotation.vkey);
+        assert_eq!(rotation.step_vkey, step.vkey);
+    }
 }
@@ -0,0 +1,105 @@
+use crate::{
+    backend::BackendResult,
+    proof::{Program, PublicOutputs},
+    SpectreProver,
+};
+use serde::Deserialize;
+use sp1_sdk::{HashableKey, SP1ProofWithPublicValues, SP1VerifyingKey};
+use sp1_verifier::{Groth16Verifier, PlonkVerifier, GROTH16_VK_BYTES, PLONK_VK_BYTES};
+use std::path::Path;
+
+/// The fields of a step or rotation fixture that are needed to verify it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixtureProof {
+    pub vkey: String,
+    pub public_values: String,
+    pub proof: String,
+}
+
+/// A proof on disk, saved with `SP1ProofWithPublicValues::save` or written as a fixture.
+pub enum ProofFile {
+    Proof(SP1ProofWithPublicValues),
+    Fixture(FixtureProof),
+}
+
+impl ProofFile {
+    pub fn load(path: impl AsRef<Path>) -> BackendResult<Self> {
+        let bytes = std::fs::read(path.as_ref())?;
+        if let Ok(fixture) = serde_json::from_slice::<FixtureProof>(&bytes) {
+            return Ok(ProofFile::Fixture(fixture));
+        }
+        Ok(ProofFile::Proof(SP1ProofWithPublicValues::load(path)?))
+    }
+
+    pub fn public_values(&self) -> BackendResult<Vec<u8>> {
+        match self {
+            ProofFile::Proof(proof) => Ok(proof.public_values.as_slice().to_vec()),
+            ProofFile::Fixture(fixture) => decode_hex(&fixture.public_values),
+        }
+    }
+}
+
+/// What a proof commits to and the key it was verified against.
+#[derive(Debug, Clone)]
+pub struct Inspection {
+    pub program: Program,
+    pub outputs: PublicOutputs,
+    pub vkey: String,
+}
+
+pub(crate) fn decode_hex(value: &str) -> BackendResult<Vec<u8>> {
+    Ok(hex::decode(value.trim_start_matches("0x"))?)
+}
+
+/// Verifies a Groth16 or Plonk fixture with the verifying keys bundled with the SP1 verifier,
+/// without setting up any program.
+pub fn verify_fixture(fixture: &FixtureProof) -> BackendResult<()> {
+    let proof = decode_hex(&fixture.proof)?;
+    let public_values = decode_hex(&fixture.public_values)?;
+    let groth16 = Groth16Verifier::verify(&proof, &public_values, &fixture.vkey, &GROTH16_VK_BYTES);
+    if groth16.is_ok() {
+        return Ok(());
+    }
+    PlonkVerifier::verify(&proof, &public_values, &fixture.vkey, &PLONK_VK_BYTES).map_err(|plonk| {
+        format!(
+            "not a valid Groth16 ({:?}) or Plonk ({:?}) proof",
+            groth16, plonk
+        )
+        .into()
+    })
+}
+
+/// Verifies a saved proof or fixture and decodes its public values.
+///
+/// Saved proofs are verified with the backend of `spectre_prover`, against `vk` or else the
+/// verifying key of the program the public values belong to. Fixtures carry their verifying key,
+/// `vk` only has to match it.
+pub fn inspect_proof(
+    spectre_prover: &SpectreProver,
+    file: &ProofFile,
+    vk: Option<&SP1VerifyingKey>,
+) -> BackendResult<Inspection> {
+    let (program, outputs) = PublicOutputs::detect(&file.public_values()?)?;
+    let vkey = match file {
+        ProofFile::Proof(proof) => {
+            let vk = vk.unwrap_or_else(|| &spectre_prover.keys(program).1);
+            spectre_prover.verify(proof, vk)?;
+            vk.bytes32()
+        }
+        ProofFile::Fixture(fixture) => {
+            if let Some(vk) = vk.filter(|vk| vk.bytes32() != fixture.vkey) {
+                return Err(
+                    format!("fixture is for vkey {}, not {}", fixture.vkey, vk.bytes32()).into(),
+                );
+            }
+            verify_fixture(fixture)?;
+            fixture.vkey.clone()
+        }
+    };
+    Ok(Inspection {
+        program,
+        outputs,
+        vkey,
+    })
+}
@@ -1,8 +1,32 @@
+pub mod aligned;
+pub mod backend;
+pub mod cache;
+pub mod cycles;
 pub mod eth;
 pub mod fixture;
+pub mod inspect;
+pub mod proof;
+pub mod store;
+use backend::{BackendKind, BackendResult, ProverBackend};
+use committee_iso::utils::compute_digest;
+use cycles::CycleReport;
+use proof::{CompressedProof, Program, ProgramInputs};
 use rotation_iso::types::RotationCircuitInputs;
-use sp1_sdk::{include_elf, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+use sp1_sdk::{
+    include_elf, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey,
+    SP1_CIRCUIT_VERSION,
+};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Instant,
+};
 use step_iso::types::{SyncStepArgs, SyncStepCircuitInput};
+
+pub const STEP_ELF: &[u8] = include_elf!("sp1-step");
+pub const ROTATION_ELF: &[u8] = include_elf!("sp1-rotation");
+
 pub enum ProofCompressionBool {
     Compressed,
     Uncompressed,
@@ -14,106 +38,361 @@ pub enum ProverOps {
     Plonk,
 }
 
+impl ProverOps {
+    pub fn mode(&self, compressed: &ProofCompressionBool) -> SP1ProofMode {
+        match (self, compressed) {
+            (ProverOps::Default, ProofCompressionBool::Compressed) => SP1ProofMode::Compressed,
+            (ProverOps::Default, ProofCompressionBool::Uncompressed) => SP1ProofMode::Core,
+            (ProverOps::Groth16, _) => SP1ProofMode::Groth16,
+            (ProverOps::Plonk, _) => SP1ProofMode::Plonk,
+        }
+    }
+}
+
+type Keys = (SP1ProvingKey, SP1VerifyingKey);
+
+/// Writes `bytes` to a temporary file next to `path` and renames it, so that a crash
+/// never leaves a partial file at `path`.
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let partial = path.with_extension("partial");
+    let mut file = std::fs::File::create(&partial)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    std::fs::rename(partial, path)
+}
+
+/// Prover for the step and rotation programs that keeps one backend and sets up every program once.
+///
+/// Keys are set up on first use. With a key directory they are read from and written to disk,
+/// named after the SP1 version and a digest of the ELF so that outdated keys are never loaded.
+pub struct SpectreProver {
+    backend: Box<dyn ProverBackend>,
+    key_dir: Option<PathBuf>,
+    step_keys: OnceLock<Keys>,
+    rotation_keys: OnceLock<Keys>,
+}
+
+impl Default for SpectreProver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SpectreProver {
+    /// Proves with the backend chosen by the SP1 environment variables.
+    pub fn new() -> Self {
+        Self::with_backend(BackendKind::Env.build())
+    }
+
+    pub fn with_backend(backend: Box<dyn ProverBackend>) -> Self {
+        sp1_sdk::utils::setup_logger();
+        Self {
+            backend,
+            key_dir: None,
+            step_keys: OnceLock::new(),
+            rotation_keys: OnceLock::new(),
+        }
+    }
+
+    /// Persists the proving and verifying keys in `key_dir`.
+    pub fn with_key_dir(mut self, key_dir: impl Into<PathBuf>) -> Self {
+        self.key_dir = Some(key_dir.into());
+        self
+    }
+
+    pub fn backend(&self) -> &dyn ProverBackend {
+        self.backend.as_ref()
+    }
+
+    pub fn step_keys(&self) -> &Keys {
+        self.step_keys
+            .get_or_init(|| self.load_or_setup("step", STEP_ELF))
+    }
+
+    pub fn rotation_keys(&self) -> &Keys {
+        self.rotation_keys
+            .get_or_init(|| self.load_or_setup("rotation", ROTATION_ELF))
+    }
+
+    /// Where the keys of `elf` are kept, per SP1 version since keys of other versions do not prove.
+    fn key_path(&self, program: &str, elf: &[u8]) -> Option<PathBuf> {
+        self.key_dir.as_ref().map(|key_dir| {
+            key_dir.join(format!(
+                "{}-{}-{}.bin",
+                program,
+                SP1_CIRCUIT_VERSION,
+                hex::encode(&compute_digest(elf)[..8])
+            ))
+        })
+    }
+
+    fn load_or_setup(&self, program: &str, elf: &[u8]) -> Keys {
+        let Some(path) = self.key_path(program, elf) else {
+            return self.backend.setup(elf);
+        };
+        if let Ok(bytes) = std::fs::read(&path) {
+            match bincode::deserialize(&bytes) {
+                Ok(keys) => return keys,
+                Err(e) => println!("Ignoring unreadable keys at {}: {}", path.display(), e),
+            }
+        }
+        let keys = self.backend.setup(elf);
+        if let Err(e) = write_atomically(
+            &path,
+            &bincode::serialize(&keys).expect("Failed to serialize keys"),
+        ) {
+            println!("Failed to persist keys to {}: {}", path.display(), e);
+        }
+        keys
+    }
+
+    fn prove(
+        &self,
+        (pk, vk): &Keys,
+        stdin: &SP1Stdin,
+        mode: SP1ProofMode,
+    ) -> BackendResult<(SP1ProofWithPublicValues, SP1VerifyingKey)> {
+        let start_time = Instant::now();
+        let proof = self.backend.prove(pk, stdin, mode)?;
+        println!("Successfully generated proof!");
+        let duration = start_time.elapsed();
+        println!("Elapsed time: {:?}", duration);
+        Ok((proof, vk.clone()))
+    }
+
+    pub fn verify(
+        &self,
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+    ) -> BackendResult<()> {
+        self.backend.verify(proof, vk)
+    }
+
+    /// Executes the step program without proving it and reports the cycles it took.
+    pub fn execute_step(
+        &self,
+        commitment: [u8; 32],
+        sync_step_args: SyncStepArgs,
+    ) -> BackendResult<CycleReport> {
+        let stdin = step_inputs(commitment, sync_step_args).stdin();
+        let (_, report) = self.backend.execute(STEP_ELF, &stdin)?;
+        Ok(CycleReport::new("step", &report))
+    }
+
+    /// Executes the rotation program without proving it and reports the cycles it took.
+    pub fn execute_rotation(&self, inputs: RotationCircuitInputs) -> BackendResult<CycleReport> {
+        let (_, report) = self
+            .backend
+            .execute(ROTATION_ELF, &ProgramInputs::Rotation(inputs).stdin())?;
+        Ok(CycleReport::new("rotation", &report))
+    }
+
+    pub fn prove_step(
+        &self,
+        ops: &ProverOps,
+        commitment: [u8; 32],
+        sync_step_args: SyncStepArgs,
+        compressed: &ProofCompressionBool,
+    ) -> BackendResult<(SP1ProofWithPublicValues, SP1VerifyingKey)> {
+        let stdin = step_inputs(commitment, sync_step_args).stdin();
+        self.prove(self.step_keys(), &stdin, ops.mode(compressed))
+    }
+
+    /// Proves a rotation, a [`ProverOps::Default`] rotation proof is compressed.
+    pub fn prove_rotation(
+        &self,
+        ops: &ProverOps,
+        inputs: RotationCircuitInputs,
+    ) -> BackendResult<(SP1ProofWithPublicValues, SP1VerifyingKey)> {
+        self.prove(
+            self.rotation_keys(),
+            &ProgramInputs::Rotation(inputs).stdin(),
+            ops.mode(&ProofCompressionBool::Compressed),
+        )
+    }
+
+    pub fn keys(&self, program: Program) -> &Keys {
+        match program {
+            Program::Step => self.step_keys(),
+            Program::Rotation => self.rotation_keys(),
+        }
+    }
+
+    /// First stage: a compressed proof that can be stored and wrapped for submission later.
+    pub fn prove_compressed(&self, inputs: &ProgramInputs) -> BackendResult<CompressedProof> {
+        let program = inputs.program();
+        let (proof, vk) = self.prove(
+            self.keys(program),
+            &inputs.stdin(),
+            SP1ProofMode::Compressed,
+        )?;
+        Ok(CompressedProof { program, proof, vk })
+    }
+
+    /// Second stage: wraps a compressed proof into the Groth16 or Plonk proof of `ops`.
+    pub fn wrap(
+        &self,
+        compressed: &CompressedProof,
+        ops: &ProverOps,
+    ) -> BackendResult<(SP1ProofWithPublicValues, SP1VerifyingKey)> {
+        if let ProverOps::Default = ops {
+            return Err("a compressed proof is already a default proof".into());
+        }
+        let start_time = Instant::now();
+        let proof = self.backend.wrap(
+            &compressed.vk,
+            &compressed.proof,
+            ops.mode(&ProofCompressionBool::Compressed),
+        )?;
+        println!("Successfully wrapped {} proof!", compressed.program);
+        println!("Elapsed time: {:?}", start_time.elapsed());
+        Ok((proof, compressed.vk.clone()))
+    }
+}
+
+fn step_inputs(commitment: [u8; 32], sync_step_args: SyncStepArgs) -> ProgramInputs {
+    ProgramInputs::Step(SyncStepCircuitInput {
+        args: sync_step_args,
+        commitment,
+    })
+}
+
+/// Sets up the step program and proves a single step, prefer a long-lived [`SpectreProver`].
 pub fn generate_step_proof_sp1(
     ops: &ProverOps,
     commitment: [u8; 32],
     sync_step_args: SyncStepArgs,
     compressed: &ProofCompressionBool,
 ) -> (SP1ProofWithPublicValues, SP1VerifyingKey) {
-    use std::time::Instant;
-    sp1_sdk::utils::setup_logger();
-    let start_time = Instant::now();
-    let inputs: SyncStepCircuitInput = SyncStepCircuitInput {
-        args: sync_step_args,
-        commitment,
-    };
-    #[allow(deprecated)]
-    let client = ProverClient::new();
-    let mut stdin = SP1Stdin::new();
-    stdin.write_vec(borsh::to_vec(&inputs).expect("Failed to serialize"));
-
-    let (proof, _, vk) = match ops {
-        ProverOps::Default => {
-            const STEP_ELF: &[u8] = include_elf!("sp1-step");
-            let (pk, vk) = client.setup(STEP_ELF);
-            let proof = match compressed {
-                ProofCompressionBool::Compressed => client
-                    .prove(&pk, &stdin)
-                    .compressed()
-                    .run()
-                    .expect("failed to generate proof"),
-                ProofCompressionBool::Uncompressed => client
-                    .prove(&pk, &stdin)
-                    .run()
-                    .expect("failed to generate proof"),
-            };
-            (proof, pk, vk)
-        }
-        ProverOps::Groth16 => {
-            const STEP_ELF: &[u8] = include_elf!("sp1-step");
-            let (pk, vk) = client.setup(STEP_ELF);
-            let proof = client
-                .prove(&pk, &stdin)
-                .groth16()
-                .run()
-                .expect("failed to generate proof");
-            (proof, pk, vk)
-        }
-        ProverOps::Plonk => {
-            const STEP_ELF: &[u8] = include_elf!("sp1-step");
-            let (pk, vk) = client.setup(STEP_ELF);
-            let proof = client
-                .prove(&pk, &stdin)
-                .plonk()
-                .run()
-                .expect("failed to generate proof");
-            (proof, pk, vk)
-        }
-    };
-    println!("Successfully generated proof!");
-    let duration = start_time.elapsed();
-    println!("Elapsed time: {:?}", duration);
-    (proof, vk)
+    SpectreProver::new()
+        .prove_step(ops, commitment, sync_step_args, compressed)
+        .expect("failed to generate proof")
 }
 
+/// Sets up the rotation program and proves a single rotation, prefer a long-lived [`SpectreProver`].
 pub fn generate_rotation_proof_sp1(
     ops: &ProverOps,
     inputs: RotationCircuitInputs,
 ) -> (SP1ProofWithPublicValues, SP1VerifyingKey) {
-    use std::time::Instant;
-    //sp1_sdk::utils::setup_logger();
-    let start_time = Instant::now();
-    #[allow(deprecated)]
-    let client = ProverClient::new();
-    let mut stdin = SP1Stdin::new();
-    stdin.write_vec(borsh::to_vec(&inputs).expect("Failed to serialize"));
-    let (proof, _, vk) = match ops {
-        ProverOps::Default => {
-            panic!("Recursive Step Proof for Default Prover mode is not supported!")
+    SpectreProver::new()
+        .prove_rotation(ops, inputs)
+        .expect("failed to generate proof")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpectreProver, STEP_ELF};
+    use crate::backend::{BackendKind, BackendResult, ProverBackend};
+    use sp1_sdk::{
+        ExecutionReport, HashableKey, SP1ProofMode, SP1ProofWithPublicValues, SP1ProvingKey,
+        SP1PublicValues, SP1Stdin, SP1VerifyingKey,
+    };
+    use std::{
+        path::{Path, PathBuf},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    /// The mock backend, counting how often a program is set up.
+    struct Counting {
+        inner: Box<dyn ProverBackend>,
+        setups: Arc<AtomicUsize>,
+    }
+
+    impl ProverBackend for Counting {
+        fn setup(&self, elf: &[u8]) -> (SP1ProvingKey, SP1VerifyingKey) {
+            self.setups.fetch_add(1, Ordering::SeqCst);
+            self.inner.setup(elf)
+        }
+
+        fn execute(
+            &self,
+            elf: &[u8],
+            stdin: &SP1Stdin,
+        ) -> BackendResult<(SP1PublicValues, ExecutionReport)> {
+            self.inner.execute(elf, stdin)
         }
-        ProverOps::Groth16 => {
-            const RECURSIVE_ELF: &[u8] = include_elf!("sp1-rotation");
-            let (pk, vk) = client.setup(RECURSIVE_ELF);
-            let proof = client
-                .prove(&pk, &stdin)
-                .groth16()
-                .run()
-                .expect("failed to generate proof");
-            (proof, pk, vk)
+
+        fn prove(
+            &self,
+            pk: &SP1ProvingKey,
+            stdin: &SP1Stdin,
+            mode: SP1ProofMode,
+        ) -> BackendResult<SP1ProofWithPublicValues> {
+            self.inner.prove(pk, stdin, mode)
         }
-        ProverOps::Plonk => {
-            const RECURSIVE_ELF: &[u8] = include_elf!("sp1-rotation");
-            let (pk, vk) = client.setup(RECURSIVE_ELF);
-            let proof = client
-                .prove(&pk, &stdin)
-                .plonk()
-                .run()
-                .expect("failed to generate proof");
-            (proof, pk, vk)
+
+        fn verify(
+            &self,
+            proof: &SP1ProofWithPublicValues,
+            vk: &SP1VerifyingKey,
+        ) -> BackendResult<()> {
+            self.inner.verify(proof, vk)
         }
-    };
-    println!("Successfully generated proof!");
-    let duration = start_time.elapsed();
-    println!("Elapsed time: {:?}", duration);
-    (proof, vk)
+
+        fn wrap(
+            &self,
+            vk: &SP1VerifyingKey,
+            proof: &SP1ProofWithPublicValues,
+            mode: SP1ProofMode,
+        ) -> BackendResult<SP1ProofWithPublicValues> {
+            self.inner.wrap(vk, proof, mode)
+        }
+    }
+
+    fn key_dir(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("spectre-keys-{}-{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn counting_prover(key_dir: &Path) -> (SpectreProver, Arc<AtomicUsize>) {
+        let setups = Arc::new(AtomicUsize::new(0));
+        let backend = Counting {
+            inner: BackendKind::Mock.build(),
+            setups: setups.clone(),
+        };
+        let prover = SpectreProver::with_backend(Box::new(backend)).with_key_dir(key_dir);
+        (prover, setups)
+    }
+
+    #[test]
+    fn test_cached_keys() {
+        let key_dir = key_dir("cached");
+        let (prover, setups) = counting_prover(&key_dir);
+        let vkey = prover.step_keys().1.bytes32();
+        assert_eq!(setups.load(Ordering::SeqCst), 1);
+        let path = prover.key_path("step", STEP_ELF).unwrap();
+        assert!(path.exists());
+        assert!(!path.with_extension("partial").exists());
+
+        // a restarted prover reads the keys instead of setting the program up again
+        let (restarted, setups) = counting_prover(&key_dir);
+        assert_eq!(restarted.step_keys().1.bytes32(), vkey);
+        assert_eq!(setups.load(Ordering::SeqCst), 0);
+        std::fs::remove_dir_all(key_dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_keys() {
+        let key_dir = key_dir("corrupt");
+        let (prover, setups) = counting_prover(&key_dir);
+        let path = prover.key_path("step", STEP_ELF).unwrap();
+        std::fs::create_dir_all(&key_dir).unwrap();
+        std::fs::write(&path, b"not bincode").unwrap();
+
+        let vkey = prover.step_keys().1.bytes32();
+        assert_eq!(setups.load(Ordering::SeqCst), 1);
+        // the corrupt file is replaced by the keys that were set up
+        let (restarted, setups) = counting_prover(&key_dir);
+        assert_eq!(restarted.step_keys().1.bytes32(), vkey);
+        assert_eq!(setups.load(Ordering::SeqCst), 0);
+        std::fs::remove_dir_all(key_dir).unwrap();
+    }
 }
@@ -3,94 +3,717 @@
     In production a loop that manages contracts on multiple networks can be used.
     This program serves as a test entry for a production environment.
 */
+#![allow(incomplete_features)]
+#![feature(generic_const_exprs)]
 
 use alloy_sol_types::SolType;
-use committee_iso::utils::{commit_to_keys_with_sign, decode_pubkeys_x};
+use beacon_api_client::{mainnet::Client as BeaconClient, BlockId};
+use clap::{Parser, Subcommand, ValueEnum};
+use committee_iso::{
+    types::CommitteeUpdateArgs,
+    utils::{commit_to_keys_with_sign, decode_pubkeys_x},
+    witness::{
+        convert_witness, Witness, WitnessEncoding, WitnessError, WitnessFile, WitnessMetadata,
+    },
+};
+use eth_types::{
+    configure_network, fork_at_slot, Chiado, Gnosis, Minimal, Period, Slot, Spec, Testnet,
+};
 use ethers::types::Bytes;
 use hex::FromHex;
-use preprocessor::{get_current_sync_step, get_light_client_update_at_slot};
-use prover::{eth::SpectreContractClient, generate_rotation_proof_sp1, generate_step_proof_sp1};
+use light_client_store::LightClientStore;
+use preprocessor::{
+    checkpoint::bootstrap_from_checkpoint,
+    events::{subscribe, Backoff, LightClientEvent, TriggerFilter},
+    fetch_rotation_args_range, get_block_header, get_network_config,
+    offline::{rotation_args_from_files, step_args_from_files},
+    store::{
+        bootstrap_store, rotation_args_from_store, step_args_from_store, sync_store,
+        sync_store_to_period,
+    },
+    BeaconSource, MultiSource, Policy, PreprocessorError,
+};
+use prover::{
+    aligned::{submit_and_wait, AlignedBatcher, AlignedSubmission},
+    backend::{BackendKind, BackendResult},
+    cache::{CachedCommittee, CachedSource, LightClientCache},
+    cycles::CycleBudgets,
+    eth::SpectreContractClient,
+    inspect::{inspect_proof, ProofFile},
+    proof::{CompressedProof, ProgramInputs},
+    store::{open_proof_store, ProofStore, StoredProof},
+    ProverOps, SpectreProver,
+};
+use reqwest::Url;
 use rotation_iso::types::{RotationCircuitInputs, WrappedOutput as WrappedRotationOutput};
-use sp1_sdk::HashableKey;
-use std::{process::Command, time::Duration};
-use step_iso::types::SyncStepCircuitInput;
+use sp1_sdk::{HashableKey, SP1VerifyingKey};
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use step_iso::types::WrappedOutput as WrappedStepOutput;
-use tokio::sync::Semaphore;
+use step_iso::{
+    compress_keys,
+    types::{SyncStepArgs, SyncStepCircuitInput},
+};
+use tokio::sync::{mpsc, Semaphore};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Parser)]
+#[command(about = "Spectre light client prover")]
+struct Cli {
+    /// Beacon chain preset of the network
+    #[arg(long, global = true, value_enum, default_value_t = Preset::Testnet)]
+    preset: Preset,
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Preset {
+    /// Sepolia, 512 member sync committees
+    Testnet,
+    /// Local devnets, 32 member sync committees
+    Minimal,
+    /// Gnosis Chain, 16 slot epochs and 512 epoch periods
+    Gnosis,
+    /// Chiado, the Gnosis Chain testnet
+    Chiado,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Keep the Spectre contract in sync with the beacon chain (default)
+    Run,
+    /// Build a circuit witness from light client objects on disk (beacon API JSON or `.ssz`)
+    Witness {
+        #[command(subcommand)]
+        witness: WitnessCommand,
+    },
+    /// Execute a program on a witness file and print its cycle counts as JSON
+    Cycles {
+        /// `step`, `step-input` or `rotation`
+        #[arg(long, value_enum)]
+        kind: Kind,
+        path: PathBuf,
+        /// Fail if the cycles exceed the budgets in this file, e.g. `data/cycle_budget.json`
+        #[arg(long)]
+        budget: Option<PathBuf>,
+        /// Replace the counts of the program in the budget file with the measured ones
+        #[arg(long, requires = "budget")]
+        record: bool,
+    },
+    /// Verify a saved proof or a proof fixture offline and print its public values
+    Verify {
+        path: PathBuf,
+        /// Bincode encoded verifying key, defaults to the key of the program that was proven
+        #[arg(long)]
+        vk: Option<PathBuf>,
+    },
+    /// Prove a witness file and settle it through Aligned Layer instead of the Spectre contract
+    ///
+    /// Prints the batch inclusion that `AlignedLightClientVerifier` consumes.
+    Aligned {
+        /// `step`, `step-input` or `rotation`
+        #[arg(long, value_enum)]
+        kind: Kind,
+        path: PathBuf,
+        /// Seconds to wait for the batch to be verified on Ethereum
+        #[arg(long, default_value_t = 900)]
+        timeout: u64,
+    },
+    /// Verify the bootstrap at a trusted block root and print the verifier constructor arguments
+    Checkpoint {
+        /// Root of a finalized block obtained out of band, e.g. from a block explorer
+        #[arg(long)]
+        block_root: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum WitnessCommand {
+    /// Write a `SyncStepCircuitInput` from a finality update
+    Step {
+        #[arg(long)]
+        finality_update: PathBuf,
+        /// Bootstrap whose current sync committee signed the update
+        #[arg(long)]
+        bootstrap: PathBuf,
+        #[arg(long, default_value = "step_witness.json")]
+        out: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Write a `RotationCircuitInputs` from a light client update
+    Rotation {
+        #[arg(long)]
+        update: PathBuf,
+        /// Bootstrap whose current sync committee signed the update
+        #[arg(long)]
+        bootstrap: PathBuf,
+        /// Bootstrap at the finalized block of the update, defaults to `--bootstrap`
+        #[arg(long)]
+        finalized_bootstrap: Option<PathBuf>,
+        #[arg(long, default_value = "rotation_witness.json")]
+        out: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Write a `CommitteeUpdateArgs` for each of `count` periods, fetched from `BEACON_RPC_URLS`
+    /// in pages and kept in `LIGHT_CLIENT_CACHE`
+    Committees {
+        #[arg(long)]
+        start_period: u64,
+        #[arg(long, default_value_t = 1)]
+        count: u64,
+        /// Files are named `committee_update_<period>.<format>`
+        #[arg(long, default_value = ".")]
+        out_dir: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+    /// Check a witness file of any version, including raw legacy JSON
+    Validate {
+        #[arg(long, value_enum)]
+        kind: Kind,
+        path: PathBuf,
+    },
+    /// Re-encode a witness file
+    Convert {
+        #[arg(long, value_enum)]
+        kind: Kind,
+        input: PathBuf,
+        #[arg(long)]
+        out: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// JSON with hex encoded bytes
+    Json,
+    Borsh,
+}
+
+impl Format {
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Borsh => "borsh",
+        }
+    }
+}
+
+impl From<Format> for WitnessEncoding {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Json => WitnessEncoding::Json,
+            Format::Borsh => WitnessEncoding::Borsh,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Kind {
+    /// `SyncStepArgs`, e.g. `data/sync_step_512.json`
+    Step,
+    /// `SyncStepCircuitInput`, as written by `witness step`
+    StepInput,
+    /// `CommitteeUpdateArgs`, e.g. `data/rotation_512.json`
+    Committee,
+    /// `RotationCircuitInputs`, as written by `witness rotation`
+    Rotation,
+}
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Commands::Run);
+    if let (
+        Preset::Minimal,
+        Commands::Run
+        | Commands::Checkpoint { .. }
+        | Commands::Witness {
+            witness:
+                WitnessCommand::Step { .. }
+                | WitnessCommand::Rotation { .. }
+                | WitnessCommand::Committees { .. },
+        },
+    ) = (&cli.preset, &command)
+    {
+        configure_devnet().await;
+    }
+    match (command, cli.preset) {
+        (Commands::Run, Preset::Testnet) => run::<Testnet>().await,
+        (Commands::Run, Preset::Minimal) => run::<Minimal>().await,
+        (Commands::Run, Preset::Gnosis) => run::<Gnosis>().await,
+        (Commands::Run, Preset::Chiado) => run::<Chiado>().await,
+        (Commands::Witness { witness }, preset) => {
+            let result = match preset {
+                Preset::Testnet => write_witness::<Testnet>(witness).await,
+                Preset::Minimal => write_witness::<Minimal>(witness).await,
+                Preset::Gnosis => write_witness::<Gnosis>(witness).await,
+                Preset::Chiado => write_witness::<Chiado>(witness).await,
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to build witness: {}", e);
+                std::process::exit(1);
+            }
+        }
+        (
+            Commands::Cycles {
+                kind,
+                path,
+                budget,
+                record,
+            },
+            _,
+        ) => {
+            if let Err(e) = print_cycles(kind, &path, budget.as_deref(), record) {
+                eprintln!("Failed to execute program: {}", e);
+                std::process::exit(1);
+            }
+        }
+        (Commands::Verify { path, vk }, _) => {
+            if let Err(e) = print_inspection(&path, vk.as_deref()) {
+                eprintln!("Failed to verify proof: {}", e);
+                std::process::exit(1);
+            }
+        }
+        (
+            Commands::Aligned {
+                kind,
+                path,
+                timeout,
+            },
+            _,
+        ) => {
+            if let Err(e) = settle_on_aligned(kind, &path, Duration::from_secs(timeout)).await {
+                eprintln!("Failed to settle through Aligned: {}", e);
+                std::process::exit(1);
+            }
+        }
+        (Commands::Checkpoint { block_root }, preset) => {
+            let result = match preset {
+                Preset::Testnet => print_checkpoint::<Testnet>(&block_root).await,
+                Preset::Minimal => print_checkpoint::<Minimal>(&block_root).await,
+                Preset::Gnosis => print_checkpoint::<Gnosis>(&block_root).await,
+                Preset::Chiado => print_checkpoint::<Chiado>(&block_root).await,
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to verify checkpoint: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn print_cycles(
+    kind: Kind,
+    path: &Path,
+    budget: Option<&Path>,
+    record: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // nothing is proven, any backend that executes will do
+    let spectre_prover =
+        SpectreProver::with_backend(BackendKind::from_env_or(BackendKind::Execute).build());
+    let report = match load_inputs(kind, path)? {
+        ProgramInputs::Step(inputs) => {
+            spectre_prover.execute_step(inputs.commitment, inputs.args)?
+        }
+        ProgramInputs::Rotation(inputs) => spectre_prover.execute_rotation(inputs)?,
+    };
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    if let Some(budget) = budget {
+        let mut budgets = CycleBudgets::load(budget)?;
+        if record {
+            budgets.record(&report);
+            budgets.save(budget)?;
+        } else if let Err(exceeded) = budgets.check(&report) {
+            return Err(format!("over budget: {}", exceeded.join(", ")).into());
+        }
+    }
+    Ok(())
+}
+
+/// Loads the inputs of the program a witness file is for.
+fn load_inputs(kind: Kind, path: &Path) -> BackendResult<ProgramInputs> {
+    Ok(match kind {
+        Kind::Step => {
+            let args = WitnessFile::<SyncStepArgs>::load(path)?.witness;
+            let (keys, signs) = decode_pubkeys_x(compress_keys(args.pubkeys_uncompressed.clone()));
+            ProgramInputs::Step(SyncStepCircuitInput {
+                commitment: commit_to_keys_with_sign(&keys, &signs),
+                args,
+            })
+        }
+        Kind::StepInput => {
+            ProgramInputs::Step(WitnessFile::<SyncStepCircuitInput>::load(path)?.witness)
+        }
+        Kind::Rotation => {
+            ProgramInputs::Rotation(WitnessFile::<RotationCircuitInputs>::load(path)?.witness)
+        }
+        Kind::Committee => {
+            return Err("a committee update is part of a rotation witness".into())
+        }
+    })
+}
+
+async fn settle_on_aligned(kind: Kind, path: &Path, timeout: Duration) -> BackendResult<()> {
+    let inputs = load_inputs(kind, path)?;
+    let batcher = AlignedBatcher::from_env().await?;
+    let compressed = tokio::task::spawn_blocking(move || {
+        SpectreProver::with_backend(BackendKind::from_env_or(BackendKind::Cpu).build())
+            .prove_compressed(&inputs)
+    })
+    .await??;
+    let submission = AlignedSubmission::new(&compressed, batcher.address());
+    let inclusion = submit_and_wait(&batcher, &[submission], POLL_INTERVAL, timeout)
+        .await?
+        .remove(0);
+    let commitment = inclusion.commitment;
+    println!("Verified {} proof on Aligned", compressed.program);
+    println!(
+        "Public values: 0x{}",
+        hex::encode(compressed.proof.public_values.as_slice())
+    );
+    println!(
+        "Proof commitment: 0x{}",
+        hex::encode(commitment.proof_commitment)
+    );
+    println!(
+        "Program id commitment: 0x{}",
+        hex::encode(commitment.program_id_commitment)
+    );
+    println!(
+        "Proof generator: 0x{}",
+        hex::encode(commitment.proof_generator)
+    );
+    println!(
+        "Batch merkle root: 0x{}",
+        hex::encode(inclusion.batch_merkle_root)
+    );
+    println!("Merkle proof: 0x{}", hex::encode(inclusion.merkle_proof()));
+    println!("Index in batch: {}", inclusion.index_in_batch);
+    Ok(())
+}
+
+fn print_inspection(path: &Path, vk: Option<&Path>) -> BackendResult<()> {
+    let file = ProofFile::load(path)?;
+    let vk: Option<SP1VerifyingKey> = match vk {
+        Some(vk) => Some(bincode::deserialize(&std::fs::read(vk)?)?),
+        None => None,
+    };
+    let spectre_prover =
+        SpectreProver::with_backend(BackendKind::from_env_or(BackendKind::Cpu).build());
+    let inspection = inspect_proof(&spectre_prover, &file, vk.as_ref())?;
+    let outputs = inspection.outputs;
+    println!("Verified {} proof", inspection.program);
+    println!("Verifying Key: {}", inspection.vkey);
+    println!("Slot: {}", outputs.slot);
+    println!("Commitment: 0x{}", hex::encode(outputs.commitment));
+    println!(
+        "Finalized header root: 0x{}",
+        hex::encode(outputs.finalized_header_root)
+    );
+    if let Some(next_commitment) = outputs.next_commitment {
+        println!("Next commitment: 0x{}", hex::encode(next_commitment));
+    }
+    Ok(())
+}
+
+async fn print_checkpoint<S: Spec>(block_root: &str) -> Result<(), Box<dyn std::error::Error>>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let trusted_block_root = <[u8; 32]>::from_hex(block_root.trim_start_matches("0x"))?;
+    let checkpoint =
+        bootstrap_from_checkpoint::<S, _>(&beacon_source_from_env(), trusted_block_root).await?;
+    println!("Slot: {}", checkpoint.slot);
+    println!(
+        "Finalized header root: 0x{}",
+        hex::encode(checkpoint.finalized_header_root)
+    );
+    println!(
+        "Active committee commitment: 0x{}",
+        hex::encode(checkpoint.active_committee_commitment)
+    );
+    println!(
+        "Next committee commitment: 0x{}",
+        hex::encode(checkpoint.next_committee_commitment)
+    );
+    Ok(())
+}
+
+async fn write_witness<S: Spec>(witness: WitnessCommand) -> Result<(), Box<dyn std::error::Error>>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    match witness {
+        WitnessCommand::Step {
+            finality_update,
+            bootstrap,
+            out,
+            format,
+        } => {
+            let (args, commitment) = step_args_from_files::<S>(finality_update, bootstrap).await?;
+            let fork = fork_at_slot::<S>(args.attested_header.slot.parse()?).name;
+            WitnessFile::new(
+                SyncStepCircuitInput { args, commitment },
+                S::NAME,
+                fork,
+                S::NETWORK,
+            )
+            .save(&out, format.into())?;
+            println!("Witness written to {}", out.display());
+        }
+        WitnessCommand::Rotation {
+            update,
+            bootstrap,
+            finalized_bootstrap,
+            out,
+            format,
+        } => {
+            let finalized_bootstrap = finalized_bootstrap.unwrap_or(bootstrap.clone());
+            let ((args, committee), oc) =
+                rotation_args_from_files::<S>(update, bootstrap, finalized_bootstrap).await?;
+            let (keys, signs) = decode_pubkeys_x(oc);
+            let inputs = RotationCircuitInputs {
+                committee,
+                step: SyncStepCircuitInput {
+                    args,
+                    commitment: commit_to_keys_with_sign(&keys, &signs),
+                },
+            };
+            let fork = fork_at_slot::<S>(inputs.step.args.attested_header.slot.parse()?).name;
+            WitnessFile::new(inputs, S::NAME, fork, S::NETWORK).save(&out, format.into())?;
+            println!("Witness written to {}", out.display());
+        }
+        WitnessCommand::Committees {
+            start_period,
+            count,
+            out_dir,
+            format,
+        } => {
+            let beacon = CachedSource::<_, S>::new(beacon_source_from_env(), light_client_cache());
+            // updates of periods before the finalized one are final and can be cached
+            let finalized = get_block_header(&beacon, BlockId::Finalized).await?;
+            beacon.set_finalized_period(Slot(finalized.slot).period::<S>().0);
+            let committees =
+                fetch_rotation_args_range::<S, _>(&beacon, start_period, count).await?;
+            if (committees.len() as u64) < count {
+                println!(
+                    "No update available at Period: {}",
+                    start_period + committees.len() as u64
+                );
+            }
+            std::fs::create_dir_all(&out_dir)?;
+            for (period, committee) in (start_period..).zip(committees) {
+                let out = out_dir.join(format!("committee_update_{period}.{}", format.extension()));
+                let fork = fork_at_slot::<S>(committee.finalized_header.slot.parse()?).name;
+                WitnessFile::new(committee, S::NAME, fork, S::NETWORK).save(&out, format.into())?;
+                println!("Witness written to {}", out.display());
+            }
+        }
+        WitnessCommand::Validate { kind, path } => {
+            let metadata = match kind {
+                Kind::Step => validate_witness::<SyncStepArgs>(&path)?,
+                Kind::StepInput => validate_witness::<SyncStepCircuitInput>(&path)?,
+                Kind::Committee => validate_witness::<CommitteeUpdateArgs>(&path)?,
+                Kind::Rotation => validate_witness::<RotationCircuitInputs>(&path)?,
+            };
+            println!("Valid witness: {:?}", metadata);
+        }
+        WitnessCommand::Convert {
+            kind,
+            input,
+            out,
+            format,
+        } => {
+            let encoding = format.into();
+            match kind {
+                Kind::Step => convert_witness::<SyncStepArgs>(&input, &out, encoding)?,
+                Kind::StepInput => convert_witness::<SyncStepCircuitInput>(&input, &out, encoding)?,
+                Kind::Committee => convert_witness::<CommitteeUpdateArgs>(&input, &out, encoding)?,
+                Kind::Rotation => convert_witness::<RotationCircuitInputs>(&input, &out, encoding)?,
+            };
+            println!("Witness written to {}", out.display());
+        }
+    }
+    Ok(())
+}
+
+fn validate_witness<T: Witness>(path: &Path) -> Result<WitnessMetadata, WitnessError> {
+    let file = WitnessFile::<T>::load(path)?;
+    file.validate()?;
+    Ok(file.metadata)
+}
+
+async fn run<S: Spec>()
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
     let abi = r#"[{"inputs":[{"internalType":"address","name":"_verifier","type":"address"},{"internalType":"bytes32","name":"_committeeProgramVKey","type":"bytes32"},{"internalType":"bytes32","name":"_stepProgramVKey","type":"bytes32"},{"internalType":"bytes32","name":"_finalizedHeaderRoot","type":"bytes32"},{"internalType":"bytes32","name":"_activeCommitteeCommitment","type":"bytes32"},{"internalType":"uint32","name":"_activeSlot","type":"uint32"}],"stateMutability":"nonpayable","type":"constructor"},{"inputs":[],"name":"activeCommitteeCommitment","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"activeSlot","outputs":[{"internalType":"uint32","name":"","type":"uint32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"committeeProgramVKey","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"finalizedHeaderRoot","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"stepProgramVKey","outputs":[{"internalType":"bytes32","name":"","type":"bytes32"}],"stateMutability":"view","type":"function"},{"inputs":[],"name":"verifier","outputs":[{"internalType":"address","name":"","type":"address"}],"stateMutability":"view","type":"function"},{"inputs":[{"internalType":"bytes","name":"_publicValues","type":"bytes"},{"internalType":"bytes","name":"_proofBytes","type":"bytes"}],"name":"verifyRotationProof","outputs":[],"stateMutability":"nonpayable","type":"function"},{"inputs":[{"internalType":"bytes","name":"_publicValues","type":"bytes"},{"internalType":"bytes","name":"_proofBytes","type":"bytes"}],"name":"verifyStepProof","outputs":[],"stateMutability":"nonpayable","type":"function"}]"#;
-    let contract = "0x61F8Bfb5DCD05a583261375767fbE121D9CD49E1";
+    // defaults to the Sepolia deployment
+    let contract = dotenv::var("SPECTRE_CONTRACT")
+        .unwrap_or("0x61F8Bfb5DCD05a583261375767fbE121D9CD49E1".to_string());
     let rpc_url = dotenv::var("SEPOLIA_RPC_URL").unwrap_or_default();
-    let chain_id = 11155111u64;
+    let chain_id = dotenv::var("CHAIN_ID")
+        .map(|id| id.parse().expect("Invalid CHAIN_ID"))
+        .unwrap_or(11155111u64);
     let client = SpectreContractClient {
-        contract: contract.to_string(),
+        contract,
         abi: serde_json::from_str(&abi).unwrap(),
         rpc_url,
         chain_id,
     };
-    let semaphore = std::sync::Arc::new(Semaphore::new(1));
+    let beacon = CachedSource::<_, S>::new(beacon_source_from_env(), light_client_cache());
+    let mut events = subscribe(beacon_urls_from_env(), Backoff::default());
+    let mut triggers = TriggerFilter::<S>::default();
+    let semaphore = Arc::new(Semaphore::new(1));
+    // programs are set up once, keys are kept across iterations and optionally across restarts
+    let backend = BackendKind::from_env_or(BackendKind::Env);
+    println!("Proving with the {} backend", backend);
+    let mut spectre_prover = SpectreProver::with_backend(backend.build());
+    if let Ok(key_dir) = dotenv::var("PROVER_KEY_DIR") {
+        spectre_prover = spectre_prover.with_key_dir(key_dir);
+    }
+    let spectre_prover = Arc::new(spectre_prover);
+    // every proof is kept, so an update that failed to land is resubmitted without proving again
+    let proofs: Arc<dyn ProofStore> = Arc::from(
+        open_proof_store(dotenv::var("PROOF_STORE").unwrap_or("proofs.db".to_string()))
+            .expect("Failed to open proof store"),
+    );
+    let mut store: Option<LightClientStore<S>> = None;
     loop {
         let last_known_slot: u32 = client.read_slot_value().await;
-        let next_update_slot = {
-            let mut x = 0;
-            while x <= last_known_slot {
-                x += 32 * 256;
+        let last_known_period = Slot(last_known_slot.into()).period::<S>();
+        let next_update_period = last_known_period.next();
+        let next_update_slot = next_update_period.start_slot::<S>();
+        // periods the contract has moved past are never requested again
+        if let Err(e) = beacon.cache().prune_before(last_known_period.0) {
+            println!("Failed to prune light client cache: {}", e);
+        }
+        prune_environment().await;
+        // follow the chain off-chain, starting at the header the contract last accepted
+        if store.is_none() {
+            match bootstrap_store::<S, _>(&beacon, client.read_finalized_header_root().await).await
+            {
+                Ok(bootstrapped) => store = Some(bootstrapped),
+                Err(e) => println!("Failed to bootstrap light client store: {}", e),
+            }
+        }
+        // only updates the store validated against the committees it follows are proven
+        let Some(store) = store.as_mut() else {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            continue;
+        };
+        let finality_update = match sync_store(&beacon, store).await {
+            Ok(finality_update) => finality_update,
+            Err(e) => {
+                println!("Failed to sync light client store: {}", e);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            }
+        };
+        println!(
+            "Light client store finalized Slot: {}, next committee known: {}",
+            store.finalized_header().slot,
+            store.next_sync_committee().is_some()
+        );
+        let sync_step = match step_args_from_store(store, finality_update).await {
+            Ok(sync_step) => sync_step,
+            Err(e) => {
+                println!("Failed to fetch sync step: {}", e);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
             }
-            x
         };
-        prune_environment().await;
-        let sync_step = get_current_sync_step().await;
         let target_slot = u64::from_str_radix(&sync_step.0.attested_header.slot, 10).unwrap();
         if target_slot <= last_known_slot.into() {
             println!("Contract is on top of chain!");
-            tokio::time::sleep(Duration::from_secs(10)).await;
+            wait_for_trigger(&mut events, &mut triggers).await;
             continue;
         }
-        let maybe_update_slot = get_light_client_update_at_slot((next_update_slot) as u64).await;
-        if maybe_update_slot.0.is_some() {
-            //target_slot % (32 * 256) == 0 {
-            let (sc, oc) = (maybe_update_slot.0.unwrap(), maybe_update_slot.1.unwrap());
-            //let (sc, oc) = get_light_client_update_at_slot((target_slot) as u64).await;
-            let (keys, signs) = decode_pubkeys_x(oc);
-            let commitment = commit_to_keys_with_sign(&keys, &signs);
+        // updates of periods before the finalized one are final and can be cached
+        if let Ok(finalized_slot) = sync_step.0.finalized_header.slot.parse::<u64>() {
+            beacon.set_finalized_period(Slot(finalized_slot).period::<S>().0);
+        }
+        // the store rotates into the period of the next committee once it finalized a header
+        // there, a contract that fell further behind is rotated from a store bootstrapped at it
+        let maybe_update_slot = match store.finalized_period().cmp(&next_update_period.0) {
+            Ordering::Less => None,
+            Ordering::Equal if store.next_sync_committee().is_none() => None,
+            Ordering::Equal => Some(rotation_args::<S, _>(&beacon, store).await),
+            Ordering::Greater => Some(
+                lagging_rotation_args::<S, _>(
+                    &beacon,
+                    client.read_finalized_header_root().await,
+                    next_update_period,
+                )
+                .await,
+            ),
+        };
+        let maybe_update_slot = match maybe_update_slot.transpose() {
+            Ok(update) => update,
+            Err(e) => {
+                println!("Failed to fetch light client update: {}", e);
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                continue;
+            }
+        };
+        if let Some((sc, commitment)) = maybe_update_slot {
             println!(
                 "Active Committee: {:?}",
                 format!("0x{}", hex::encode(commitment))
             );
             println!("Generating Rotation proof at: {}", &target_slot);
-            let permit = semaphore.clone().acquire_owned().await.unwrap();
-            let proof: (sp1_sdk::SP1ProofWithPublicValues, sp1_sdk::SP1VerifyingKey) =
-                tokio::task::spawn_blocking(move || {
-                    let proof = generate_rotation_proof_sp1(
-                        &prover::ProverOps::Groth16,
-                        RotationCircuitInputs {
-                            committee: sc.1,
-                            step: SyncStepCircuitInput {
-                                args: sc.0,
-                                commitment,
-                            },
-                        },
-                    );
-                    drop(permit);
-                    proof
-                })
-                .await
-                .expect("Prover Task failed!");
-            let payload: (Bytes, Bytes) = (
-                Bytes::from_hex(&format!(
-                    "0x{}",
-                    hex::encode(proof.0.public_values.as_slice())
-                ))
-                .unwrap(),
-                Bytes::from_hex(&format!("0x{}", hex::encode(proof.0.bytes()))).unwrap(),
-            );
-            println!("Verifying Key: {}", &proof.1.bytes32());
+            let inputs = ProgramInputs::Rotation(RotationCircuitInputs {
+                committee: sc.1,
+                step: SyncStepCircuitInput {
+                    args: sc.0,
+                    commitment,
+                },
+            });
+            let stored =
+                match prove_or_reuse::<S>(&proofs, &spectre_prover, &semaphore, inputs).await {
+                    Ok(stored) => stored,
+                    Err(e) => {
+                        println!("Failed to generate proof: {}", e);
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                        continue;
+                    }
+                };
+            println!("Verifying Key: {}", &stored.vkey);
             let circuit_out =
-                WrappedRotationOutput::abi_decode(&proof.0.public_values.as_slice(), false)
-                    .unwrap();
+                WrappedRotationOutput::abi_decode(&stored.public_values, false).unwrap();
             println!("Commitment: {:?}", &circuit_out.commitment);
             println!("Slot: {:?}", &circuit_out.slot);
+            let payload = submission_payload(&stored);
             match client.call_with_args("verifyRotationProof", payload).await {
                 Ok(_) => {}
                 Err(e) => {
@@ -99,33 +722,24 @@ async fn main() {
             }
         } else {
             println!("Generating Step proof at: {}", &target_slot);
-            let permit = semaphore.clone().acquire_owned().await.unwrap();
-            let proof: (sp1_sdk::SP1ProofWithPublicValues, sp1_sdk::SP1VerifyingKey) =
-                tokio::task::spawn_blocking(move || {
-                    let proof = generate_step_proof_sp1(
-                        &prover::ProverOps::Groth16,
-                        sync_step.1,
-                        sync_step.0,
-                        &prover::ProofCompressionBool::Uncompressed,
-                    );
-                    drop(permit);
-                    proof
-                })
-                .await
-                .expect("Prover Task failed!");
-            let payload: (Bytes, Bytes) = (
-                Bytes::from_hex(&format!(
-                    "0x{}",
-                    hex::encode(proof.0.public_values.as_slice())
-                ))
-                .unwrap(),
-                Bytes::from_hex(&format!("0x{}", hex::encode(proof.0.bytes()))).unwrap(),
-            );
-            println!("Verifying Key: {}", &proof.1.bytes32());
-            let circuit_out =
-                WrappedStepOutput::abi_decode(&proof.0.public_values.as_slice(), false).unwrap();
+            let inputs = ProgramInputs::Step(SyncStepCircuitInput {
+                args: sync_step.0,
+                commitment: sync_step.1,
+            });
+            let stored =
+                match prove_or_reuse::<S>(&proofs, &spectre_prover, &semaphore, inputs).await {
+                    Ok(stored) => stored,
+                    Err(e) => {
+                        println!("Failed to generate proof: {}", e);
+                        tokio::time::sleep(Duration::from_secs(10)).await;
+                        continue;
+                    }
+                };
+            println!("Verifying Key: {}", &stored.vkey);
+            let circuit_out = WrappedStepOutput::abi_decode(&stored.public_values, false).unwrap();
             println!("Commitment: {:?}", &circuit_out.commitment);
             println!("Slot: {:?}", &circuit_out.slot);
+            let payload = submission_payload(&stored);
             match client.call_with_args("verifyStepProof", payload).await {
                 Ok(_) => {}
                 Err(e) => {
@@ -134,11 +748,230 @@ async fn main() {
             }
         }
 
-        println!("Update Success, waiting 10 seconds...");
-        tokio::time::sleep(Duration::from_secs(10)).await;
+        println!("Update Success, waiting for the next light client update...");
+        wait_for_trigger(&mut events, &mut triggers).await;
+    }
+}
+
+/// Returns the current committee of `store`, which is final for its finalized period,
+/// from the cache or computed and cached.
+fn finalized_committee<S: Spec, B: BeaconSource>(
+    beacon: &CachedSource<B, S>,
+    store: &LightClientStore<S>,
+) -> CachedCommittee {
+    let period = store.finalized_period();
+    let pubkeys = store.current_sync_committee();
+    match beacon.cache().committee(period) {
+        Ok(Some(committee)) if &committee.pubkeys == pubkeys => return committee,
+        Ok(_) => {}
+        Err(e) => println!("Failed to read cached committee: {}", e),
+    }
+    let (keys, signs) = decode_pubkeys_x(pubkeys.clone());
+    let committee = CachedCommittee {
+        commitment: commit_to_keys_with_sign(&keys, &signs),
+        pubkeys: pubkeys.clone(),
+    };
+    if let Err(e) = beacon.cache().put_committee(period, &committee) {
+        println!("Failed to cache committee: {}", e);
+    }
+    committee
+}
+
+/// Builds the rotation out of the finalized period of `store` and returns it with the commitment
+/// to the committee that signs it.
+async fn rotation_args<S: Spec, B: BeaconSource>(
+    beacon: &CachedSource<B, S>,
+    store: &LightClientStore<S>,
+) -> preprocessor::Result<((SyncStepArgs, CommitteeUpdateArgs), [u8; 32])>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let args = rotation_args_from_store(beacon, store).await?;
+    Ok((args, finalized_committee(beacon, store).commitment))
+}
+
+/// Like [`rotation_args`] for a contract whose next period is behind the store, from a store
+/// bootstrapped at the finalized header of the contract and synced up to `period`.
+async fn lagging_rotation_args<S: Spec, B: BeaconSource>(
+    beacon: &CachedSource<B, S>,
+    trusted_block_root: [u8; 32],
+    period: Period,
+) -> preprocessor::Result<((SyncStepArgs, CommitteeUpdateArgs), [u8; 32])>
+where
+    [(); S::SYNC_COMMITTEE_SIZE]:,
+    [(); S::FINALIZED_HEADER_DEPTH]:,
+    [(); S::BYTES_PER_LOGS_BLOOM]:,
+    [(); S::MAX_EXTRA_DATA_BYTES]:,
+    [(); S::SYNC_COMMITTEE_ROOT_INDEX]:,
+    [(); S::SYNC_COMMITTEE_DEPTH]:,
+    [(); S::FINALIZED_HEADER_INDEX]:,
+{
+    let mut store = bootstrap_store::<S, _>(beacon, trusted_block_root).await?;
+    sync_store_to_period(beacon, &mut store, period.0).await?;
+    // a store stuck in an earlier period would rotate into a period the contract is past
+    if store.finalized_period() != period.0 {
+        return Err(PreprocessorError::Unvalidated {
+            slot: period.start_slot::<S>().0,
+        });
+    }
+    rotation_args(beacon, &store).await
+}
+
+/// Returns the stored Groth16 proof of `inputs`, or proves and stores it.
+async fn prove_or_reuse<S: Spec>(
+    proofs: &Arc<dyn ProofStore>,
+    spectre_prover: &Arc<SpectreProver>,
+    semaphore: &Arc<Semaphore>,
+    inputs: ProgramInputs,
+) -> BackendResult<StoredProof> {
+    let witness_hash = inputs.hash();
+    let program = inputs.program();
+    // proofs of an older build of the program do not verify against the deployed vkey
+    let vkey = {
+        let spectre_prover = spectre_prover.clone();
+        tokio::task::spawn_blocking(move || spectre_prover.keys(program).1.bytes32())
+            .await
+            .expect("Setup Task failed!")
+    };
+    if let Some(stored) = stored_proof(proofs, &witness_hash, "groth16", &vkey) {
+        println!("Reusing stored {} proof {}", stored.program, stored.id);
+        return Ok(stored);
+    }
+    let compressed = stored_proof(proofs, &witness_hash, "compressed", &vkey);
+    let permit = semaphore.clone().acquire_owned().await.unwrap();
+    let spectre_prover = spectre_prover.clone();
+    let proofs = proofs.clone();
+    tokio::task::spawn_blocking(move || -> BackendResult<StoredProof> {
+        let start_time = Instant::now();
+        // the compressed proof is stored first, a failed wrap is retried from it
+        let compressed = match compressed {
+            Some(stored) => {
+                println!("Wrapping stored {} proof {}", stored.program, stored.id);
+                CompressedProof {
+                    program,
+                    proof: stored.proof()?,
+                    vk: spectre_prover.keys(program).1.clone(),
+                }
+            }
+            None => {
+                let compressed = spectre_prover.prove_compressed(&inputs)?;
+                put_proof(
+                    &proofs,
+                    &StoredProof::new::<S>(
+                        program,
+                        witness_hash,
+                        &compressed.proof,
+                        &compressed.vk,
+                        start_time.elapsed(),
+                    )?,
+                );
+                compressed
+            }
+        };
+        let (proof, vk) = spectre_prover.wrap(&compressed, &ProverOps::Groth16)?;
+        drop(permit);
+        let stored =
+            StoredProof::new::<S>(program, witness_hash, &proof, &vk, start_time.elapsed())?;
+        put_proof(&proofs, &stored);
+        Ok(stored)
+    })
+    .await
+    .expect("Prover Task failed!")
+}
+
+/// The latest proof of a witness in `mode` against `vkey`, a failed lookup is proven again.
+fn stored_proof(
+    proofs: &Arc<dyn ProofStore>,
+    witness_hash: &[u8; 32],
+    mode: &str,
+    vkey: &str,
+) -> Option<StoredProof> {
+    proofs
+        .by_witness(witness_hash, mode, vkey)
+        .unwrap_or_else(|e| {
+            println!("Proof store lookup failed: {}", e);
+            None
+        })
+}
+
+fn put_proof(proofs: &Arc<dyn ProofStore>, stored: &StoredProof) {
+    if let Err(e) = proofs.put(stored) {
+        println!("Failed to store proof: {}", e);
+    }
+}
+
+fn submission_payload(stored: &StoredProof) -> (Bytes, Bytes) {
+    (
+        Bytes::from(stored.public_values.clone()),
+        Bytes::from(stored.proof_bytes.clone()),
+    )
+}
+
+/// Minimal preset devnets choose their own genesis validators root and fork schedule,
+/// which enter the signing domain, so they are read from the beacon node.
+async fn configure_devnet() {
+    match get_network_config::<Minimal, _>(&beacon_source_from_env()).await {
+        Ok(config) => configure_network::<Minimal>(config),
+        Err(e) => {
+            eprintln!("Failed to read the devnet config: {}", e);
+            std::process::exit(1);
+        }
     }
 }
 
+fn beacon_urls_from_env() -> Vec<Url> {
+    dotenv::var("BEACON_RPC_URLS")
+        .unwrap_or("https://lodestar-sepolia.chainsafe.io".to_string())
+        .split(',')
+        .map(|url| Url::parse(url.trim()).expect("Invalid beacon node url"))
+        .collect()
+}
+
+/// Waits until the event stream reports a new finalized header or sync committee period.
+/// Falls back to polling after [`POLL_INTERVAL`] in case the stream is quiet or unavailable.
+async fn wait_for_trigger<S: Spec>(
+    events: &mut mpsc::Receiver<LightClientEvent>,
+    triggers: &mut TriggerFilter<S>,
+) {
+    let _ = tokio::time::timeout(POLL_INTERVAL, async {
+        while let Some(event) = events.recv().await {
+            if let Some(trigger) = triggers.on_event(&event) {
+                println!("Triggered by {:?}", trigger);
+                return;
+            }
+        }
+        std::future::pending::<()>().await
+    })
+    .await;
+}
+
+/// Beacon nodes are read from the comma separated `BEACON_RPC_URLS`.
+/// If `BEACON_QUORUM` is set, that many nodes must agree on every response,
+/// otherwise the nodes are queried in order until one answers.
+fn beacon_source_from_env() -> MultiSource<BeaconClient> {
+    let clients = beacon_urls_from_env()
+        .into_iter()
+        .map(BeaconClient::new)
+        .collect::<Vec<_>>();
+    let policy = match dotenv::var("BEACON_QUORUM") {
+        Ok(quorum) => Policy::Quorum(quorum.parse().expect("Invalid BEACON_QUORUM")),
+        Err(_) => Policy::Fallback,
+    };
+    MultiSource::new(clients, policy)
+}
+
+fn light_client_cache() -> LightClientCache {
+    let cache_path =
+        dotenv::var("LIGHT_CLIENT_CACHE").unwrap_or("light_client_cache.db".to_string());
+    LightClientCache::open(cache_path).expect("Failed to open light client cache")
+}
+
 async fn prune_environment() {
     let _ = Command::new("rm")
         .arg("-rf")
@@ -0,0 +1,208 @@
+use crate::{backend::BackendResult, ROTATION_ELF, STEP_ELF};
+use alloy_sol_types::SolType;
+use committee_iso::utils::compute_digest;
+use rotation_iso::types::{RotationCircuitInputs, WrappedOutput as WrappedRotationOutput};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey};
+use std::{fmt::Display, path::Path, str::FromStr};
+use step_iso::types::{
+    SyncStepCircuitInput, SyncStepCircuitOutput, WrappedOutput as WrappedStepOutput,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Program {
+    Step,
+    Rotation,
+}
+
+impl Program {
+    pub fn elf(&self) -> &'static [u8] {
+        match self {
+            Program::Step => STEP_ELF,
+            Program::Rotation => ROTATION_ELF,
+        }
+    }
+}
+
+impl Display for Program {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Program::Step => write!(f, "step"),
+            Program::Rotation => write!(f, "rotation"),
+        }
+    }
+}
+
+impl FromStr for Program {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "step" => Ok(Program::Step),
+            "rotation" => Ok(Program::Rotation),
+            _ => Err(format!("unknown program {}", s)),
+        }
+    }
+}
+
+/// Inputs of either program.
+pub enum ProgramInputs {
+    Step(SyncStepCircuitInput),
+    Rotation(RotationCircuitInputs),
+}
+
+impl ProgramInputs {
+    pub fn program(&self) -> Program {
+        match self {
+            ProgramInputs::Step(_) => Program::Step,
+            ProgramInputs::Rotation(_) => Program::Rotation,
+        }
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        match self {
+            ProgramInputs::Step(inputs) => borsh::to_vec(inputs),
+            ProgramInputs::Rotation(inputs) => borsh::to_vec(inputs),
+        }
+        .expect("Failed to serialize")
+    }
+
+    /// Digest of the program input, proofs of equal inputs are interchangeable.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut input = self.program().to_string().into_bytes();
+        input.extend(self.to_vec());
+        compute_digest(&input).try_into().unwrap()
+    }
+
+    pub(crate) fn stdin(&self) -> SP1Stdin {
+        let mut stdin = SP1Stdin::new();
+        stdin.write_vec(self.to_vec());
+        stdin
+    }
+}
+
+/// Public values of either program.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicOutputs {
+    pub slot: u32,
+    pub commitment: [u8; 32],
+    pub finalized_header_root: [u8; 32],
+    /// Only committed to by a rotation
+    pub next_commitment: Option<[u8; 32]>,
+}
+
+impl PublicOutputs {
+    /// Decodes the ABI encoded outputs, or the borsh encoded outputs of a step program built
+    /// without the `wrapped` feature.
+    pub fn decode(program: Program, public_values: &[u8]) -> BackendResult<Self> {
+        match program {
+            Program::Step => match WrappedStepOutput::abi_decode(public_values, true) {
+                Ok(output) => Ok(Self {
+                    slot: output.slot,
+                    commitment: output.commitment.0,
+                    finalized_header_root: output.finalized_header_root.0,
+                    next_commitment: None,
+                }),
+                Err(_) => {
+                    let output: SyncStepCircuitOutput = borsh::from_slice(public_values)?;
+                    Ok(Self {
+                        slot: output.slot,
+                        commitment: output.commitment,
+                        finalized_header_root: output.finalized_header_root,
+                        next_commitment: None,
+                    })
+                }
+            },
+            Program::Rotation => {
+                let output = WrappedRotationOutput::abi_decode(public_values, true)?;
+                Ok(Self {
+                    slot: output.slot,
+                    commitment: output.commitment.0,
+                    finalized_header_root: output.finalized_header_root.0,
+                    next_commitment: Some(output.next_commitment.0),
+                })
+            }
+        }
+    }
+    /// Decodes the outputs of whichever program committed to `public_values`.
+    pub fn detect(public_values: &[u8]) -> BackendResult<(Program, Self)> {
+        // rotation outputs have one more word, so they never decode as step outputs
+        match Self::decode(Program::Rotation, public_values) {
+            Ok(outputs) => Ok((Program::Rotation, outputs)),
+            Err(_) => Ok((Program::Step, Self::decode(Program::Step, public_values)?)),
+        }
+    }
+}
+
+/// Compressed proof of a program, the input to wrapping and to recursive aggregation.
+///
+/// Stored with bincode, next to the verifying key of the program that was proven.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CompressedProof {
+    pub program: Program,
+    pub proof: SP1ProofWithPublicValues,
+    pub vk: SP1VerifyingKey,
+}
+
+impl CompressedProof {
+    pub fn save(&self, path: impl AsRef<Path>) -> BackendResult<()> {
+        std::fs::write(path, bincode::serialize(self)?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> BackendResult<Self> {
+        Ok(bincode::deserialize(&std::fs::read(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Program, PublicOutputs};
+    use alloy_sol_types::SolType;
+    use rotation_iso::types::WrappedOutput as WrappedRotationOutput;
+    use step_iso::types::{SyncStepCircuitOutput, WrappedOutput as WrappedStepOutput};
+
+    #[test]
+    fn test_detect_public_outputs() {
+        let step = PublicOutputs {
+            slot: 6897664,
+            commitment: [1; 32],
+            finalized_header_root: [2; 32],
+            next_commitment: None,
+        };
+        let rotation = PublicOutputs {
+            next_commitment: Some([3; 32]),
+            ..step.clone()
+        };
+
+        let wrapped_step = WrappedStepOutput::abi_encode(&WrappedStepOutput {
+            slot: step.slot,
+            commitment: step.commitment.into(),
+            finalized_header_root: step.finalized_header_root.into(),
+        });
+        let borsh_step = borsh::to_vec(&SyncStepCircuitOutput {
+            slot: step.slot,
+            commitment: step.commitment,
+            finalized_header_root: step.finalized_header_root,
+        })
+        .unwrap();
+        let wrapped_rotation = WrappedRotationOutput::abi_encode(&WrappedRotationOutput {
+            slot: rotation.slot,
+            commitment: rotation.commitment.into(),
+            finalized_header_root: rotation.finalized_header_root.into(),
+            next_commitment: [3; 32].into(),
+        });
+
+        for public_values in [wrapped_step, borsh_step] {
+            assert_eq!(
+                PublicOutputs::detect(&public_values).unwrap(),
+                (Program::Step, step.clone())
+            );
+        }
+        assert_eq!(
+            PublicOutputs::detect(&wrapped_rotation).unwrap(),
+            (Program::Rotation, rotation)
+        );
+        assert!(PublicOutputs::detect(&[0; 7]).is_err());
+    }
+}
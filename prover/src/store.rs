@@ -0,0 +1,431 @@
+use crate::{
+    backend::BackendResult,
+    proof::{Program, PublicOutputs},
+};
+use committee_iso::utils::compute_digest;
+use eth_types::{Slot, Spec};
+use rusqlite::{params, types::Type, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use sp1_sdk::{HashableKey, SP1Proof, SP1ProofWithPublicValues, SP1VerifyingKey};
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+/// A generated proof with everything needed to submit it again.
+///
+/// Entries are addressed by the digest of the encoded proof, so storing a proof twice is a no-op.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredProof {
+    /// Hex encoded digest of `artifact`
+    pub id: String,
+    pub program: Program,
+    /// `core`, `compressed`, `groth16` or `plonk`
+    pub mode: String,
+    pub vkey: String,
+    /// See [`crate::proof::ProgramInputs::hash`]
+    pub witness_hash: [u8; 32],
+    pub slot: u32,
+    pub period: u64,
+    pub finalized_header_root: [u8; 32],
+    pub public_values: Vec<u8>,
+    /// Proof as submitted on-chain, empty unless the proof is wrapped
+    pub proof_bytes: Vec<u8>,
+    /// The bincode encoded `SP1ProofWithPublicValues`
+    pub artifact: Vec<u8>,
+    pub proving_time_ms: u64,
+    /// Seconds since the unix epoch
+    pub created_at: u64,
+}
+
+impl StoredProof {
+    pub fn new<S: Spec>(
+        program: Program,
+        witness_hash: [u8; 32],
+        proof: &SP1ProofWithPublicValues,
+        vk: &SP1VerifyingKey,
+        proving_time: Duration,
+    ) -> BackendResult<Self> {
+        let outputs = PublicOutputs::decode(program, proof.public_values.as_slice())?;
+        let (mode, proof_bytes) = match &proof.proof {
+            SP1Proof::Core(_) => ("core", vec![]),
+            SP1Proof::Compressed(_) => ("compressed", vec![]),
+            SP1Proof::Groth16(_) => ("groth16", proof.bytes()),
+            SP1Proof::Plonk(_) => ("plonk", proof.bytes()),
+        };
+        let artifact = bincode::serialize(proof)?;
+        let created_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        Ok(Self {
+            id: hex::encode(compute_digest(&artifact)),
+            program,
+            mode: mode.to_string(),
+            vkey: vk.bytes32(),
+            witness_hash,
+            slot: outputs.slot,
+            period: Slot(outputs.slot.into()).period::<S>().0,
+            finalized_header_root: outputs.finalized_header_root,
+            public_values: proof.public_values.as_slice().to_vec(),
+            proof_bytes,
+            artifact,
+            proving_time_ms: proving_time.as_millis() as u64,
+            created_at,
+        })
+    }
+
+    pub fn proof(&self) -> BackendResult<SP1ProofWithPublicValues> {
+        Ok(bincode::deserialize(&self.artifact)?)
+    }
+
+    pub fn outputs(&self) -> BackendResult<PublicOutputs> {
+        PublicOutputs::decode(self.program, &self.public_values)
+    }
+}
+
+/// Proofs that were generated before, looked up instead of proving the same input again.
+///
+/// Lookups return the most recent entries first.
+pub trait ProofStore: Send + Sync {
+    fn put(&self, proof: &StoredProof) -> BackendResult<()>;
+
+    fn get(&self, id: &str) -> BackendResult<Option<StoredProof>>;
+
+    /// The latest proof of the witness in `mode` against the program with verifying key `vkey`.
+    fn by_witness(
+        &self,
+        witness_hash: &[u8; 32],
+        mode: &str,
+        vkey: &str,
+    ) -> BackendResult<Option<StoredProof>>;
+
+    fn by_slot(&self, slot: u32) -> BackendResult<Vec<StoredProof>>;
+
+    fn by_period(&self, period: u64) -> BackendResult<Vec<StoredProof>>;
+
+    fn by_finalized_header_root(&self, root: &[u8; 32]) -> BackendResult<Vec<StoredProof>>;
+}
+
+/// A path ending in `.db` or `.sqlite` is opened as a [`SqliteProofStore`],
+/// anything else as the directory of a [`FsProofStore`].
+pub fn open_proof_store(path: impl AsRef<Path>) -> BackendResult<Box<dyn ProofStore>> {
+    let path = path.as_ref();
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("db") | Some("sqlite") => Ok(Box::new(SqliteProofStore::open(path)?)),
+        _ => Ok(Box::new(FsProofStore::open(path)?)),
+    }
+}
+
+const COLUMNS: &str = "id, program, mode, vkey, witness_hash, slot, period, finalized_header_root,
+     public_values, proof_bytes, artifact, proving_time_ms, created_at";
+
+pub struct SqliteProofStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteProofStore {
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS proofs (
+                id TEXT PRIMARY KEY,
+                program TEXT NOT NULL,
+                mode TEXT NOT NULL,
+                vkey TEXT NOT NULL,
+                witness_hash BLOB NOT NULL,
+                slot INTEGER NOT NULL,
+                period INTEGER NOT NULL,
+                finalized_header_root BLOB NOT NULL,
+                public_values BLOB NOT NULL,
+                proof_bytes BLOB NOT NULL,
+                artifact BLOB NOT NULL,
+                proving_time_ms INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS proofs_witness ON proofs (witness_hash, mode);
+            CREATE INDEX IF NOT EXISTS proofs_slot ON proofs (slot);
+            CREATE INDEX IF NOT EXISTS proofs_period ON proofs (period);
+            CREATE INDEX IF NOT EXISTS proofs_root ON proofs (finalized_header_root);",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Decodes a row, a column that does not decode fails the lookup.
+    fn row(row: &Row) -> rusqlite::Result<StoredProof> {
+        let invalid = |column: usize, kind: Type, e: String| {
+            rusqlite::Error::FromSqlConversionFailure(column, kind, e.into())
+        };
+        let digest = |column: usize| -> rusqlite::Result<[u8; 32]> {
+            let bytes: Vec<u8> = row.get(column)?;
+            bytes.try_into().map_err(|bytes: Vec<u8>| {
+                invalid(
+                    column,
+                    Type::Blob,
+                    format!("{} bytes, expected 32", bytes.len()),
+                )
+            })
+        };
+        let program: String = row.get(1)?;
+        Ok(StoredProof {
+            id: row.get(0)?,
+            program: program.parse().map_err(|e| invalid(1, Type::Text, e))?,
+            mode: row.get(2)?,
+            vkey: row.get(3)?,
+            witness_hash: digest(4)?,
+            slot: row.get(5)?,
+            period: row.get(6)?,
+            finalized_header_root: digest(7)?,
+            public_values: row.get(8)?,
+            proof_bytes: row.get(9)?,
+            artifact: row.get(10)?,
+            proving_time_ms: row.get(11)?,
+            created_at: row.get(12)?,
+        })
+    }
+
+    fn query(&self, filter: &str, param: &dyn rusqlite::ToSql) -> BackendResult<Vec<StoredProof>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(&format!(
+            "SELECT {} FROM proofs WHERE {} ORDER BY created_at DESC",
+            COLUMNS, filter
+        ))?;
+        let proofs = statement
+            .query_map([param], Self::row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(proofs)
+    }
+}
+
+impl ProofStore for SqliteProofStore {
+    fn put(&self, proof: &StoredProof) -> BackendResult<()> {
+        self.conn.lock().unwrap().execute(
+            &format!(
+                "INSERT OR IGNORE INTO proofs ({})
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                COLUMNS
+            ),
+            params![
+                proof.id,
+                proof.program.to_string(),
+                proof.mode,
+                proof.vkey,
+                proof.witness_hash.to_vec(),
+                proof.slot,
+                proof.period,
+                proof.finalized_header_root.to_vec(),
+                proof.public_values,
+                proof.proof_bytes,
+                proof.artifact,
+                proof.proving_time_ms,
+                proof.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> BackendResult<Option<StoredProof>> {
+        Ok(self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {} FROM proofs WHERE id = ?1", COLUMNS),
+                params![id],
+                Self::row,
+            )
+            .optional()?)
+    }
+
+    fn by_witness(
+        &self,
+        witness_hash: &[u8; 32],
+        mode: &str,
+        vkey: &str,
+    ) -> BackendResult<Option<StoredProof>> {
+        let proofs = self.query("witness_hash = ?1", &witness_hash.to_vec())?;
+        Ok(proofs
+            .into_iter()
+            .find(|proof| proof.mode == mode && proof.vkey == vkey))
+    }
+
+    fn by_slot(&self, slot: u32) -> BackendResult<Vec<StoredProof>> {
+        self.query("slot = ?1", &slot)
+    }
+
+    fn by_period(&self, period: u64) -> BackendResult<Vec<StoredProof>> {
+        self.query("period = ?1", &period)
+    }
+
+    fn by_finalized_header_root(&self, root: &[u8; 32]) -> BackendResult<Vec<StoredProof>> {
+        self.query("finalized_header_root = ?1", &root.to_vec())
+    }
+}
+
+/// One bincode file per proof, named after its id. Lookups other than by id read every entry,
+/// prefer the [`SqliteProofStore`] for long running provers.
+pub struct FsProofStore {
+    dir: PathBuf,
+}
+
+impl FsProofStore {
+    pub fn open(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.bin", id))
+    }
+
+    fn filter(&self, keep: impl Fn(&StoredProof) -> bool) -> BackendResult<Vec<StoredProof>> {
+        let mut proofs = vec![];
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("bin") {
+                continue;
+            }
+            let proof: StoredProof = bincode::deserialize(&std::fs::read(&path)?)?;
+            if keep(&proof) {
+                proofs.push(proof);
+            }
+        }
+        proofs.sort_by_key(|proof| std::cmp::Reverse(proof.created_at));
+        Ok(proofs)
+    }
+}
+
+impl ProofStore for FsProofStore {
+    fn put(&self, proof: &StoredProof) -> BackendResult<()> {
+        let path = self.path(&proof.id);
+        if path.exists() {
+            return Ok(());
+        }
+        // written next to the entry and renamed, so readers never see a partial proof
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, bincode::serialize(proof)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> BackendResult<Option<StoredProof>> {
+        match std::fs::read(self.path(id)) {
+            Ok(bytes) => Ok(Some(bincode::deserialize(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn by_witness(
+        &self,
+        witness_hash: &[u8; 32],
+        mode: &str,
+        vkey: &str,
+    ) -> BackendResult<Option<StoredProof>> {
+        let proofs = self.filter(|proof| {
+            &proof.witness_hash == witness_hash && proof.mode == mode && proof.vkey == vkey
+        })?;
+        Ok(proofs.into_iter().next())
+    }
+
+    fn by_slot(&self, slot: u32) -> BackendResult<Vec<StoredProof>> {
+        self.filter(|proof| proof.slot == slot)
+    }
+
+    fn by_period(&self, period: u64) -> BackendResult<Vec<StoredProof>> {
+        self.filter(|proof| proof.period == period)
+    }
+
+    fn by_finalized_header_root(&self, root: &[u8; 32]) -> BackendResult<Vec<StoredProof>> {
+        self.filter(|proof| &proof.finalized_header_root == root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FsProofStore, ProofStore, SqliteProofStore, StoredProof};
+    use crate::proof::Program;
+
+    fn stored(id: &str, slot: u32, created_at: u64) -> StoredProof {
+        StoredProof {
+            id: id.to_string(),
+            program: Program::Step,
+            mode: "groth16".to_string(),
+            vkey: "0x01".to_string(),
+            witness_hash: [(slot / 8192) as u8; 32],
+            slot,
+            period: slot as u64 / 8192,
+            finalized_header_root: [(slot / 8192) as u8 + 10; 32],
+            public_values: vec![1, 2, 3],
+            proof_bytes: vec![4, 5],
+            artifact: vec![6],
+            proving_time_ms: 1000,
+            created_at,
+        }
+    }
+
+    fn check_store(store: &dyn ProofStore) {
+        let first = stored("aa", 8192, 1);
+        let again = stored("bb", 8192, 2);
+        let other = stored("cc", 16384, 3);
+        for proof in [&first, &again, &other, &first] {
+            store.put(proof).unwrap();
+        }
+
+        assert_eq!(store.get("aa").unwrap(), Some(first.clone()));
+        assert_eq!(store.get("dd").unwrap(), None);
+        assert_eq!(
+            store.by_slot(8192).unwrap(),
+            vec![again.clone(), first.clone()]
+        );
+        assert_eq!(store.by_period(2).unwrap(), vec![other.clone()]);
+        assert_eq!(
+            store.by_finalized_header_root(&[11; 32]).unwrap(),
+            vec![again.clone(), first]
+        );
+        assert_eq!(
+            store.by_witness(&[1; 32], "groth16", "0x01").unwrap(),
+            Some(again)
+        );
+        assert_eq!(store.by_witness(&[1; 32], "plonk", "0x01").unwrap(), None);
+        // proofs of another build of the program are not reused
+        assert_eq!(store.by_witness(&[1; 32], "groth16", "0x02").unwrap(), None);
+    }
+
+    #[test]
+    fn test_sqlite_proof_store() {
+        check_store(&SqliteProofStore::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn test_sqlite_invalid_row() {
+        let store = SqliteProofStore::open_in_memory().unwrap();
+        store.put(&stored("aa", 8192, 1)).unwrap();
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE proofs SET program = 'committee'", [])
+            .unwrap();
+        assert!(store.get("aa").is_err());
+        assert!(store.by_slot(8192).is_err());
+    }
+
+    #[test]
+    fn test_fs_proof_store() {
+        let dir = std::env::temp_dir().join(format!("spectre-proofs-{}", std::process::id()));
+        check_store(&FsProofStore::open(&dir).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1 +1 @@
-cargo run --release -F wrapped -- --nocapture
\ No newline at end of file
+cargo run --release -F wrapped -- run
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod test_cycles {
+    use committee_iso::{
+        utils::{commit_to_keys_with_sign, decode_pubkeys_x},
+        witness::WitnessFile,
+    };
+    use prover::{
+        backend::BackendKind,
+        cycles::{CycleBudgets, CycleReport},
+        SpectreProver,
+    };
+    use rotation_iso::types::RotationCircuitInputs;
+    use step_iso::{compress_keys, utils::load_circuit_args_env as load_step_args_env};
+
+    const BUDGET_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../data/cycle_budget.json");
+    const ROTATION_PATH: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../data/synthetic_rotation_512.json"
+    );
+
+    fn execute_prover() -> SpectreProver {
+        SpectreProver::with_backend(BackendKind::Execute.build())
+    }
+
+    fn check_budget(report: &CycleReport) {
+        println!("{}", serde_json::to_string_pretty(report).unwrap());
+        let budgets = CycleBudgets::load(BUDGET_PATH).expect("Failed to load cycle budget");
+        if budgets.sp1_version.is_none() {
+            println!("the cycle budget holds estimates, record measured counts as in the README");
+        }
+        if let Err(exceeded) = budgets.check(report) {
+            panic!("Cycle budget exceeded:\n{}", exceeded.join("\n"));
+        }
+    }
+
+    #[test]
+    fn test_step_cycle_budget() {
+        let args = load_step_args_env();
+        let (keys, signs) = decode_pubkeys_x(compress_keys(args.pubkeys_uncompressed.clone()));
+        let report = execute_prover()
+            .execute_step(commit_to_keys_with_sign(&keys, &signs), args)
+            .expect("Failed to execute step program");
+        assert!(report.spans.contains_key("pairing"));
+        check_budget(&report);
+    }
+
+    #[test]
+    fn test_rotation_cycle_budget() {
+        // the committee update and sync step in `data/` are not for the same header, the
+        // rotation of a synthetic committee is generated by `step_iso::testgen`
+        let inputs = WitnessFile::<RotationCircuitInputs>::load(ROTATION_PATH)
+            .expect("Failed to load rotation witness")
+            .witness;
+        let report = execute_prover()
+            .execute_rotation(inputs)
+            .expect("Failed to execute rotation program");
+        assert!(report.spans.contains_key("committee commitment"));
+        check_budget(&report);
+    }
+}
@@ -1,68 +1,180 @@
 #[cfg(test)]
 mod tests {
+    use beacon_api_client::{mainnet::Client as BeaconClient, BlockId};
+    use beacon_mock::{MockBeacon, Recorder};
     use committee_iso::utils::{commit_to_keys_with_sign, decode_pubkeys_x};
-    use preprocessor::get_light_client_update_at_slot;
+    use eth_types::{domain_at_slot, Spec, Testnet};
+    use ethers::utils::keccak256;
+    use preprocessor::{
+        checkpoint::{bootstrap_from_checkpoint, verify_bootstrap},
+        get_block_root, get_light_client_bootstrap, get_light_client_update_at_slot,
+        PreprocessorError,
+    };
     use prover::{
-        fixture::{create_rotation_proof_fixture, create_step_proof_fixture},
-        generate_rotation_proof_sp1, generate_step_proof_sp1,
+        aligned::{submit_and_wait, AlignedSubmission, Batcher, LocalBatcher},
+        backend::BackendKind,
+        fixture::{ContractState, FixtureWriter, ProgramVkeys, ProofFixture},
+        inspect::{inspect_proof, ProofFile},
+        proof::{CompressedProof, Program, ProgramInputs, PublicOutputs},
+        ProverOps, SpectreProver, STEP_ELF,
     };
+    use reqwest::Url;
     use rotation_iso::types::RotationCircuitInputs;
-    use sp1_sdk::ProverClient;
-    use step_iso::types::SyncStepCircuitInput;
+    use std::{path::Path, sync::Arc, time::Duration};
+    use step_iso::{
+        testgen::{generate_rotation, StepParams, SyntheticCommittee},
+        types::SyncStepCircuitInput,
+    };
 
-    #[tokio::test]
-    async fn generate_rotation_proof_payload() {
-        let (sc, oc) = get_light_client_update_at_slot(6897664 - (256 * 32)).await;
-        let s = sc.clone().unwrap().0;
-        let c = sc.unwrap().1;
-        let (keys, signs) = decode_pubkeys_x(oc.unwrap());
-        let commitment = commit_to_keys_with_sign(&keys, &signs);
-        let rotation_inputs = RotationCircuitInputs {
-            committee: c,
+    const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/sepolia");
+    const ROTATION_SLOTS: [u64; 2] = [6823936, 6897664 - Testnet::SLOTS_PER_SYNC_COMMITTEE_PERIOD];
+
+    /// Serves the recorded Sepolia responses, the returned server must be kept alive.
+    async fn sepolia_client() -> (MockBeacon, BeaconClient) {
+        assert!(
+            Path::new(FIXTURES).exists(),
+            "missing fixtures, run `cargo test -p prover --test integration record_sepolia_fixtures -- --ignored`"
+        );
+        let mock = MockBeacon::start(FIXTURES).await.unwrap();
+        let client = BeaconClient::new(mock.url());
+        (mock, client)
+    }
+
+    /// A rotation signed by a synthetic committee of Sepolia's size, its step is valid on its own.
+    /// Tests that only prove use it, so they run without the recorded Sepolia responses.
+    fn synthetic_rotation() -> RotationCircuitInputs {
+        let committee = SyntheticCommittee::new(Testnet::SYNC_COMMITTEE_SIZE, 1);
+        let next = SyntheticCommittee::new(Testnet::SYNC_COMMITTEE_SIZE, 2);
+        let attested_slot = ROTATION_SLOTS[1] + 96;
+        let params = StepParams::new::<Testnet>(
+            &committee,
+            attested_slot,
+            domain_at_slot::<Testnet>(attested_slot + 1),
+        );
+        let (args, update) = generate_rotation(&committee, &next, &params);
+        RotationCircuitInputs {
+            committee: update,
             step: SyncStepCircuitInput {
-                args: s,
-                commitment: commitment,
+                args,
+                commitment: committee.commitment(),
             },
-        };
+        }
+    }
+
+    /// Proofs are mocked unless `PROVER_BACKEND` selects a real backend.
+    fn test_prover() -> Arc<SpectreProver> {
+        Arc::new(SpectreProver::with_backend(
+            BackendKind::from_env_or(BackendKind::Mock).build(),
+        ))
+    }
+
+    /// Fixtures of mocked proofs fail in Foundry, they replace the Foundry fixtures only when
+    /// `FIXTURE_DIR` points there.
+    fn fixture_writer() -> FixtureWriter {
+        match std::env::var("FIXTURE_DIR") {
+            Ok(dir) => FixtureWriter::new(dir),
+            Err(_) => FixtureWriter::new(std::env::temp_dir().join("spectre-fixtures")),
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "records fixtures from the live Sepolia beacon node"]
+    async fn record_sepolia_fixtures() {
+        let recorder = Recorder::new(
+            BeaconClient::new(Url::parse("https://lodestar-sepolia.chainsafe.io").unwrap()),
+            FIXTURES,
+        );
+        for slot in ROTATION_SLOTS {
+            get_light_client_update_at_slot::<Testnet, _>(&recorder, slot)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the Sepolia responses recorded by `record_sepolia_fixtures`"]
+    async fn test_bootstrap_from_checkpoint() {
+        let (_mock, client) = sepolia_client().await;
+        let block_root = get_block_root(&client, BlockId::Slot(ROTATION_SLOTS[0]))
+            .await
+            .unwrap();
+        let trusted_block_root: [u8; 32] = block_root.as_ref().try_into().unwrap();
+        let checkpoint = bootstrap_from_checkpoint::<Testnet, _>(&client, trusted_block_root)
+            .await
+            .unwrap();
+        assert_eq!(checkpoint.slot, ROTATION_SLOTS[0]);
+        assert_eq!(checkpoint.finalized_header_root, trusted_block_root);
+        assert_ne!(
+            checkpoint.active_committee_commitment,
+            checkpoint.next_committee_commitment
+        );
+
+        // a bootstrap served for another block is rejected
+        let other_root = get_block_root(&client, BlockId::Slot(ROTATION_SLOTS[1]))
+            .await
+            .unwrap();
+        let bootstrap = get_light_client_bootstrap::<Testnet, _>(&client, other_root)
+            .await
+            .unwrap();
+        assert!(verify_bootstrap::<Testnet>(&bootstrap, other_root).is_ok());
+        assert!(matches!(
+            verify_bootstrap::<Testnet>(&bootstrap, block_root),
+            Err(PreprocessorError::UntrustedBootstrap { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn generate_rotation_proof_payload() {
+        let rotation_inputs = synthetic_rotation();
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
         let (rotation_proof, rotation_vk) = tokio::task::spawn_blocking(move || {
-            generate_rotation_proof_sp1(&prover::ProverOps::Groth16, rotation_inputs)
+            spectre_prover.prove_rotation(&prover::ProverOps::Groth16, rotation_inputs)
         })
         .await
-        .expect("Failed to join the spawned blocking task");
-        #[allow(deprecated)]
-        let client = ProverClient::new();
-        client
+        .expect("Failed to join the spawned blocking task")
+        .expect("Failed to generate rotation proof");
+        spectre
             .verify(&rotation_proof, &rotation_vk)
             .expect("Failed to verify rotation proof");
-        create_rotation_proof_fixture(&rotation_proof, &rotation_vk, &prover::ProverOps::Groth16);
+        fixture_writer()
+            .write(
+                &rotation_proof,
+                &ProgramVkeys::new(&spectre),
+                &prover::ProverOps::Groth16,
+            )
+            .expect("Failed to write rotation fixture");
     }
 
     #[tokio::test]
+    #[ignore = "needs the Sepolia responses recorded by `record_sepolia_fixtures`"]
     async fn find_last_committee() {
-        let mut current_height = 6823936;
+        let (_mock, client) = sepolia_client().await;
+        let mut current_height = ROTATION_SLOTS[0] - Testnet::SLOTS_PER_SYNC_COMMITTEE_PERIOD;
         loop {
-            current_height += 32 * 256;
-            let (sc, _) = get_light_client_update_at_slot(current_height).await;
-            match sc {
-                Some(_) => {
+            current_height += Testnet::SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+            match get_light_client_update_at_slot::<Testnet, _>(&client, current_height).await {
+                Ok(_) => {
                     println!("Slot: {}", current_height)
                 }
-                None => {
+                Err(e) if e.is_not_found() => {
                     break;
                 }
+                Err(e) => panic!("Failed to fetch light client update: {}", e),
             }
         }
     }
 
     #[tokio::test]
     async fn generate_step_proof_payload() {
-        let (sc, oc) = get_light_client_update_at_slot(6897664 - (256 * 32)).await;
-        let s = sc.clone().unwrap().0;
-        let c = sc.unwrap().1;
-        let (keys, signs) = decode_pubkeys_x(oc.unwrap());
-        let commitment = commit_to_keys_with_sign(&keys, &signs);
+        let SyncStepCircuitInput {
+            args: s,
+            commitment,
+        } = synthetic_rotation().step;
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
         let (step_proof, step_vk) = tokio::task::spawn_blocking(move || {
-            generate_step_proof_sp1(
+            spectre_prover.prove_step(
                 &prover::ProverOps::Groth16,
                 commitment,
                 s,
@@ -70,21 +182,29 @@ mod tests {
             )
         })
         .await
-        .expect("Failed to join the spawned blocking task");
-        #[allow(deprecated)]
-        let client = ProverClient::new();
-        client
+        .expect("Failed to join the spawned blocking task")
+        .expect("Failed to generate step proof");
+        spectre
             .verify(&step_proof, &step_vk)
             .expect("Failed to verify rotation proof");
-        create_step_proof_fixture(&step_proof, &step_vk, &prover::ProverOps::Groth16);
+        fixture_writer()
+            .write(
+                &step_proof,
+                &ProgramVkeys::new(&spectre),
+                &prover::ProverOps::Groth16,
+            )
+            .expect("Failed to write step fixture");
     }
 
     #[tokio::test]
+    #[ignore = "needs the Sepolia responses recorded by `record_sepolia_fixtures`"]
     async fn test_committee_rotation_beacon_cli_e2e_plonk() {
-        let (sc, oc) = get_light_client_update_at_slot(6823936).await;
-        let s = sc.clone().unwrap().0;
-        let c = sc.unwrap().1;
-        let (keys, signs) = decode_pubkeys_x(oc.unwrap());
+        let (_mock, client) = sepolia_client().await;
+        let ((s, c), oc) =
+            get_light_client_update_at_slot::<Testnet, _>(&client, ROTATION_SLOTS[0])
+                .await
+                .unwrap();
+        let (keys, signs) = decode_pubkeys_x(oc);
         let commitment = commit_to_keys_with_sign(&keys, &signs);
         let rotation_inputs = RotationCircuitInputs {
             committee: c,
@@ -93,15 +213,170 @@ mod tests {
                 commitment: commitment,
             },
         };
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
         let (rotation_proof, rotation_vk) = tokio::task::spawn_blocking(move || {
-            generate_rotation_proof_sp1(&prover::ProverOps::Plonk, rotation_inputs)
+            spectre_prover.prove_rotation(&prover::ProverOps::Plonk, rotation_inputs)
         })
         .await
-        .expect("Failed to join the spawned blocking task");
-        #[allow(deprecated)]
-        let client = ProverClient::new();
-        client
+        .expect("Failed to join the spawned blocking task")
+        .expect("Failed to generate rotation proof");
+        spectre
             .verify(&rotation_proof, &rotation_vk)
             .expect("Failed to verify rotation proof");
     }
+
+    /// Proves a sync step and submits it to a model of the verifier contract.
+    #[tokio::test]
+    async fn test_step_flow() {
+        let SyncStepCircuitInput {
+            args: s,
+            commitment,
+        } = synthetic_rotation().step;
+        let finalized_slot: u32 = s.finalized_header.slot.parse().unwrap();
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
+        let (step_proof, step_vk) = tokio::task::spawn_blocking(move || {
+            spectre_prover.prove_step(
+                &prover::ProverOps::Groth16,
+                commitment,
+                s,
+                &prover::ProofCompressionBool::Uncompressed,
+            )
+        })
+        .await
+        .expect("Failed to join the spawned blocking task")
+        .expect("Failed to generate step proof");
+        spectre
+            .verify(&step_proof, &step_vk)
+            .expect("Failed to verify step proof");
+
+        // the mock backend leaves the proof bytes empty, the public values are always set
+        let outputs = PublicOutputs::decode(Program::Step, step_proof.public_values.as_slice())
+            .expect("Failed to decode step outputs");
+        assert_eq!(outputs.slot, finalized_slot);
+        assert_eq!(outputs.commitment, commitment);
+
+        // submit the proof to the model of `verifyStepProof`, deployed with the signing committee
+        let fixture = ProofFixture::new(&step_proof, &ProgramVkeys::new(&spectre)).unwrap();
+        fixture.check().expect("Failed to submit step proof");
+        assert_eq!(
+            fixture.post_state.finalized_header_root,
+            format!("0x{}", hex::encode(outputs.finalized_header_root))
+        );
+        // a contract following another committee rejects it
+        let other_committee = ContractState {
+            active_committee_commitment: format!("0x{}", hex::encode([0; 32])),
+            ..fixture.pre_state
+        };
+        assert!(other_committee.apply(&outputs).is_err());
+    }
+
+    /// Proves a compressed step, stores it and wraps the stored proof for submission.
+    #[tokio::test]
+    async fn test_compressed_then_wrap() {
+        let inputs = ProgramInputs::Step(synthetic_rotation().step);
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
+        let compressed =
+            tokio::task::spawn_blocking(move || spectre_prover.prove_compressed(&inputs))
+                .await
+                .expect("Failed to join the spawned blocking task")
+                .expect("Failed to generate compressed proof");
+        assert_eq!(compressed.program, Program::Step);
+
+        let path = std::env::temp_dir().join("spectre-compressed-step.bin");
+        compressed.save(&path).unwrap();
+        let stored = CompressedProof::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            stored.proof.public_values.as_slice(),
+            compressed.proof.public_values.as_slice()
+        );
+
+        let spectre_prover = spectre.clone();
+        let (proof, vk) =
+            tokio::task::spawn_blocking(move || spectre_prover.wrap(&stored, &ProverOps::Groth16))
+                .await
+                .expect("Failed to join the spawned blocking task")
+                .expect("Failed to wrap proof");
+        spectre
+            .verify(&proof, &vk)
+            .expect("Failed to verify wrapped proof");
+        assert_eq!(
+            proof.public_values.as_slice(),
+            compressed.proof.public_values.as_slice()
+        );
+        assert!(spectre.wrap(&compressed, &ProverOps::Default).is_err());
+    }
+
+    /// Settles a compressed step through the in-process stand-in for the Aligned batcher.
+    #[tokio::test]
+    async fn test_aligned_local_batcher() {
+        let inputs = ProgramInputs::Step(synthetic_rotation().step);
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
+        let compressed =
+            tokio::task::spawn_blocking(move || spectre_prover.prove_compressed(&inputs))
+                .await
+                .expect("Failed to join the spawned blocking task")
+                .expect("Failed to generate compressed proof");
+
+        let batcher = LocalBatcher::new(spectre, Duration::from_millis(50));
+        let submissions = [
+            AlignedSubmission::new(&compressed, [1; 20]),
+            AlignedSubmission::new(&compressed, [2; 20]),
+        ];
+        let commitment = submissions[0].commitment().unwrap();
+        assert_eq!(
+            commitment.pub_input_commitment,
+            keccak256(compressed.proof.public_values.as_slice())
+        );
+        assert_eq!(commitment.program_id_commitment, keccak256(STEP_ELF));
+
+        let inclusions = submit_and_wait(
+            &batcher,
+            &submissions,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await
+        .expect("Failed to settle through the local batcher");
+        assert_eq!(batcher.batches(), 1);
+        assert_eq!(inclusions.len(), 2);
+        assert_eq!(inclusions[0].commitment, commitment);
+        assert_eq!(inclusions[1].index_in_batch, 1);
+        assert!(inclusions.iter().all(|inclusion| inclusion.verify()));
+        assert!(batcher.submit(&[]).await.is_err());
+    }
+
+    /// Saves a step proof and verifies the saved file like the `verify` command does.
+    #[tokio::test]
+    async fn test_inspect_saved_proof() {
+        let SyncStepCircuitInput { args, commitment } = synthetic_rotation().step;
+        let finalized_slot: u32 = args.finalized_header.slot.parse().unwrap();
+        let spectre = test_prover();
+        let spectre_prover = spectre.clone();
+        let (proof, vk) = tokio::task::spawn_blocking(move || {
+            spectre_prover.prove_step(
+                &ProverOps::Groth16,
+                commitment,
+                args,
+                &prover::ProofCompressionBool::Uncompressed,
+            )
+        })
+        .await
+        .expect("Failed to join the spawned blocking task")
+        .expect("Failed to generate step proof");
+
+        let path = std::env::temp_dir().join("spectre-step-proof.bin");
+        proof.save(&path).unwrap();
+        let file = ProofFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let inspection = inspect_proof(&spectre, &file, Some(&vk)).unwrap();
+        assert_eq!(inspection.program, Program::Step);
+        assert_eq!(inspection.outputs.slot, finalized_slot);
+        assert_eq!(inspection.outputs.commitment, commitment);
+        assert_eq!(inspection.outputs.next_commitment, None);
+    }
 }
@@ -1,6 +1,9 @@
 #[cfg(test)]
 mod test_circuits {
-    use prover::{fixture::create_step_proof_fixture, generate_step_proof_sp1, ProverOps};
+    use prover::{
+        fixture::{FixtureWriter, ProgramVkeys},
+        generate_step_proof_sp1, ProverOps, SpectreProver,
+    };
     use step_iso::{types::SyncStepArgs, utils::load_circuit_args_env as load_step_args_env};
 
     #[test]
@@ -26,13 +29,18 @@ mod test_circuits {
             106, 92, 62, 66, 60, 86, 8, 54, 215, 185, 238, 54, 75, 39, 221, 15, 81, 229, 23, 145,
             198, 242, 244, 199, 60, 103, 60, 206, 116, 216, 86, 227,
         ];
-        let (proof, vk) = generate_step_proof_sp1(
-            &ops,
-            commitment,
-            sync_step_args,
-            &prover::ProofCompressionBool::Uncompressed,
-        );
-        create_step_proof_fixture(&proof, &vk, &ops);
+        let spectre = SpectreProver::new();
+        let (proof, _) = spectre
+            .prove_step(
+                &ops,
+                commitment,
+                sync_step_args,
+                &prover::ProofCompressionBool::Uncompressed,
+            )
+            .expect("failed to generate proof");
+        FixtureWriter::new(std::env::temp_dir().join("spectre-fixtures"))
+            .write(&proof, &ProgramVkeys::new(&spectre), &ops)
+            .expect("failed to write fixture");
     }
 
     #[test]
@@ -43,12 +51,17 @@ mod test_circuits {
             106, 92, 62, 66, 60, 86, 8, 54, 215, 185, 238, 54, 75, 39, 221, 15, 81, 229, 23, 145,
             198, 242, 244, 199, 60, 103, 60, 206, 116, 216, 86, 227,
         ];
-        let (proof, vk) = generate_step_proof_sp1(
-            &ops,
-            commitment,
-            sync_step_args,
-            &prover::ProofCompressionBool::Uncompressed,
-        );
-        create_step_proof_fixture(&proof, &vk, &ops);
+        let spectre = SpectreProver::new();
+        let (proof, _) = spectre
+            .prove_step(
+                &ops,
+                commitment,
+                sync_step_args,
+                &prover::ProofCompressionBool::Uncompressed,
+            )
+            .expect("failed to generate proof");
+        FixtureWriter::new(std::env::temp_dir().join("spectre-fixtures"))
+            .write(&proof, &ProgramVkeys::new(&spectre), &ops)
+            .expect("failed to write fixture");
     }
 }
@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MIT
+pragma solidity =0.8.20;
+import {LightClient} from "./LightClient.sol";
+
+/// @notice The batch inclusion check of the Aligned Layer service manager.
+interface IAlignedServiceManager {
+    function verifyBatchInclusion(
+        bytes32 proofCommitment,
+        bytes32 pubInputCommitment,
+        bytes32 provingSystemAuxDataCommitment,
+        bytes20 proofGeneratorAddr,
+        bytes32 batchMerkleRoot,
+        bytes memory merkleProof,
+        uint256 verificationDataBatchIndex,
+        address senderAddress
+    ) external view returns (bool);
+}
+
+/// @notice Where a proof was included in a batch verified by Aligned Layer.
+struct AlignedInclusion {
+    bytes32 proofCommitment;
+    bytes20 proofGeneratorAddr;
+    bytes32 batchMerkleRoot;
+    bytes merkleProof;
+    uint256 verificationDataBatchIndex;
+}
+
+/// @title Aligned Light Client Verifier
+/// @author Chainsafe Systems
+/// @notice Apply committee updates and steps whose compressed proofs were verified by Aligned Layer
+contract AlignedLightClientVerifier is LightClient {
+    /// @notice The Aligned Layer service manager that batches are verified by.
+    address public alignedServiceManager;
+    /// @notice The batcher payment service that submits the batches.
+    address public paymentService;
+    /// @notice keccak256 of the committee program ELF.
+    bytes32 public committeeProgramIdCommitment;
+    /// @notice keccak256 of the step program ELF.
+    bytes32 public stepProgramIdCommitment;
+
+    constructor(
+        address _alignedServiceManager,
+        address _paymentService,
+        bytes32 _committeeProgramIdCommitment,
+        bytes32 _stepProgramIdCommitment,
+        bytes32 _finalizedHeaderRoot,
+        bytes32 _activeCommitteeCommitment,
+        bytes32 _nextCommitteeCommitment,
+        uint32 _activeSlot
+    )
+        LightClient(
+            _finalizedHeaderRoot,
+            _activeCommitteeCommitment,
+            _nextCommitteeCommitment,
+            _activeSlot
+        )
+    {
+        alignedServiceManager = _alignedServiceManager;
+        paymentService = _paymentService;
+        committeeProgramIdCommitment = _committeeProgramIdCommitment;
+        stepProgramIdCommitment = _stepProgramIdCommitment;
+    }
+
+    /// @notice Apply a committee update verified by Aligned Layer.
+    /// @param _publicValues The encoded public values.
+    /// @param _inclusion The inclusion of the proof in a verified batch.
+    function verifyRotationInclusion(
+        bytes calldata _publicValues,
+        AlignedInclusion calldata _inclusion
+    ) external {
+        verifyInclusion(committeeProgramIdCommitment, _publicValues, _inclusion);
+        applyRotation(_publicValues);
+    }
+
+    /// @notice Apply a step verified by Aligned Layer.
+    /// @param _publicValues The encoded public values.
+    /// @param _inclusion The inclusion of the proof in a verified batch.
+    function verifyStepInclusion(
+        bytes calldata _publicValues,
+        AlignedInclusion calldata _inclusion
+    ) external {
+        verifyInclusion(stepProgramIdCommitment, _publicValues, _inclusion);
+        applyStep(_publicValues);
+    }
+
+    /// @dev The public values are bound to the proof through the public input commitment.
+    function verifyInclusion(
+        bytes32 _programIdCommitment,
+        bytes calldata _publicValues,
+        AlignedInclusion calldata _inclusion
+    ) internal view {
+        require(
+            IAlignedServiceManager(alignedServiceManager).verifyBatchInclusion(
+                _inclusion.proofCommitment,
+                keccak256(_publicValues),
+                _programIdCommitment,
+                _inclusion.proofGeneratorAddr,
+                _inclusion.batchMerkleRoot,
+                _inclusion.merkleProof,
+                _inclusion.verificationDataBatchIndex,
+                paymentService
+            ),
+            "proof is not in a verified batch"
+        );
+    }
+}
@@ -2,14 +2,26 @@
 pragma solidity ^0.8.20;
 import {stdJson} from "forge-std/StdJson.sol";
 
+/// State of the LightClientVerifier before or after a proof is applied
+struct ContractState {
+    bytes32 finalizedHeaderRoot;
+    bytes32 activeCommitteeCommitment;
+    bytes32 nextCommitteeCommitment;
+    uint32 activeSlot;
+}
+
 struct RotationProofFixture {
     uint32 slot;
     bytes32 root;
     bytes32 commitment;
     bytes32 next_commitment;
     bytes32 vkey;
+    bytes32 stepVkey;
+    bytes32 rotationVkey;
     bytes publicValues;
     bytes proof;
+    ContractState preState;
+    ContractState postState;
 }
 
 struct StepProofFixture {
@@ -17,12 +29,41 @@ struct StepProofFixture {
     bytes32 root;
     bytes32 commitment;
     bytes32 vkey;
+    bytes32 stepVkey;
+    bytes32 rotationVkey;
     bytes publicValues;
     bytes proof;
+    ContractState preState;
+    ContractState postState;
 }
 
 library FixtureLoader {
     using stdJson for string;
+
+    function parseState(
+        string memory json,
+        string memory key
+    ) internal pure returns (ContractState memory) {
+        ContractState memory state;
+        state.finalizedHeaderRoot = abi.decode(
+            json.parseRaw(string.concat(key, ".finalizedHeaderRoot")),
+            (bytes32)
+        );
+        state.activeCommitteeCommitment = abi.decode(
+            json.parseRaw(string.concat(key, ".activeCommitteeCommitment")),
+            (bytes32)
+        );
+        state.nextCommitteeCommitment = abi.decode(
+            json.parseRaw(string.concat(key, ".nextCommitteeCommitment")),
+            (bytes32)
+        );
+        state.activeSlot = abi.decode(
+            json.parseRaw(string.concat(key, ".activeSlot")),
+            (uint32)
+        );
+        return state;
+    }
+
     function parseRotationFixture(
         string memory json
     ) internal pure returns (RotationProofFixture memory) {
@@ -43,6 +84,13 @@ library FixtureLoader {
             (bytes)
         );
         fixture.proof = abi.decode(json.parseRaw(".proof"), (bytes));
+        fixture.stepVkey = abi.decode(json.parseRaw(".stepVkey"), (bytes32));
+        fixture.rotationVkey = abi.decode(
+            json.parseRaw(".rotationVkey"),
+            (bytes32)
+        );
+        fixture.preState = parseState(json, ".preState");
+        fixture.postState = parseState(json, ".postState");
         return fixture;
     }
 
@@ -62,6 +110,13 @@ library FixtureLoader {
             (bytes)
         );
         fixture.proof = abi.decode(json.parseRaw(".proof"), (bytes));
+        fixture.stepVkey = abi.decode(json.parseRaw(".stepVkey"), (bytes32));
+        fixture.rotationVkey = abi.decode(
+            json.parseRaw(".rotationVkey"),
+            (bytes32)
+        );
+        fixture.preState = parseState(json, ".preState");
+        fixture.postState = parseState(json, ".postState");
         return fixture;
     }
 }
@@ -0,0 +1,69 @@
+// SPDX-License-Identifier: MIT
+pragma solidity =0.8.20;
+
+struct RotationOutputStruct {
+    uint32 slot;
+    bytes32 commitment;
+    bytes32 finalized_header_root;
+    bytes32 next_commitment;
+}
+
+struct StepOutputStruct {
+    uint32 slot;
+    bytes32 commitment;
+    bytes32 finalized_header_root;
+}
+
+/// @title Light Client
+/// @author Chainsafe Systems
+/// @notice The light client state and how proven committee updates and steps change it,
+///         verifiers only decide which proofs are valid.
+abstract contract LightClient {
+    /// @notice The current finalized header root.
+    bytes32 public finalizedHeaderRoot;
+    // the current active committee
+    bytes32 public activeCommitteeCommitment;
+    // the next active committee
+    bytes32 public nextCommitteeCommitment;
+    // the current active slot
+    uint32 public activeSlot;
+
+    constructor(
+        bytes32 _finalizedHeaderRoot,
+        bytes32 _activeCommitteeCommitment,
+        bytes32 _nextCommitteeCommitment,
+        uint32 _activeSlot
+    ) {
+        finalizedHeaderRoot = _finalizedHeaderRoot;
+        activeCommitteeCommitment = _activeCommitteeCommitment;
+        nextCommitteeCommitment = _nextCommitteeCommitment;
+        activeSlot = _activeSlot;
+    }
+
+    /// @dev Applies the public values of a verified rotation proof.
+    function applyRotation(bytes calldata _publicValues) internal {
+        RotationOutputStruct memory publicValues = abi.decode(
+            _publicValues,
+            (RotationOutputStruct)
+        );
+        require(nextCommitteeCommitment == publicValues.commitment);
+        require(publicValues.slot > activeSlot);
+        // this is the attested slot
+        activeSlot = publicValues.slot;
+        activeCommitteeCommitment = nextCommitteeCommitment;
+        nextCommitteeCommitment = publicValues.next_commitment;
+        finalizedHeaderRoot = publicValues.finalized_header_root;
+    }
+
+    /// @dev Applies the public values of a verified step proof.
+    function applyStep(bytes calldata _publicValues) internal {
+        StepOutputStruct memory publicValues = abi.decode(
+            _publicValues,
+            (StepOutputStruct)
+        );
+        require(activeCommitteeCommitment == publicValues.commitment);
+        // this is the finalized slot
+        require(publicValues.slot > activeSlot);
+        finalizedHeaderRoot = publicValues.finalized_header_root;
+    }
+}
@@ -1,24 +1,12 @@
 // SPDX-License-Identifier: MIT
 pragma solidity =0.8.20;
 import {ISP1Verifier} from "sp1-contracts/contracts/src/ISP1Verifier.sol";
-
-struct RotationOutputStruct {
-    uint32 slot;
-    bytes32 commitment;
-    bytes32 finalized_header_root;
-    bytes32 next_commitment;
-}
-
-struct StepOutputStruct {
-    uint32 slot;
-    bytes32 commitment;
-    bytes32 finalized_header_root;
-}
+import {LightClient} from "./LightClient.sol";
 
 /// @title Committee Verifier
 /// @author Chainsafe Systems
 /// @notice Verify a committee update
-contract LightClientVerifier {
+contract LightClientVerifier is LightClient {
     /// @notice The address of the SP1 verifier contract.
     /// @dev This can either be a specific SP1Verifier for a specific version, or the
     ///      SP1VerifierGateway which can be used to verify proofs for any version of SP1.
@@ -29,14 +17,6 @@ contract LightClientVerifier {
     bytes32 public committeeProgramVKey;
     /// @notice The verification key for the step program.
     bytes32 public stepProgramVKey;
-    /// @notice The current finalized header root.
-    bytes32 public finalizedHeaderRoot;
-    // the current active committee
-    bytes32 public activeCommitteeCommitment;
-    // the next active committee
-    bytes32 public nextCommitteeCommitment;
-    // the current active slot
-    uint32 public activeSlot;
 
     constructor(
         address _verifier,
@@ -46,14 +26,17 @@ contract LightClientVerifier {
         bytes32 _activeCommitteeCommitment,
         bytes32 _nextCommitteeCommitment,
         uint32 _activeSlot
-    ) {
+    )
+        LightClient(
+            _finalizedHeaderRoot,
+            _activeCommitteeCommitment,
+            _nextCommitteeCommitment,
+            _activeSlot
+        )
+    {
         verifier = _verifier;
         committeeProgramVKey = _committeeProgramVKey;
         stepProgramVKey = _stepProgramVKey;
-        finalizedHeaderRoot = _finalizedHeaderRoot;
-        activeCommitteeCommitment = _activeCommitteeCommitment;
-        nextCommitteeCommitment = _nextCommitteeCommitment;
-        activeSlot = _activeSlot;
     }
 
     /// @notice Verify the committee proof.
@@ -68,17 +51,7 @@ contract LightClientVerifier {
             _publicValues,
             _proofBytes
         );
-        RotationOutputStruct memory publicValues = abi.decode(
-            _publicValues,
-            (RotationOutputStruct)
-        );
-        require(nextCommitteeCommitment == publicValues.commitment);
-        require(publicValues.slot > activeSlot);
-        // this is the attested slot
-        activeSlot = publicValues.slot;
-        activeCommitteeCommitment = nextCommitteeCommitment;
-        nextCommitteeCommitment = publicValues.next_commitment;
-        finalizedHeaderRoot = publicValues.finalized_header_root;
+        applyRotation(_publicValues);
     }
 
     /// @notice Verify the step proof.
@@ -93,13 +66,6 @@ contract LightClientVerifier {
             _publicValues,
             _proofBytes
         );
-        StepOutputStruct memory publicValues = abi.decode(
-            _publicValues,
-            (StepOutputStruct)
-        );
-        require(activeCommitteeCommitment == publicValues.commitment);
-        // this is the finalized slot
-        require(publicValues.slot > activeSlot);
-        finalizedHeaderRoot = publicValues.finalized_header_root;
+        applyStep(_publicValues);
     }
 }
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+import {Test} from "forge-std/Test.sol";
+import {AlignedLightClientVerifier, AlignedInclusion} from "../src/AlignedVerifier.sol";
+import {FixtureLoader, RotationProofFixture, StepProofFixture} from "../src/Fixture.sol";
+
+/// @notice Stand-in for the Aligned service manager, batches are verified once marked so.
+contract MockAlignedServiceManager {
+    mapping(bytes32 => bool) public verifiedBatches;
+
+    function markVerified(bytes32 batchMerkleRoot) external {
+        verifiedBatches[batchMerkleRoot] = true;
+    }
+
+    function verifyBatchInclusion(
+        bytes32 proofCommitment,
+        bytes32 pubInputCommitment,
+        bytes32 provingSystemAuxDataCommitment,
+        bytes20 proofGeneratorAddr,
+        bytes32 batchMerkleRoot,
+        bytes memory merkleProof,
+        uint256 verificationDataBatchIndex,
+        address
+    ) external view returns (bool) {
+        if (!verifiedBatches[batchMerkleRoot]) {
+            return false;
+        }
+        bytes32 node = keccak256(
+            abi.encodePacked(
+                proofCommitment,
+                pubInputCommitment,
+                provingSystemAuxDataCommitment,
+                proofGeneratorAddr
+            )
+        );
+        uint256 index = verificationDataBatchIndex;
+        for (uint256 i = 0; i < merkleProof.length; i += 32) {
+            bytes32 sibling;
+            assembly {
+                sibling := mload(add(add(merkleProof, 32), i))
+            }
+            node = index % 2 == 1
+                ? keccak256(abi.encodePacked(sibling, node))
+                : keccak256(abi.encodePacked(node, sibling));
+            index /= 2;
+        }
+        return node == batchMerkleRoot;
+    }
+}
+
+contract AlignedVerifierTest is Test {
+    bytes32 constant STEP_PROGRAM_ID = keccak256("sp1-step");
+    bytes32 constant ROTATION_PROGRAM_ID = keccak256("sp1-rotation");
+    bytes20 constant PROOF_GENERATOR = bytes20(uint160(0xa11ce));
+
+    MockAlignedServiceManager service_manager;
+    AlignedLightClientVerifier lc_verifier;
+    StepProofFixture step_fixture;
+    RotationProofFixture rotation_fixture;
+
+    function setUp() public {
+        string memory root = vm.projectRoot();
+        step_fixture = FixtureLoader.parseStepFixture(
+            vm.readFile(string.concat(root, "/test/fixtures/step-groth16.json"))
+        );
+        rotation_fixture = FixtureLoader.parseRotationFixture(
+            vm.readFile(
+                string.concat(root, "/test/fixtures/rotation-groth16.json")
+            )
+        );
+        service_manager = new MockAlignedServiceManager();
+        lc_verifier = new AlignedLightClientVerifier(
+            address(service_manager),
+            address(0),
+            ROTATION_PROGRAM_ID,
+            STEP_PROGRAM_ID,
+            0x00,
+            step_fixture.commitment,
+            rotation_fixture.commitment,
+            0
+        );
+    }
+
+    function leaf(
+        bytes memory publicValues,
+        bytes32 programId
+    ) internal pure returns (bytes32) {
+        return
+            keccak256(
+                abi.encodePacked(
+                    keccak256("proof"),
+                    keccak256(publicValues),
+                    programId,
+                    PROOF_GENERATOR
+                )
+            );
+    }
+
+    function inclusion(
+        bytes32 batchMerkleRoot,
+        bytes memory merkleProof,
+        uint256 index
+    ) internal pure returns (AlignedInclusion memory) {
+        return
+            AlignedInclusion({
+                proofCommitment: keccak256("proof"),
+                proofGeneratorAddr: PROOF_GENERATOR,
+                batchMerkleRoot: batchMerkleRoot,
+                merkleProof: merkleProof,
+                verificationDataBatchIndex: index
+            });
+    }
+
+    function test_ValidStepInclusion() public {
+        bytes32 batchRoot = leaf(step_fixture.publicValues, STEP_PROGRAM_ID);
+        service_manager.markVerified(batchRoot);
+        lc_verifier.verifyStepInclusion(
+            step_fixture.publicValues,
+            inclusion(batchRoot, "", 0)
+        );
+        assertEq(lc_verifier.finalizedHeaderRoot(), step_fixture.root);
+    }
+
+    function test_ValidRotationInclusion() public {
+        bytes32 stepLeaf = leaf(step_fixture.publicValues, STEP_PROGRAM_ID);
+        bytes32 rotationLeaf = leaf(
+            rotation_fixture.publicValues,
+            ROTATION_PROGRAM_ID
+        );
+        bytes32 batchRoot = keccak256(abi.encodePacked(stepLeaf, rotationLeaf));
+        service_manager.markVerified(batchRoot);
+        lc_verifier.verifyRotationInclusion(
+            rotation_fixture.publicValues,
+            inclusion(batchRoot, abi.encodePacked(stepLeaf), 1)
+        );
+        assertEq(lc_verifier.activeSlot(), rotation_fixture.slot);
+        assertEq(
+            lc_verifier.nextCommitteeCommitment(),
+            rotation_fixture.next_commitment
+        );
+    }
+
+    function test_RevertWhen_BatchNotVerified() public {
+        bytes32 batchRoot = leaf(step_fixture.publicValues, STEP_PROGRAM_ID);
+        vm.expectRevert("proof is not in a verified batch");
+        lc_verifier.verifyStepInclusion(
+            step_fixture.publicValues,
+            inclusion(batchRoot, "", 0)
+        );
+    }
+
+    function test_RevertWhen_WrongProgram() public {
+        // a step proof cannot be applied as a rotation
+        bytes32 batchRoot = leaf(step_fixture.publicValues, STEP_PROGRAM_ID);
+        service_manager.markVerified(batchRoot);
+        vm.expectRevert("proof is not in a verified batch");
+        lc_verifier.verifyRotationInclusion(
+            step_fixture.publicValues,
+            inclusion(batchRoot, "", 0)
+        );
+    }
+
+    function test_RevertWhen_PublicValuesDiffer() public {
+        bytes32 batchRoot = leaf(step_fixture.publicValues, STEP_PROGRAM_ID);
+        service_manager.markVerified(batchRoot);
+        bytes memory publicValues = step_fixture.publicValues;
+        publicValues[0] = 0x01;
+        vm.expectRevert("proof is not in a verified batch");
+        lc_verifier.verifyStepInclusion(
+            publicValues,
+            inclusion(batchRoot, "", 0)
+        );
+    }
+}
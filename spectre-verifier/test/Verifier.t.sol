@@ -4,84 +4,115 @@ import {Test, console} from "forge-std/Test.sol";
 import {stdJson} from "forge-std/StdJson.sol";
 import {LightClientVerifier} from "../src/Verifier.sol";
 import {SP1Verifier} from "sp1-contracts/contracts/src/v4.0.0-rc.3/SP1VerifierGroth16.sol";
-import {FixtureLoader, RotationProofFixture, StepProofFixture} from "../src/Fixture.sol";
+import {ContractState, FixtureLoader, RotationProofFixture, StepProofFixture} from "../src/Fixture.sol";
 
 contract RotationTest is Test {
     using stdJson for string;
 
     address verifier;
-    LightClientVerifier public lc_verifier;
 
     function setUp() public {
+        // Deploy the actual SP1Verifier contract
+        verifier = address(new SP1Verifier());
+    }
+
+    function loadRotationFixture()
+        internal
+        view
+        returns (RotationProofFixture memory)
+    {
         string memory root = vm.projectRoot();
         string memory path = string.concat(
             root,
             "/test/fixtures/rotation-groth16.json"
         );
-        string memory json = vm.readFile(path);
-        RotationProofFixture memory rotation_fixture = FixtureLoader
-            .parseRotationFixture(json);
-        path = string.concat(root, "/test/fixtures/step-groth16.json");
-        json = vm.readFile(path);
-        StepProofFixture memory step_fixture = FixtureLoader.parseStepFixture(
-            json
-        );
-        // Deploy the actual SP1Verifier contract
-        verifier = address(new SP1Verifier());
-        // Deploy the LightClientVerifier using the deployed SP1Verifier
-        lc_verifier = new LightClientVerifier(
-            verifier,
-            rotation_fixture.vkey, // either a deterministic build (nix, docker), or derived from ELF
-            step_fixture.vkey,
-            0x00,
-            0x0fbe43bcd87569860753ec08e275400ec8d307259e618ca8319b03e714f06523, // see fixture payload
-            0x403a49cd58a653a01aa3e2031a5ec52cd8199d555b5ed67643aab6a42e40bcef,
-            6823935 // attested slot - 1 for testing!
-        );
+        return FixtureLoader.parseRotationFixture(vm.readFile(path));
     }
 
-    function test_ValidRotationProof() public {
+    function loadStepFixture()
+        internal
+        view
+        returns (StepProofFixture memory)
+    {
         string memory root = vm.projectRoot();
         string memory path = string.concat(
             root,
-            "/test/fixtures/rotation-groth16.json"
+            "/test/fixtures/step-groth16.json"
+        );
+        return FixtureLoader.parseStepFixture(vm.readFile(path));
+    }
+
+    /// Deploys the LightClientVerifier in the state the fixture is applied to.
+    function deploy(
+        bytes32 rotationVkey,
+        bytes32 stepVkey,
+        ContractState memory state
+    ) internal returns (LightClientVerifier) {
+        return
+            new LightClientVerifier(
+                verifier,
+                rotationVkey, // either a deterministic build (nix, docker), or derived from ELF
+                stepVkey,
+                state.finalizedHeaderRoot,
+                state.activeCommitteeCommitment,
+                state.nextCommitteeCommitment,
+                state.activeSlot
+            );
+    }
+
+    function assertState(
+        LightClientVerifier lc_verifier,
+        ContractState memory state
+    ) internal view {
+        assertEq(lc_verifier.finalizedHeaderRoot(), state.finalizedHeaderRoot);
+        assertEq(
+            lc_verifier.activeCommitteeCommitment(),
+            state.activeCommitteeCommitment
+        );
+        assertEq(
+            lc_verifier.nextCommitteeCommitment(),
+            state.nextCommitteeCommitment
+        );
+        assertEq(lc_verifier.activeSlot(), state.activeSlot);
+    }
+
+    function test_ValidRotationProof() public {
+        RotationProofFixture memory rotation_fixture = loadRotationFixture();
+        LightClientVerifier lc_verifier = deploy(
+            rotation_fixture.rotationVkey,
+            rotation_fixture.stepVkey,
+            rotation_fixture.preState
         );
-        string memory json = vm.readFile(path);
-        RotationProofFixture memory rotation_fixture = FixtureLoader
-            .parseRotationFixture(json);
 
         // Actual call to SP1Verifier
         lc_verifier.verifyRotationProof(
             rotation_fixture.publicValues,
             rotation_fixture.proof
         );
+        assertState(lc_verifier, rotation_fixture.postState);
     }
 
     function test_ValidStepProof() public {
-        string memory root = vm.projectRoot();
-        string memory path = string.concat(
-            root,
-            "/test/fixtures/step-groth16.json"
-        );
-        string memory json = vm.readFile(path);
-        StepProofFixture memory step_fixture = FixtureLoader.parseStepFixture(
-            json
+        StepProofFixture memory step_fixture = loadStepFixture();
+        LightClientVerifier lc_verifier = deploy(
+            step_fixture.rotationVkey,
+            step_fixture.stepVkey,
+            step_fixture.preState
         );
         lc_verifier.verifyStepProof(
             step_fixture.publicValues,
             step_fixture.proof
         );
+        assertState(lc_verifier, step_fixture.postState);
     }
 
     function testFail_InvalidRotationProof() external {
-        string memory root = vm.projectRoot();
-        string memory path = string.concat(
-            root,
-            "/test/fixtures/rotation-groth16.json"
+        RotationProofFixture memory rotation_fixture = loadRotationFixture();
+        LightClientVerifier lc_verifier = deploy(
+            rotation_fixture.rotationVkey,
+            rotation_fixture.stepVkey,
+            rotation_fixture.preState
         );
-        string memory json = vm.readFile(path);
-        RotationProofFixture memory rotation_fixture = FixtureLoader
-            .parseRotationFixture(json);
 
         // Generate a fake proof to simulate invalid proof behavior
         bytes memory fakeProof = new bytes(rotation_fixture.proof.length);